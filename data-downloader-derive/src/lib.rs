@@ -1,108 +1,309 @@
 //! This is documentation for the `data-downloader-derive` crate.
 //!
+//! Derives the three stages of the `structural::facade` example, each one
+//! configured by an attribute on the type:
+//!
+//! - `#[downloader(scheme = "file")]` restricts the accepted URL schemes,
+//!   `file` and `http` being supported; without it both are accepted.
+//! - `#[decoder(base64)]` or `#[decoder(hex)]` selects how the payload is
+//!   decoded; base64 is the default.
+//! - `#[deserializer(json, target = "Person")]` selects the format and the
+//!   type to deserialize; JSON into `Person` is the default.
+//!
+//! Misuse is reported with `compile_error!` instead of a panic. The stages
+//! below derive once the `facade` items they use are in scope:
+//!
+//! ```rust
+//! # #[macro_use]
+//! # extern crate data_downloader_derive;
+//! # mod facade {
+//! #     pub mod base64 { pub fn decode(data: Vec<u8>) -> Result<Vec<u8>, super::DecodeError> { Ok(data) } }
+//! #     pub mod hex { pub fn decode(data: Vec<u8>) -> Result<Vec<u8>, super::DecodeError> { Ok(data) } }
+//! #     #[derive(Debug)] pub struct DecodeError;
+//! #     impl From<::std::string::FromUtf8Error> for DecodeError {
+//! #         fn from(_: ::std::string::FromUtf8Error) -> DecodeError { DecodeError }
+//! #     }
+//! #     #[derive(Debug)] pub struct ParseError;
+//! # }
+//! # mod serde_json {
+//! #     pub fn from_str<T>(_: &str) -> Result<T, ::facade::ParseError> { Err(::facade::ParseError) }
+//! # }
+//! # trait DataDecoder { fn decode(&self, data: Vec<u8>) -> Result<String, facade::DecodeError>; }
+//! # trait DataDeserializer<T> { fn parse(&self, data: String) -> Result<T, facade::ParseError>; }
+//! # struct Person;
+//! #[derive(DataDecoder, DataDeserializer)]
+//! #[decoder(hex)]
+//! #[deserializer(json, target = "Person")]
+//! struct Facade;
+//! # fn main() {}
+//! ```
+//!
+//! but an unknown decoder is rejected with "unknown decoder `rot13`,
+//! expected `base64` or `hex`":
+//!
+//! ```compile_fail
+//! # #[macro_use]
+//! # extern crate data_downloader_derive;
+//! # mod facade {
+//! #     pub mod base64 { pub fn decode(data: Vec<u8>) -> Result<Vec<u8>, super::DecodeError> { Ok(data) } }
+//! #     pub mod hex { pub fn decode(data: Vec<u8>) -> Result<Vec<u8>, super::DecodeError> { Ok(data) } }
+//! #     #[derive(Debug)] pub struct DecodeError;
+//! #     impl From<::std::string::FromUtf8Error> for DecodeError {
+//! #         fn from(_: ::std::string::FromUtf8Error) -> DecodeError { DecodeError }
+//! #     }
+//! #     #[derive(Debug)] pub struct ParseError;
+//! # }
+//! # mod serde_json {
+//! #     pub fn from_str<T>(_: &str) -> Result<T, ::facade::ParseError> { Err(::facade::ParseError) }
+//! # }
+//! # trait DataDecoder { fn decode(&self, data: Vec<u8>) -> Result<String, facade::DecodeError>; }
+//! # trait DataDeserializer<T> { fn parse(&self, data: String) -> Result<T, facade::ParseError>; }
+//! # struct Person;
+//! #[derive(DataDecoder)]
+//! #[decoder(rot13)]
+//! struct Facade;
+//! # fn main() {}
+//! ```
+//!
+//! as is a decoder given as a value, with "expected `#[decoder(...)]`":
+//!
+//! ```compile_fail
+//! # #[macro_use]
+//! # extern crate data_downloader_derive;
+//! # mod facade {
+//! #     pub mod base64 { pub fn decode(data: Vec<u8>) -> Result<Vec<u8>, super::DecodeError> { Ok(data) } }
+//! #     pub mod hex { pub fn decode(data: Vec<u8>) -> Result<Vec<u8>, super::DecodeError> { Ok(data) } }
+//! #     #[derive(Debug)] pub struct DecodeError;
+//! #     impl From<::std::string::FromUtf8Error> for DecodeError {
+//! #         fn from(_: ::std::string::FromUtf8Error) -> DecodeError { DecodeError }
+//! #     }
+//! #     #[derive(Debug)] pub struct ParseError;
+//! # }
+//! # mod serde_json {
+//! #     pub fn from_str<T>(_: &str) -> Result<T, ::facade::ParseError> { Err(::facade::ParseError) }
+//! # }
+//! # trait DataDecoder { fn decode(&self, data: Vec<u8>) -> Result<String, facade::DecodeError>; }
+//! # trait DataDeserializer<T> { fn parse(&self, data: String) -> Result<T, facade::ParseError>; }
+//! # struct Person;
+//! #[derive(DataDecoder)]
+//! #[decoder = "hex"]
+//! struct Facade;
+//! # fn main() {}
+//! ```
+//!
+//! and a `target` without its type, with "`target` of `#[deserializer]`
+//! expects a type, such as `target = \"Person\"`":
+//!
+//! ```compile_fail
+//! # #[macro_use]
+//! # extern crate data_downloader_derive;
+//! # mod facade {
+//! #     pub mod base64 { pub fn decode(data: Vec<u8>) -> Result<Vec<u8>, super::DecodeError> { Ok(data) } }
+//! #     pub mod hex { pub fn decode(data: Vec<u8>) -> Result<Vec<u8>, super::DecodeError> { Ok(data) } }
+//! #     #[derive(Debug)] pub struct DecodeError;
+//! #     impl From<::std::string::FromUtf8Error> for DecodeError {
+//! #         fn from(_: ::std::string::FromUtf8Error) -> DecodeError { DecodeError }
+//! #     }
+//! #     #[derive(Debug)] pub struct ParseError;
+//! # }
+//! # mod serde_json {
+//! #     pub fn from_str<T>(_: &str) -> Result<T, ::facade::ParseError> { Err(::facade::ParseError) }
+//! # }
+//! # trait DataDecoder { fn decode(&self, data: Vec<u8>) -> Result<String, facade::DecodeError>; }
+//! # trait DataDeserializer<T> { fn parse(&self, data: String) -> Result<T, facade::ParseError>; }
+//! # struct Person;
+//! #[derive(DataDeserializer)]
+//! #[deserializer(json, target)]
+//! struct Facade;
+//! # fn main() {}
+//! ```
+//!
+//! The generated code refers to the `facade` module and the `serde_json`
+//! crate from the root of the crate using it.
 extern crate proc_macro;
 extern crate syn;
 #[macro_use]
 extern crate quote;
 
 use proc_macro::TokenStream;
+use syn::{Lit, MetaItem, NestedMetaItem};
 
 /// Macro to derive DataDownloader for facade example.
 ///
-#[proc_macro_derive(DataDownloader)]
+#[proc_macro_derive(DataDownloader, attributes(downloader))]
 pub fn download(input: TokenStream) -> TokenStream {
+    expand(input, impl_download)
+}
+
+/// Macro to derive DataDecoder for facade example.
+///
+#[proc_macro_derive(DataDecoder, attributes(decoder))]
+pub fn decode(input: TokenStream) -> TokenStream {
+    expand(input, impl_decode)
+}
+
+/// Macro to derive DataDeserializer for facade example.
+///
+#[proc_macro_derive(DataDeserializer, attributes(deserializer))]
+pub fn parse(input: TokenStream) -> TokenStream {
+    expand(input, impl_parse)
+}
+
+/// Parses the type definition and builds the impl, or a `compile_error!`.
+///
+fn expand(input: TokenStream, build: fn(&syn::DeriveInput) -> Result<quote::Tokens, String>) -> TokenStream {
     // Construct a string representation of the type definition
     let s = input.to_string();
-    
-    // Parse the string representation
-    let ast = syn::parse_derive_input(&s).unwrap();
 
-    // Build the impl
-    let gen = impl_download(&ast);
-    
+    // Parse the string representation and build the impl
+    let gen = syn::parse_derive_input(&s)
+        .and_then(|ast| build(&ast))
+        .unwrap_or_else(|message| compile_error(&message));
+
     // Return the generated impl
-    gen.parse().unwrap()
+    gen.parse().unwrap_or_else(|_| compile_error("generated an invalid impl").parse().unwrap())
 }
 
-/// Implementation of trait DataDownloader.
-/// 
-fn impl_download(ast: &syn::DeriveInput) -> quote::Tokens {
-    let name = &ast.ident;
+fn compile_error(message: &str) -> quote::Tokens {
     quote! {
-        impl DataDownloader for #name {
-            fn download(&self, url: String) -> String {
-                println!("Downloading from: {}", url);
-                String::from("ew0KICAgICJuYW1lIjogIkl2YW4iLA0KICAgICJhZ2UiOiAyNg0KfQ==")
-            }
-        }
+        compile_error!(#message);
     }
 }
 
-/// Macro to derive DataDecoder for facade example.
+/// Items of the `#[name(...)]` attributes; a bare `#[name]` or
+/// `#[name = ...]` is an error.
 ///
-#[proc_macro_derive(DataDecoder)]
-pub fn decode(input: TokenStream) -> TokenStream {
-    // Construct a string representation of the type definition
-    let s = input.to_string();
-    
-    // Parse the string representation
-    let ast = syn::parse_derive_input(&s).unwrap();
+fn attribute_items(ast: &syn::DeriveInput, name: &str) -> Result<Vec<NestedMetaItem>, String> {
+    let mut items = Vec::new();
+    for attr in ast.attrs.iter().filter(|attr| attr.name() == name) {
+        match attr.value {
+            MetaItem::List(_, ref nested) => items.extend(nested.iter().cloned()),
+            _ => return Err(format!("expected `#[{}(...)]`", name)),
+        }
+    }
+    Ok(items)
+}
 
-    // Build the impl
-    let gen = impl_decode(&ast);
-    
-    // Return the generated impl
-    gen.parse().unwrap()
+fn string_value(attribute: &str, key: &str, value: &Lit) -> Result<String, String> {
+    match *value {
+        Lit::Str(ref text, _) => Ok(text.clone()),
+        _ => Err(format!("`{}` of `#[{}]` expects a string", key, attribute)),
+    }
 }
 
-/// Implementation of trait DataDecoder.
-/// 
-fn impl_decode(ast: &syn::DeriveInput) -> quote::Tokens {
-    let name = &ast.ident;
-    quote! {
-        impl DataDecoder for #name {
-            fn decode(&self, data: String) -> String {
-                println!("Decoding from: {}", data);
-                String::from("{\n\t\"name\": \"Laurent Deleris\",\n\t\"age\": 50\n}")
+/// Implementation of trait DataDownloader.
+///
+fn impl_download(ast: &syn::DeriveInput) -> Result<quote::Tokens, String> {
+    let mut schemes = Vec::new();
+    for item in attribute_items(ast, "downloader")? {
+        match item {
+            NestedMetaItem::MetaItem(MetaItem::NameValue(ref key, ref value)) if key == "scheme" => {
+                let scheme = string_value("downloader", "scheme", value)?;
+                if scheme != "file" && scheme != "http" {
+                    return Err(format!("unsupported scheme `{}`, expected `file` or `http`", scheme));
+                }
+                schemes.push(scheme);
             }
+            _ => return Err(String::from("expected `#[downloader(scheme = \"...\")]`")),
         }
     }
+
+    let name = &ast.ident;
+    let (impl_generics, ty_generics, where_clause) = ast.generics.split_for_impl();
+    let check = if schemes.is_empty() {
+        quote! {}
+    } else {
+        let schemes = &schemes;
+        quote! {
+            let scheme = url.split("://").next().unwrap_or("");
+            if ![#(#schemes),*].contains(&scheme) || !url.contains("://") {
+                return Err(::facade::DownloadError::UnsupportedScheme(String::from(scheme)));
+            }
+        }
+    };
+    Ok(quote! {
+        impl #impl_generics DataDownloader for #name #ty_generics #where_clause {
+            fn download(&self, url: String) -> Result<Vec<u8>, ::facade::DownloadError> {
+                #check
+                ::facade::fetch(&url)
+            }
+        }
+    })
 }
 
-/// Macro to derive DataDeserializer for facade example.
+/// Implementation of trait DataDecoder.
 ///
-#[proc_macro_derive(DataDeserializer)]
-pub fn parse(input: TokenStream) -> TokenStream {
-    // Construct a string representation of the type definition
-    let s = input.to_string();
-    
-    // Parse the string representation
-    let ast = syn::parse_derive_input(&s).unwrap();
+fn impl_decode(ast: &syn::DeriveInput) -> Result<quote::Tokens, String> {
+    let mut decoders = Vec::new();
+    for item in attribute_items(ast, "decoder")? {
+        match item {
+            NestedMetaItem::MetaItem(MetaItem::Word(ref word)) if word == "base64" || word == "hex" => {
+                decoders.push(word.clone());
+            }
+            NestedMetaItem::MetaItem(MetaItem::Word(ref word)) => {
+                return Err(format!("unknown decoder `{}`, expected `base64` or `hex`", word));
+            }
+            _ => return Err(String::from("expected `#[decoder(base64)]` or `#[decoder(hex)]`")),
+        }
+    }
+    if decoders.len() > 1 {
+        return Err(String::from("expected a single decoder"));
+    }
+    let decoder = decoders.pop().unwrap_or_else(|| syn::Ident::from("base64"));
 
-    // Build the impl
-    let gen = impl_parse(&ast);
-    
-    // Return the generated impl
-    gen.parse().unwrap()
+    let name = &ast.ident;
+    let (impl_generics, ty_generics, where_clause) = ast.generics.split_for_impl();
+    Ok(quote! {
+        impl #impl_generics DataDecoder for #name #ty_generics #where_clause {
+            fn decode(&self, data: Vec<u8>) -> Result<String, ::facade::DecodeError> {
+                Ok(String::from_utf8(::facade::#decoder::decode(data)?)?)
+            }
+        }
+    })
 }
 
 /// Implementation of trait DataDeserializer.
-/// 
-fn impl_parse(ast: &syn::DeriveInput) -> quote::Tokens {
-    let name = &ast.ident;
-    quote! {
-        impl DataDeserializer for #name {
-            fn parse(&self, data: String) -> Option<Person> {
-                println!("Parsing from: {}", data);
-                Some(
-                    Person {
-                    name: Some(String::from("Laurent Deleris")),
-                    age: Some(50),
-                    }
-                )            
+///
+fn impl_parse(ast: &syn::DeriveInput) -> Result<quote::Tokens, String> {
+    let mut formats = Vec::new();
+    let mut target = None;
+    for item in attribute_items(ast, "deserializer")? {
+        match item {
+            NestedMetaItem::MetaItem(MetaItem::Word(ref word)) if word == "json" => formats.push(word.clone()),
+            NestedMetaItem::MetaItem(MetaItem::Word(ref word)) if word == "target" => {
+                return Err(String::from("`target` of `#[deserializer]` expects a type, such as `target = \"Person\"`"));
+            }
+            NestedMetaItem::MetaItem(MetaItem::Word(ref word)) => {
+                return Err(format!("unknown format `{}`, expected `json`", word));
             }
+            NestedMetaItem::MetaItem(MetaItem::NameValue(ref key, ref value)) if key == "target" => {
+                if target.is_some() {
+                    return Err(String::from("`target` is given twice"));
+                }
+                let text = string_value("deserializer", "target", value)?;
+                let ty = syn::parse_type(&text).map_err(|_| format!("invalid target type `{}`", text))?;
+                target = Some(ty);
+            }
+            _ => return Err(String::from("expected `#[deserializer(json, target = \"...\")]`")),
         }
     }
+    if formats.len() > 1 {
+        return Err(String::from("expected a single format"));
+    }
+    let target = match target {
+        Some(target) => target,
+        None => syn::parse_type("Person")?,
+    };
+
+    let name = &ast.ident;
+    let (impl_generics, ty_generics, where_clause) = ast.generics.split_for_impl();
+    Ok(quote! {
+        impl #impl_generics DataDeserializer<#target> for #name #ty_generics #where_clause {
+            fn parse(&self, data: String) -> Result<#target, ::facade::ParseError> {
+                Ok(::serde_json::from_str(&data)?)
+            }
+        }
+    })
 }
 
 
@@ -0,0 +1,63 @@
+//! Compares streaming an `InputReader` chain line by line with
+//! materializing the whole input through `read_lines`.
+//!
+//! The input file is generated in the temporary directory. Its size in
+//! megabytes can be set with `INPUT_READERS_BENCH_MB` (default: 300).
+//!
+//! run: cargo bench -p structural --bench input_readers
+
+extern crate structural;
+
+use std::env;
+use std::fs::{self, File};
+use std::io::{BufWriter, Write};
+use std::path::Path;
+use std::time::Instant;
+
+use structural::decorator::input_readers::*;
+
+fn generate(path: &Path, megabytes: usize) {
+  let mut out = BufWriter::new(File::create(path).unwrap());
+  let target = megabytes * 1024 * 1024;
+  let mut written = 0;
+  let mut i = 0u64;
+  while written < target {
+    let line = format!("{:>10} this is a data file which contains lines manipulated by the reader\n", i);
+    out.write_all(line.as_bytes()).unwrap();
+    written += line.len();
+    i += 1;
+  }
+  out.flush().unwrap();
+}
+
+fn chain(path: &str) -> Box<dyn InputReader> {
+  LenghtInputReader::new(CapitalizedInputReader::new(AdvancedInputReader::new(path).unwrap()))
+}
+
+fn main() {
+  let megabytes = env::var("INPUT_READERS_BENCH_MB").ok()
+    .and_then(|mb| mb.parse().ok())
+    .unwrap_or(300);
+  let path = env::temp_dir().join("structural-input-readers-bench.txt");
+  println!("Generating {} MB in {}", megabytes, path.display());
+  generate(&path, megabytes);
+  let path_str = path.to_str().unwrap();
+
+  let start = Instant::now();
+  let mut reader = chain(path_str);
+  let (mut lines, mut bytes) = (0usize, 0usize);
+  for line in reader.lines() {
+    lines += 1;
+    bytes += line.unwrap().len();
+  }
+  println!("streaming:     {} lines, {} bytes in {:?}", lines, bytes, start.elapsed());
+
+  let start = Instant::now();
+  let mut reader = chain(path_str);
+  let all = reader.read_lines().unwrap();
+  let bytes: usize = all.iter().map(|line| line.len()).sum();
+  println!("materialized:  {} lines, {} bytes in {:?} (all held in memory)", all.len(), bytes, start.elapsed());
+
+  drop(all);
+  let _ = fs::remove_file(&path);
+}
@@ -0,0 +1,125 @@
+//! Compares the `GridIndex` of a `Graphic` with a linear scan of its
+//! circles, for point, rectangle and nearest-neighbour queries, then times
+//! the grid on a sparse scene and on a scene with one large circle.
+//!
+//! The circles are spread pseudo-randomly over a square scene. Their number
+//! can be set with `SPATIAL_INDEX_BENCH_CIRCLES` (default: 200000).
+//!
+//! run: cargo bench -p structural --bench spatial_index
+
+extern crate structural;
+
+use std::env;
+use std::time::Instant;
+
+use structural::flyweight::*;
+
+const QUERIES: usize = 1000;
+
+/// A linear congruential generator, enough to spread circles.
+struct Lcg(u64);
+
+impl Lcg {
+  fn next(&mut self) -> f32 {
+    self.0 = self.0.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+    (self.0 >> 40) as f32 / (1u64 << 24) as f32
+  }
+}
+
+fn circles(graphic: &Graphic) -> Vec<(ItemId, f32, f32, f32)> {
+  graphic.items.iter().filter_map(|item| match item.shape {
+    Shape::Circle { x, y, radius, .. } => Some((item.id, x, y, radius)),
+    _ => None,
+  }).collect()
+}
+
+fn linear_at_point(circles: &[(ItemId, f32, f32, f32)], x: f32, y: f32) -> Vec<ItemId> {
+  circles.iter()
+    .filter(|&&(_, cx, cy, r)| (x - cx).hypot(y - cy) <= r)
+    .map(|&(id, ..)| id)
+    .collect()
+}
+
+fn linear_in_rect(circles: &[(ItemId, f32, f32, f32)], x0: f32, y0: f32, x1: f32, y1: f32) -> Vec<ItemId> {
+  circles.iter()
+    .filter(|&&(_, cx, cy, r)| (cx.max(x0).min(x1) - cx).hypot(cy.max(y0).min(y1) - cy) <= r)
+    .map(|&(id, ..)| id)
+    .collect()
+}
+
+fn linear_nearest(circles: &[(ItemId, f32, f32, f32)], x: f32, y: f32) -> Option<ItemId> {
+  circles.iter()
+    .map(|&(id, cx, cy, r)| (id, ((x - cx).hypot(y - cy) - r).max(0.0)))
+    .fold(None, |best: Option<(ItemId, f32)>, (id, d)| match best {
+      Some((_, best_d)) if best_d <= d => best,
+      _ => Some((id, d)),
+    })
+    .map(|(id, _)| id)
+}
+
+fn main() {
+  let count: usize = env::var("SPATIAL_INDEX_BENCH_CIRCLES").ok()
+    .and_then(|n| n.parse().ok())
+    .unwrap_or(200_000);
+  let side = (count as f32).sqrt() * 4.0;
+  let mut random = Lcg(42);
+  let mut graphic = Graphic::new();
+  let mut factory = CircleFactory::new();
+  let colors = [Color::Red, Color::Green, Color::Blue, Color::Yellow, Color::Magenta];
+  for i in 0..count {
+    let (x, y) = ((random.next() * side) as i32, (random.next() * side) as i32);
+    let circle = factory.make_circle(colors[i % colors.len()]);
+    graphic.add_circle(x, y, 0.5 + random.next() * 2.0, circle);
+  }
+  let points: Vec<(f32, f32)> = (0..QUERIES).map(|_| (random.next() * side, random.next() * side)).collect();
+  println!("{} circles over {:.0} x {:.0}, {} queries of each kind", count, side, side, QUERIES);
+
+  let start = Instant::now();
+  let index = graphic.circle_index(4.0);
+  println!("grid built in {:?}", start.elapsed());
+  let circles = circles(&graphic);
+
+  let start = Instant::now();
+  let hits: usize = points.iter().map(|&(x, y)| linear_at_point(&circles, x, y).len()).sum();
+  let linear = start.elapsed();
+  let start = Instant::now();
+  let grid_hits: usize = points.iter().map(|&(x, y)| index.at_point(x, y).len()).sum();
+  println!("point:     linear {:>12?}  grid {:>12?}  ({} hits)", linear, start.elapsed(), hits);
+  assert_eq!(hits, grid_hits);
+
+  let start = Instant::now();
+  let hits: usize = points.iter().map(|&(x, y)| linear_in_rect(&circles, x, y, x + 20.0, y + 20.0).len()).sum();
+  let linear = start.elapsed();
+  let start = Instant::now();
+  let grid_hits: usize = points.iter().map(|&(x, y)| index.in_rect(x, y, x + 20.0, y + 20.0).len()).sum();
+  println!("rectangle: linear {:>12?}  grid {:>12?}  ({} hits)", linear, start.elapsed(), hits);
+  assert_eq!(hits, grid_hits);
+
+  let start = Instant::now();
+  let nearest: Vec<Option<ItemId>> = points.iter().map(|&(x, y)| linear_nearest(&circles, x, y)).collect();
+  let linear = start.elapsed();
+  let start = Instant::now();
+  let grid_nearest: Vec<Option<ItemId>> = points.iter().map(|&(x, y)| index.nearest(x, y).map(|(id, _)| id)).collect();
+  println!("nearest:   linear {:>12?}  grid {:>12?}", linear, start.elapsed());
+  assert_eq!(nearest, grid_nearest);
+
+  // Two circles far apart, on cells much smaller than the scene.
+  let mut sparse = Graphic::new();
+  let a = sparse.add_circle(0, 0, 1.0, factory.make_circle(Color::Red));
+  let b = sparse.add_circle(30000, 30000, 1.0, factory.make_circle(Color::Blue));
+  let start = Instant::now();
+  let index = sparse.circle_index(1.0);
+  let hits = index.in_rect(-1.0, -1.0, 30001.0, 30001.0);
+  println!("sparse:    grid {:>12?}  (scene-wide rectangle)", start.elapsed());
+  assert_eq!(hits, vec![a, b]);
+
+  // One circle covering millions of cells, among the others.
+  let large = graphic.add_circle(0, 0, 3000.0, factory.make_circle(Color::Green));
+  let start = Instant::now();
+  let index = graphic.circle_index(4.0);
+  let grid_hits: usize = points.iter().map(|&(x, y)| index.at_point(x, y).len()).sum();
+  println!("large:     grid {:>12?}  (build and point queries)", start.elapsed());
+  let mut circles = circles;
+  circles.push((large, 0.0, 0.0, 3000.0));
+  assert_eq!(grid_hits, points.iter().map(|&(x, y)| linear_at_point(&circles, x, y).len()).sum::<usize>());
+}
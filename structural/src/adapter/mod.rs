@@ -12,6 +12,9 @@
 //! logger.log_warning(String::from("About to finish."));
 //! logger.log_info(String::from("Bye!"));
 //! ```
+//!
+//! The `closures` and `io` modules, together with the `adapter!` macro,
+//! provide reusable adapters for other mismatched interfaces.
 
 pub trait Log {
   fn log_info(&self, message: String);
@@ -53,4 +56,331 @@ mod logger {
       println!("{}: {}", severity.to_uppercase(), message);
     }
   }
-}
\ No newline at end of file
+}
+
+/// Severity levels understood by the `Log` trait.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+  Info,
+  Debug,
+  Warning,
+  Error,
+}
+
+impl Severity {
+  /// Dispatches `message` to the `Log` method matching this severity.
+  pub fn log(&self, log: &dyn Log, message: String) {
+    match *self {
+      Severity::Info => log.log_info(message),
+      Severity::Debug => log.log_debug(message),
+      Severity::Warning => log.log_warning(message),
+      Severity::Error => log.log_error(message),
+    }
+  }
+}
+
+pub mod closures {
+  //! `closures` module.
+  //!
+  //! Adapters turning plain closures into implementations of our traits.
+  //!
+  //! # Example
+  //!
+  //! ``` rust
+  //! use structural::adapter::*;
+  //! use structural::adapter::closures::*;
+  //! use structural::decorator::input_readers::*;
+  //!
+  //! let logger = LogFn::new(|severity: Severity, message: String| {
+  //!   println!("[{:?}] {}", severity, message);
+  //! });
+  //! logger.log_warning(String::from("Adapted from a closure."));
+  //!
+  //! let mut count = 0;
+  //! let mut reader = InputReaderFn::new(move || {
+  //!   count += 1;
+  //!   if count <= 2 { Some(Ok(format!("line {}", count))) } else { None }
+  //! });
+  //! assert_eq!(reader.read_lines().unwrap(), vec!["line 1", "line 2"]);
+  //! ```
+
+  use std::io;
+  use adapter::{Log, Severity};
+  use decorator::input_readers::InputReader;
+
+  /// Adapts a `Fn(Severity, String)` closure to the `Log` trait.
+  pub struct LogFn<F> {
+    f: F,
+  }
+
+  impl<F: Fn(Severity, String)> LogFn<F> {
+    pub fn new(f: F) -> LogFn<F> {
+      LogFn { f }
+    }
+  }
+
+  impl<F: Fn(Severity, String)> Log for LogFn<F> {
+    fn log_info(&self, message: String) { (self.f)(Severity::Info, message) }
+    fn log_debug(&self, message: String) { (self.f)(Severity::Debug, message) }
+    fn log_warning(&self, message: String) { (self.f)(Severity::Warning, message) }
+    fn log_error(&self, message: String) { (self.f)(Severity::Error, message) }
+  }
+
+  /// Adapts a closure producing one line per call, `None` at the end of the
+  /// input, to the `InputReader` trait.
+  pub struct InputReaderFn<F> {
+    f: F,
+  }
+
+  impl<F: FnMut() -> Option<io::Result<String>>> InputReaderFn<F> {
+    pub fn new(f: F) -> InputReaderFn<F> {
+      InputReaderFn { f }
+    }
+  }
+
+  impl<F: FnMut() -> Option<io::Result<String>>> InputReader for InputReaderFn<F> {
+    fn next_line(&mut self) -> Option<io::Result<String>> {
+      (self.f)()
+    }
+  }
+}
+
+pub mod io {
+  //! `io` module.
+  //!
+  //! Adapters exposing our traits through the standard `Read`, `Write`
+  //! and `Iterator` traits, and the other way around.
+  //!
+  //! # Example
+  //!
+  //! ``` rust
+  //! use std::io::{Read, Write};
+  //! use structural::adapter::*;
+  //! use structural::adapter::closures::*;
+  //! use structural::adapter::io::*;
+  //! use structural::decorator::input_readers::*;
+  //!
+  //! let reader = IteratorInputReader::new(vec![String::from("one"), String::from("two")]);
+  //! let reader = CapitalizedInputReader::new(Box::new(reader));
+  //! let lines: Vec<String> = InputReaderIter::new(reader).collect::<Result<_, _>>().unwrap();
+  //! assert_eq!(lines, vec!["ONE", "TWO"]);
+  //!
+  //! let reader = IteratorInputReader::new(vec![String::from("one"), String::from("two")]);
+  //! let mut text = String::new();
+  //! InputReaderRead::new(Box::new(reader)).read_to_string(&mut text).unwrap();
+  //! assert_eq!(text, "one\ntwo\n");
+  //!
+  //! let logger = LogFn::new(|severity: Severity, message: String| {
+  //!   assert_eq!(severity, Severity::Warning);
+  //!   assert_eq!(message, "disk almost full");
+  //! });
+  //! let mut writer = LogWriter::new(logger, Severity::Warning);
+  //! writeln!(writer, "disk almost full").unwrap();
+  //! ```
+
+  use std::io;
+  use adapter::{Log, Severity};
+  use decorator::input_readers::InputReader;
+
+  /// Owning iterator over the lines of an `InputReader`.
+  pub struct InputReaderIter {
+    input_reader: Box<dyn InputReader>,
+  }
+
+  impl InputReaderIter {
+    pub fn new(input_reader: Box<dyn InputReader>) -> InputReaderIter {
+      InputReaderIter { input_reader }
+    }
+  }
+
+  impl Iterator for InputReaderIter {
+    type Item = io::Result<String>;
+
+    fn next(&mut self) -> Option<io::Result<String>> {
+      self.input_reader.next_line()
+    }
+  }
+
+  /// Exposes an `InputReader` as a `Read`, each line terminated by `\n`.
+  pub struct InputReaderRead {
+    lines: InputReaderIter,
+    pending: Vec<u8>,
+    position: usize,
+  }
+
+  impl InputReaderRead {
+    pub fn new(input_reader: Box<dyn InputReader>) -> InputReaderRead {
+      InputReaderRead {
+        lines: InputReaderIter::new(input_reader),
+        pending: Vec::new(),
+        position: 0,
+      }
+    }
+  }
+
+  impl io::Read for InputReaderRead {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+      while self.position == self.pending.len() {
+        match self.lines.next() {
+          Some(Err(e)) => return Err(e),
+          Some(Ok(line)) => {
+            self.pending = line.into_bytes();
+            self.pending.push(b'\n');
+            self.position = 0;
+          }
+          None => return Ok(0),
+        }
+      }
+      let n = buf.len().min(self.pending.len() - self.position);
+      buf[..n].copy_from_slice(&self.pending[self.position..self.position + n]);
+      self.position += n;
+      Ok(n)
+    }
+  }
+
+  /// Exposes a `Log` as a `Write`: every complete line is logged with a
+  /// fixed severity, a trailing partial line is logged on `flush`.
+  pub struct LogWriter<L: Log> {
+    log: L,
+    severity: Severity,
+    buffer: Vec<u8>,
+  }
+
+  impl<L: Log> LogWriter<L> {
+    pub fn new(log: L, severity: Severity) -> LogWriter<L> {
+      LogWriter {
+        log,
+        severity,
+        buffer: Vec::new(),
+      }
+    }
+
+    fn emit(&mut self, line: Vec<u8>) {
+      let message = String::from_utf8_lossy(&line).trim_end_matches('\r').to_string();
+      self.severity.log(&self.log, message);
+    }
+  }
+
+  impl<L: Log> io::Write for LogWriter<L> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+      self.buffer.extend_from_slice(buf);
+      while let Some(end) = self.buffer.iter().position(|b| *b == b'\n') {
+        let line: Vec<u8> = self.buffer.drain(..=end).take(end).collect();
+        self.emit(line);
+      }
+      Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+      if !self.buffer.is_empty() {
+        let line = self.buffer.split_off(0);
+        self.emit(line);
+      }
+      Ok(())
+    }
+  }
+
+  impl<L: Log> Drop for LogWriter<L> {
+    fn drop(&mut self) {
+      let _ = io::Write::flush(self);
+    }
+  }
+
+  /// Adapts any iterator of lines to the `InputReader` trait.
+  pub struct IteratorInputReader<I> {
+    lines: I,
+  }
+
+  impl<I: Iterator<Item = String>> IteratorInputReader<I> {
+    pub fn new<T: IntoIterator<Item = String, IntoIter = I>>(lines: T) -> IteratorInputReader<I> {
+      IteratorInputReader { lines: lines.into_iter() }
+    }
+  }
+
+  impl<I: Iterator<Item = String>> InputReader for IteratorInputReader<I> {
+    fn next_line(&mut self) -> Option<io::Result<String>> {
+      self.lines.next().map(Ok)
+    }
+  }
+}
+
+/// Generates an adapter struct implementing a trait by delegating each
+/// method to a method of the wrapped adaptee.
+///
+/// Every mapping `fn method(args) => target(exprs);` becomes
+/// `self.adaptee.target(exprs)`. Both `&self` and `&mut self` receivers are
+/// accepted.
+///
+/// # Example
+///
+/// ``` rust
+/// #[macro_use]
+/// extern crate structural;
+///
+/// use structural::adapter::Log;
+///
+/// pub struct ThirdPartyLogger;
+/// impl ThirdPartyLogger {
+///   pub fn write(&self, level: u8, text: &str) {
+///     println!("{} {}", level, text);
+///   }
+/// }
+///
+/// adapter! {
+///   /// `Log` on top of `ThirdPartyLogger`.
+///   pub struct ThirdPartyLog(ThirdPartyLogger): Log {
+///     fn log_info(&self, message: String) => write(0, &message);
+///     fn log_debug(&self, message: String) => write(1, &message);
+///     fn log_warning(&self, message: String) => write(2, &message);
+///     fn log_error(&self, message: String) => write(3, &message);
+///   }
+/// }
+///
+/// fn main() {
+///   let logger = ThirdPartyLog::new(ThirdPartyLogger);
+///   logger.log_info(String::from("Generated adapter."));
+/// }
+/// ```
+#[macro_export]
+macro_rules! adapter {
+  (
+    $(#[$meta:meta])*
+    $vis:vis struct $name:ident($adaptee:ty): $trait_:path {
+      $($methods:tt)*
+    }
+  ) => {
+    $(#[$meta])*
+    $vis struct $name {
+      adaptee: $adaptee,
+    }
+
+    impl $name {
+      $vis fn new(adaptee: $adaptee) -> $name {
+        $name { adaptee }
+      }
+    }
+
+    impl $trait_ for $name {
+      $crate::adapter!(@methods $($methods)*);
+    }
+  };
+  (@methods) => {};
+  (@methods
+    fn $method:ident(&self $(, $arg:ident: $arg_ty:ty)*) $(-> $ret:ty)* => $target:ident($($expr:expr),*);
+    $($rest:tt)*
+  ) => {
+    fn $method(&self $(, $arg: $arg_ty)*) $(-> $ret)* {
+      self.adaptee.$target($($expr),*)
+    }
+    $crate::adapter!(@methods $($rest)*);
+  };
+  (@methods
+    fn $method:ident(&mut self $(, $arg:ident: $arg_ty:ty)*) $(-> $ret:ty)* => $target:ident($($expr:expr),*);
+    $($rest:tt)*
+  ) => {
+    fn $method(&mut self $(, $arg: $arg_ty)*) $(-> $ret)* {
+      self.adaptee.$target($($expr),*)
+    }
+    $crate::adapter!(@methods $($rest)*);
+  };
+}
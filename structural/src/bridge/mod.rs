@@ -1,99 +1,705 @@
 //! `bridge` module.
 //!
+//! Keys (the implementation side) authenticate against a `CredentialStore`
+//! and return an `AccessGranted` or an `AccessDenied`; doors (the
+//! abstraction side) only rely on the `Key` trait.
+//!
 //! # Example
-//! 
+//!
+//! ``` rust
+//! use std::time::{Duration, SystemTime};
+//! use structural::bridge::access::*;
+//! use structural::bridge::door::*;
+//! use structural::bridge::key::*;
+//!
+//! let mut store = CredentialStore::new();
+//! store.fit_key("front door", HouseDoorKey::CUT);
+//! store.fit_key("garage", CarDoorKey::CUT);
+//! store.set_pin("garage", "1234");
+//! store.allow_badge(42, "front door");
+//! store.issue_token("a1b2c3", "front door", SystemTime::now() + Duration::from_secs(3600));
+//! store.add_master_key("open sesame");
+//!
+//! let mut house = HouseOneDoor::new("front door", Box::new(HouseDoorKey));
+//! println!("{}", house.enter(&store).unwrap());
+//! println!("{}", house.leave(&store).unwrap());
+//! assert_eq!(house.state(), DoorState::Locked);
+//! let mut garage = CarOneDoor::new("garage", Box::new(CarDoorKey));
+//! println!("{}", garage.enter(&store).unwrap());
+//! println!("{}", garage.leave(&store).unwrap());
+//!
+//! let now = SystemTime::now();
+//! assert!(PinCode::new("1234").authenticate("garage", &store, now).is_ok());
+//! assert_eq!(PinCode::new("0000").authenticate("garage", &store, now).unwrap_err().reason, DenyReason::WrongPin);
+//! assert!(RfidBadge::new(42).authenticate("front door", &store, now).is_ok());
+//! assert_eq!(RfidBadge::new(42).authenticate("garage", &store, now).unwrap_err().reason, DenyReason::NotAllowed);
+//! let token = TimeLimitedToken::new("a1b2c3");
+//! assert!(token.authenticate("front door", &store, now).is_ok());
+//! let tomorrow = now + Duration::from_secs(86400);
+//! assert_eq!(token.authenticate("front door", &store, tomorrow).unwrap_err().reason, DenyReason::Expired);
+//! assert!(MasterKey::new("open sesame").authenticate("cellar", &store, now).is_ok());
+//! assert!(HouseDoorKey.authenticate("garage", &store, now).is_err());
+//! ```
+//!
+//! A building gives each door its own key and records every attempt:
+//!
 //! ``` rust
+//! use structural::bridge::access::*;
+//! use structural::bridge::door::*;
 //! use structural::bridge::key::*;
-//! 
-//! let house = HouseOneDoor::new(Box::new(HouseDoorKey));
-//! println!("{}", house.enter());
-//! println!("{}", house.leave());
-//! let garage = CarOneDoor::new(Box::new(CarDoorKey));
-//! println!("{}", garage.enter());
-//! println!("{}", garage.leave());   
+//!
+//! let mut store = CredentialStore::new();
+//! store.set_pin("lobby", "2468");
+//! store.allow_badge(7, "office");
+//!
+//! let mut building = Building::new("HQ");
+//! building.add_door("lobby", Box::new(PinCode::new("2468")));
+//! building.add_door("office", Box::new(RfidBadge::new(7)));
+//! building.add_door("vault", Box::new(RfidBadge::new(7)));
+//!
+//! building.open("lobby", &store).unwrap();
+//! building.lock("office", &store).unwrap();
+//! match building.open("office", &store) {
+//!   Err(DoorError::IllegalTransition { from, .. }) => assert_eq!(from, DoorState::Locked),
+//!   _ => unreachable!(),
+//! }
+//! building.unlock("office", &store).unwrap();
+//! assert!(building.open("vault", &store).is_err());
+//! assert!(building.open("roof", &store).is_err());
+//!
+//! assert_eq!(building.state("lobby"), Some(DoorState::Open));
+//! assert_eq!(building.state("office"), Some(DoorState::Closed));
+//! assert_eq!(building.audit().len(), 6);
+//! assert_eq!(building.audit()[4].outcome, AuditOutcome::Denied(DenyReason::NotAllowed));
+//! assert_eq!(building.audit()[5].outcome, AuditOutcome::UnknownDoor);
+//! assert_eq!(building.audit()[5].by, None);
+//! assert_eq!(building.audit_of("office").count(), 3);
+//! for entry in building.audit() {
+//!   println!("{}", entry);
+//! }
 //! ```
- 
+
+pub mod access {
+  //! `access` module.
+  //!
+  //! Credentials registered per door, and the outcome of an authentication.
+
+  use std::collections::{HashMap, HashSet};
+  use std::error::Error;
+  use std::fmt;
+  use std::time::SystemTime;
+
+  /// Proof that a key was accepted by a door.
+  #[derive(Debug, Clone, PartialEq)]
+  pub struct AccessGranted {
+    pub door: String,
+    /// Description of the key, e.g. `RFID badge 42`.
+    pub by: String,
+    pub at: SystemTime,
+  }
+
+  /// Why a key was refused.
+  #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+  pub enum DenyReason {
+    /// The store knows nothing about the door.
+    UnknownDoor,
+    /// The mechanical key does not fit the lock.
+    WrongKey,
+    WrongPin,
+    /// The badge, token or master key is not registered.
+    UnknownCredential,
+    /// The credential is valid, but not for this door.
+    NotAllowed,
+    Expired,
+  }
+
+  impl fmt::Display for DenyReason {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+      let text = match *self {
+        DenyReason::UnknownDoor => "unknown door",
+        DenyReason::WrongKey => "the key does not fit",
+        DenyReason::WrongPin => "wrong PIN code",
+        DenyReason::UnknownCredential => "unknown credential",
+        DenyReason::NotAllowed => "not allowed on this door",
+        DenyReason::Expired => "expired",
+      };
+      write!(f, "{}", text)
+    }
+  }
+
+  /// Refusal of a key by a door.
+  #[derive(Debug, Clone, PartialEq)]
+  pub struct AccessDenied {
+    pub door: String,
+    pub by: String,
+    pub at: SystemTime,
+    pub reason: DenyReason,
+  }
+
+  impl fmt::Display for AccessDenied {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+      write!(f, "{} denied on {}: {}", self.by, self.door, self.reason)
+    }
+  }
+
+  impl Error for AccessDenied {}
+
+  /// Credentials accepted by each door.
+  #[derive(Debug, Default)]
+  pub struct CredentialStore {
+    cuts: HashMap<String, HashSet<String>>,
+    pins: HashMap<String, String>,
+    badges: HashMap<u64, HashSet<String>>,
+    tokens: HashMap<String, (String, SystemTime)>,
+    master_keys: HashSet<String>,
+  }
+
+  impl CredentialStore {
+    pub fn new() -> CredentialStore {
+      CredentialStore::default()
+    }
+
+    /// Lets the mechanical keys with `cut` open `door`.
+    pub fn fit_key(&mut self, door: &str, cut: &str) {
+      self.cuts.entry(String::from(door)).or_default().insert(String::from(cut));
+    }
+
+    pub fn set_pin(&mut self, door: &str, pin: &str) {
+      self.pins.insert(String::from(door), String::from(pin));
+    }
+
+    pub fn allow_badge(&mut self, badge: u64, door: &str) {
+      self.badges.entry(badge).or_default().insert(String::from(door));
+    }
+
+    pub fn revoke_badge(&mut self, badge: u64) {
+      self.badges.remove(&badge);
+    }
+
+    /// Issues a token opening `door` until `valid_until` (excluded).
+    pub fn issue_token(&mut self, token: &str, door: &str, valid_until: SystemTime) {
+      self.tokens.insert(String::from(token), (String::from(door), valid_until));
+    }
+
+    /// Registers a master key, opening any door.
+    pub fn add_master_key(&mut self, secret: &str) {
+      self.master_keys.insert(String::from(secret));
+    }
+
+    /// Tells if `door` has any credential registered.
+    pub fn knows_door(&self, door: &str) -> bool {
+      self.cuts.contains_key(door)
+        || self.pins.contains_key(door)
+        || self.badges.values().any(|doors| doors.contains(door))
+        || self.tokens.values().any(|(d, _)| d == door)
+    }
+
+    pub fn check_cut(&self, door: &str, cut: &str) -> Result<(), DenyReason> {
+      match self.cuts.get(door) {
+        Some(cuts) if cuts.contains(cut) => Ok(()),
+        _ if !self.knows_door(door) => Err(DenyReason::UnknownDoor),
+        _ => Err(DenyReason::WrongKey),
+      }
+    }
+
+    pub fn check_pin(&self, door: &str, pin: &str) -> Result<(), DenyReason> {
+      match self.pins.get(door) {
+        Some(expected) if expected == pin => Ok(()),
+        Some(_) => Err(DenyReason::WrongPin),
+        None if !self.knows_door(door) => Err(DenyReason::UnknownDoor),
+        None => Err(DenyReason::NotAllowed),
+      }
+    }
+
+    pub fn check_badge(&self, door: &str, badge: u64) -> Result<(), DenyReason> {
+      match self.badges.get(&badge) {
+        Some(doors) if doors.contains(door) => Ok(()),
+        Some(_) => Err(DenyReason::NotAllowed),
+        None => Err(DenyReason::UnknownCredential),
+      }
+    }
+
+    pub fn check_token(&self, door: &str, token: &str, at: SystemTime) -> Result<(), DenyReason> {
+      match self.tokens.get(token) {
+        Some((d, _)) if d != door => Err(DenyReason::NotAllowed),
+        Some(&(_, valid_until)) if at >= valid_until => Err(DenyReason::Expired),
+        Some(_) => Ok(()),
+        None => Err(DenyReason::UnknownCredential),
+      }
+    }
+
+    pub fn check_master_key(&self, secret: &str) -> Result<(), DenyReason> {
+      if self.master_keys.contains(secret) {
+        Ok(())
+      } else {
+        Err(DenyReason::UnknownCredential)
+      }
+    }
+  }
+}
+
 pub mod key {
   //! `key` module.
-  //! 
-  
+  //!
+
+  use std::time::SystemTime;
+  use bridge::access::{AccessDenied, AccessGranted, CredentialStore, DenyReason};
+
   pub trait Key {
-    fn open(&self) -> String;
-    fn close(&self) -> String;
+    /// Description of the key, recorded in grants and denials. It never
+    /// reveals a secret.
+    fn describe(&self) -> String;
+
+    /// Checks the key against the credentials of `door` at time `at`.
+    fn authenticate(&self, door: &str, store: &CredentialStore, at: SystemTime) -> Result<AccessGranted, AccessDenied>;
+  }
+
+  /// Turns the result of a credential check into a grant or a denial.
+  fn outcome(key: &dyn Key, door: &str, at: SystemTime, check: Result<(), DenyReason>) -> Result<AccessGranted, AccessDenied> {
+    match check {
+      Ok(()) => Ok(AccessGranted { door: String::from(door), by: key.describe(), at }),
+      Err(reason) => Err(AccessDenied { door: String::from(door), by: key.describe(), at, reason }),
+    }
   }
-  
+
   pub struct CarDoorKey;
+  impl CarDoorKey {
+    pub const CUT: &'static str = "car remote control";
+  }
   impl Key for CarDoorKey {
-    fn open(&self) -> String {
-      String::from("I'm pushing down the button of remote control.")
+    fn describe(&self) -> String {
+      String::from("car remote control")
     }
-    fn close(&self) -> String {
-      String::from("I'm pushing up the button of remote control.")
+    fn authenticate(&self, door: &str, store: &CredentialStore, at: SystemTime) -> Result<AccessGranted, AccessDenied> {
+      outcome(self, door, at, store.check_cut(door, CarDoorKey::CUT))
     }
   }
-  
+
   pub struct HouseDoorKey;
+  impl HouseDoorKey {
+    pub const CUT: &'static str = "house key";
+  }
   impl Key for HouseDoorKey {
-    fn open(&self) -> String {
-      String::from("I'm turning the key in the right side.")
+    fn describe(&self) -> String {
+      String::from("house key")
+    }
+    fn authenticate(&self, door: &str, store: &CredentialStore, at: SystemTime) -> Result<AccessGranted, AccessDenied> {
+      outcome(self, door, at, store.check_cut(door, HouseDoorKey::CUT))
+    }
+  }
+
+  /// A code typed on a keypad.
+  pub struct PinCode {
+    pin: String,
+  }
+  impl PinCode {
+    pub fn new(pin: &str) -> PinCode {
+      PinCode { pin: String::from(pin) }
+    }
+  }
+  impl Key for PinCode {
+    fn describe(&self) -> String {
+      String::from("PIN code")
+    }
+    fn authenticate(&self, door: &str, store: &CredentialStore, at: SystemTime) -> Result<AccessGranted, AccessDenied> {
+      outcome(self, door, at, store.check_pin(door, &self.pin))
+    }
+  }
+
+  pub struct RfidBadge {
+    id: u64,
+  }
+  impl RfidBadge {
+    pub fn new(id: u64) -> RfidBadge {
+      RfidBadge { id }
+    }
+  }
+  impl Key for RfidBadge {
+    fn describe(&self) -> String {
+      format!("RFID badge {}", self.id)
+    }
+    fn authenticate(&self, door: &str, store: &CredentialStore, at: SystemTime) -> Result<AccessGranted, AccessDenied> {
+      outcome(self, door, at, store.check_badge(door, self.id))
+    }
+  }
+
+  /// A token valid on one door until it expires.
+  pub struct TimeLimitedToken {
+    token: String,
+  }
+  impl TimeLimitedToken {
+    pub fn new(token: &str) -> TimeLimitedToken {
+      TimeLimitedToken { token: String::from(token) }
     }
-    fn close(&self) -> String {
-      String::from("I'm turning the key in the left side.")
-    }  
   }
-  
-  struct Door {
-    key: Box<Key>,
+  impl Key for TimeLimitedToken {
+    fn describe(&self) -> String {
+      let prefix: String = self.token.chars().take(2).collect();
+      format!("token {}...", prefix)
+    }
+    fn authenticate(&self, door: &str, store: &CredentialStore, at: SystemTime) -> Result<AccessGranted, AccessDenied> {
+      outcome(self, door, at, store.check_token(door, &self.token, at))
+    }
+  }
+
+  /// Opens any door, once registered in the store.
+  pub struct MasterKey {
+    secret: String,
+  }
+  impl MasterKey {
+    pub fn new(secret: &str) -> MasterKey {
+      MasterKey { secret: String::from(secret) }
+    }
+  }
+  impl Key for MasterKey {
+    fn describe(&self) -> String {
+      String::from("master key")
+    }
+    fn authenticate(&self, door: &str, store: &CredentialStore, at: SystemTime) -> Result<AccessGranted, AccessDenied> {
+      outcome(self, door, at, store.check_master_key(&self.secret))
+    }
   }
-  
+}
+
+pub mod door {
+  //! `door` module.
+  //!
+  //! The abstraction side of the bridge: doors with a state, the one-door
+  //! places using them, and a building keeping an audit trail. Any `Key`
+  //! works with any of them.
+
+  use std::collections::BTreeMap;
+  use std::error::Error;
+  use std::fmt;
+  use std::time::{SystemTime, UNIX_EPOCH};
+  use bridge::access::{AccessDenied, AccessGranted, CredentialStore, DenyReason};
+  use bridge::key::Key;
+
+  #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+  pub enum DoorState {
+    Open,
+    Closed,
+    Locked,
+  }
+
+  impl fmt::Display for DoorState {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+      let text = match *self {
+        DoorState::Open => "open",
+        DoorState::Closed => "closed",
+        DoorState::Locked => "locked",
+      };
+      write!(f, "{}", text)
+    }
+  }
+
+  #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+  pub enum DoorAction {
+    Open,
+    Close,
+    Lock,
+    Unlock,
+  }
+
+  impl DoorAction {
+    /// State reached by applying the action from `from`, or `None` when
+    /// the transition is illegal.
+    pub fn target(self, from: DoorState) -> Option<DoorState> {
+      match (self, from) {
+        (DoorAction::Open, DoorState::Closed) => Some(DoorState::Open),
+        (DoorAction::Close, DoorState::Open) => Some(DoorState::Closed),
+        (DoorAction::Lock, DoorState::Closed) => Some(DoorState::Locked),
+        (DoorAction::Unlock, DoorState::Locked) => Some(DoorState::Closed),
+        _ => None,
+      }
+    }
+  }
+
+  impl fmt::Display for DoorAction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+      let text = match *self {
+        DoorAction::Open => "open",
+        DoorAction::Close => "close",
+        DoorAction::Lock => "lock",
+        DoorAction::Unlock => "unlock",
+      };
+      write!(f, "{}", text)
+    }
+  }
+
+  #[derive(Debug)]
+  pub enum DoorError {
+    UnknownDoor(String),
+    Denied(AccessDenied),
+    IllegalTransition { door: String, from: DoorState, action: DoorAction },
+  }
+
+  impl fmt::Display for DoorError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+      match *self {
+        DoorError::UnknownDoor(ref door) => write!(f, "no door named {}", door),
+        DoorError::Denied(ref denied) => write!(f, "{}", denied),
+        DoorError::IllegalTransition { ref door, from, action } => {
+          write!(f, "cannot {} the {}: it is {}", action, door, from)
+        }
+      }
+    }
+  }
+
+  impl Error for DoorError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+      match *self {
+        DoorError::Denied(ref denied) => Some(denied),
+        _ => None,
+      }
+    }
+  }
+
+  impl From<AccessDenied> for DoorError {
+    fn from(denied: AccessDenied) -> DoorError {
+      DoorError::Denied(denied)
+    }
+  }
+
+  pub struct Door {
+    name: String,
+    key: Box<dyn Key>,
+    state: DoorState,
+  }
+
   impl Door {
-    fn new(key: Box<Key>) -> Door {
-      Door { key }
+    /// Creates a closed, unlocked door opened with `key`.
+    pub fn new(name: &str, key: Box<dyn Key>) -> Door {
+      Door { name: String::from(name), key, state: DoorState::Closed }
+    }
+
+    pub fn name(&self) -> &str {
+      &self.name
+    }
+
+    pub fn key(&self) -> &dyn Key {
+      &*self.key
+    }
+
+    pub fn state(&self) -> DoorState {
+      self.state
+    }
+
+    /// Applies `action` at time `at` if the transition is legal and the key
+    /// is accepted; the state is left untouched otherwise.
+    pub fn apply(&mut self, action: DoorAction, store: &CredentialStore, at: SystemTime) -> Result<AccessGranted, DoorError> {
+      let next = action.target(self.state).ok_or_else(|| DoorError::IllegalTransition {
+        door: self.name.clone(),
+        from: self.state,
+        action,
+      })?;
+      let granted = self.key.authenticate(&self.name, store, at)?;
+      self.state = next;
+      Ok(granted)
     }
-  
-    fn open_the_door(&self) -> String { self.key.open() }
-    fn close_the_door(&self) -> String { self.key.close() }
+
+    pub fn open(&mut self, store: &CredentialStore) -> Result<AccessGranted, DoorError> {
+      self.apply(DoorAction::Open, store, SystemTime::now())
+    }
+    pub fn close(&mut self, store: &CredentialStore) -> Result<AccessGranted, DoorError> {
+      self.apply(DoorAction::Close, store, SystemTime::now())
+    }
+    pub fn lock(&mut self, store: &CredentialStore) -> Result<AccessGranted, DoorError> {
+      self.apply(DoorAction::Lock, store, SystemTime::now())
+    }
+    pub fn unlock(&mut self, store: &CredentialStore) -> Result<AccessGranted, DoorError> {
+      self.apply(DoorAction::Unlock, store, SystemTime::now())
+    }
+
+    /// Unlocks the door when needed, then opens it.
+    fn pass_in(&mut self, store: &CredentialStore) -> Result<AccessGranted, DoorError> {
+      if self.state == DoorState::Locked {
+        self.unlock(store)?;
+      }
+      self.open(store)
+    }
+
+    /// Closes the door, then locks it.
+    fn pass_out(&mut self, store: &CredentialStore) -> Result<AccessGranted, DoorError> {
+      self.close(store)?;
+      self.lock(store)
+    }
+
     fn prevent_owner(&self) -> String {
       String::from("Hi Owner! You have a guest.")
     }
   }
-  
+
   pub struct HouseOneDoor {
     door: Door,
   }
-  
+
   impl HouseOneDoor {
-    pub fn new(key: Box<Key>) -> HouseOneDoor {
+    pub fn new(door: &str, key: Box<dyn Key>) -> HouseOneDoor {
       HouseOneDoor {
-        door: Door::new(key),
+        door: Door::new(door, key),
       }
     }
-    pub fn enter(&self) -> String {
-      format!("{}\n{}",
+    pub fn state(&self) -> DoorState {
+      self.door.state()
+    }
+    pub fn enter(&mut self, store: &CredentialStore) -> Result<String, DoorError> {
+      let granted = self.door.pass_in(store)?;
+      Ok(format!("{}\nThe {} opened the {}.",
         self.door.prevent_owner(),
-        self.door.open_the_door()
-      )
+        granted.by,
+        granted.door,
+      ))
     }
-    pub fn leave(&self) -> String {
-      self.door.close_the_door()
+    pub fn leave(&mut self, store: &CredentialStore) -> Result<String, DoorError> {
+      let granted = self.door.pass_out(store)?;
+      Ok(format!("The {} closed and locked the {}.", granted.by, granted.door))
     }
   }
-  
+
   pub struct CarOneDoor {
     door: Door,
   }
-  
+
   impl CarOneDoor {
-    pub fn new(key: Box<Key>) -> HouseOneDoor {
-      HouseOneDoor {
-        door: Door::new(key),
+    pub fn new(door: &str, key: Box<dyn Key>) -> CarOneDoor {
+      CarOneDoor {
+        door: Door::new(door, key),
+      }
+    }
+    pub fn state(&self) -> DoorState {
+      self.door.state()
+    }
+    pub fn enter(&mut self, store: &CredentialStore) -> Result<String, DoorError> {
+      let granted = self.door.pass_in(store)?;
+      Ok(format!("The {} opened the {}. You can park the car.", granted.by, granted.door))
+    }
+    pub fn leave(&mut self, store: &CredentialStore) -> Result<String, DoorError> {
+      let granted = self.door.pass_out(store)?;
+      Ok(format!("The {} closed and locked the {}. Have a good trip.", granted.by, granted.door))
+    }
+  }
+
+  /// What happened to an attempt recorded in the audit trail.
+  #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+  pub enum AuditOutcome {
+    /// The action was performed; the door is now in this state.
+    Done(DoorState),
+    Denied(DenyReason),
+    /// The action was illegal from this state.
+    Illegal(DoorState),
+    /// The building has no door of this name.
+    UnknownDoor,
+  }
+
+  /// One attempt on a door of a building.
+  #[derive(Debug, Clone, PartialEq)]
+  pub struct AuditEntry {
+    pub door: String,
+    /// The key of the door, `None` when there is no such door.
+    pub by: Option<String>,
+    pub action: DoorAction,
+    pub at: SystemTime,
+    pub outcome: AuditOutcome,
+  }
+
+  impl fmt::Display for AuditEntry {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+      let seconds = self.at.duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0);
+      let by = self.by.as_ref().map_or("someone", String::as_str);
+      write!(f, "[{}] {} {} {}: ", seconds, by, self.action, self.door)?;
+      match self.outcome {
+        AuditOutcome::Done(state) => write!(f, "now {}", state),
+        AuditOutcome::Denied(reason) => write!(f, "denied, {}", reason),
+        AuditOutcome::Illegal(state) => write!(f, "illegal, it is {}", state),
+        AuditOutcome::UnknownDoor => write!(f, "no such door"),
       }
     }
-    pub fn enter(&self) -> String {
-      format!("{} You can park the car.", self.door.open_the_door())
+  }
+
+  /// Many doors, each with its own key, and the trail of every attempt.
+  pub struct Building {
+    name: String,
+    doors: BTreeMap<String, Door>,
+    audit: Vec<AuditEntry>,
+  }
+
+  impl Building {
+    pub fn new(name: &str) -> Building {
+      Building { name: String::from(name), doors: BTreeMap::new(), audit: Vec::new() }
+    }
+
+    pub fn name(&self) -> &str {
+      &self.name
+    }
+
+    /// Adds a closed door opened with `key`, replacing any door of the
+    /// same name.
+    pub fn add_door(&mut self, name: &str, key: Box<dyn Key>) {
+      self.doors.insert(String::from(name), Door::new(name, key));
+    }
+
+    pub fn door(&self, name: &str) -> Option<&Door> {
+      self.doors.get(name)
+    }
+
+    /// Doors in name order.
+    pub fn doors(&self) -> impl Iterator<Item = &Door> {
+      self.doors.values()
+    }
+
+    pub fn state(&self, name: &str) -> Option<DoorState> {
+      self.doors.get(name).map(Door::state)
+    }
+
+    /// Applies `action` on the door `name` at time `at` and records the
+    /// attempt, whatever its outcome.
+    pub fn perform(&mut self, name: &str, action: DoorAction, store: &CredentialStore, at: SystemTime) -> Result<AccessGranted, DoorError> {
+      let door = match self.doors.get_mut(name) {
+        Some(door) => door,
+        None => {
+          self.audit.push(AuditEntry { door: String::from(name), by: None, action, at, outcome: AuditOutcome::UnknownDoor });
+          return Err(DoorError::UnknownDoor(String::from(name)));
+        }
+      };
+      let from = door.state();
+      let result = door.apply(action, store, at);
+      let outcome = match result {
+        Ok(_) => AuditOutcome::Done(door.state()),
+        Err(DoorError::Denied(ref denied)) => AuditOutcome::Denied(denied.reason),
+        Err(_) => AuditOutcome::Illegal(from),
+      };
+      self.audit.push(AuditEntry {
+        door: String::from(name),
+        by: Some(door.key().describe()),
+        action,
+        at,
+        outcome,
+      });
+      result
+    }
+
+    pub fn open(&mut self, name: &str, store: &CredentialStore) -> Result<AccessGranted, DoorError> {
+      self.perform(name, DoorAction::Open, store, SystemTime::now())
+    }
+    pub fn close(&mut self, name: &str, store: &CredentialStore) -> Result<AccessGranted, DoorError> {
+      self.perform(name, DoorAction::Close, store, SystemTime::now())
+    }
+    pub fn lock(&mut self, name: &str, store: &CredentialStore) -> Result<AccessGranted, DoorError> {
+      self.perform(name, DoorAction::Lock, store, SystemTime::now())
+    }
+    pub fn unlock(&mut self, name: &str, store: &CredentialStore) -> Result<AccessGranted, DoorError> {
+      self.perform(name, DoorAction::Unlock, store, SystemTime::now())
+    }
+
+    /// Every attempt, oldest first.
+    pub fn audit(&self) -> &[AuditEntry] {
+      &self.audit
     }
-    pub fn leave(&self) -> String {
-      format!("{} Have a good trip.", self.door.close_the_door())
+
+    /// Attempts on the door `name`, oldest first.
+    pub fn audit_of<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a AuditEntry> + 'a {
+      self.audit.iter().filter(move |entry| entry.door == name)
     }
   }
-}
\ No newline at end of file
+}
@@ -0,0 +1,281 @@
+//! `format` module.
+//!
+//! Round-trips trees through nested JSON and an indented outline.
+//!
+//! In JSON, a branch is `{ "name": ..., "children": [...] }` and a leaf is
+//! `{ "name": ..., "value": ... }`. In an outline, each line is a node
+//! indented with spaces below its parent: a branch is a bare name and a
+//! leaf is `name = value`. A name or value which could not be read back
+//! as is, such as an empty one or a name containing `=`, is written as a
+//! JSON string.
+//!
+//! # Example
+//!
+//! ```rust
+//! use structural::composite::*;
+//! use structural::composite::format::*;
+//!
+//! let tree: Tree<u32> = from_json(r#"{
+//!   "name": "company",
+//!   "children": [
+//!     { "name": "sales", "children": [{ "name": "Alice", "value": 52 }] },
+//!     { "name": "Bob", "value": 41 }
+//!   ]
+//! }"#).unwrap();
+//! assert_eq!(tree.lookup("sales/Alice").and_then(|node| node.value()), Some(&52));
+//!
+//! let outline = to_outline(&tree);
+//! assert_eq!(outline, "company\n  sales\n    Alice = 52\n  Bob = 41\n");
+//! let parsed: Tree<u32> = from_outline(&outline).unwrap();
+//! assert_eq!(to_json(&parsed).unwrap(), to_json(&tree).unwrap());
+//!
+//! let error = from_outline::<u32>("company\n  Bob = forty").err().unwrap();
+//! assert_eq!(error.to_string(), "line 2: invalid value `forty`: invalid digit found in string");
+//!
+//! let mut tree: Tree<String> = Tree::named("notes");
+//! tree.add(Box::new(Leaf::named("empty", String::new())));
+//! tree.add(Box::new(Leaf::named("a = b", String::from("  padded"))));
+//! tree.add(Box::new(Tree::<String>::named("")));
+//! let outline = to_outline(&tree);
+//! assert_eq!(outline, "notes\n  empty = \"\"\n  \"a = b\" = \"  padded\"\n  \"\"\n");
+//! let parsed: Tree<String> = from_outline(&outline).unwrap();
+//! assert_eq!(to_json(&parsed).unwrap(), to_json(&tree).unwrap());
+//! ```
+
+use std::error::Error;
+use std::fmt;
+use std::fmt::Display;
+use std::str::FromStr;
+
+use serde::de::{self, Deserialize, Deserializer};
+use serde::ser::{Serialize, SerializeMap, Serializer};
+use serde_json;
+
+use composite::{Leaf, Node, Tree};
+
+#[derive(Debug)]
+pub enum FormatError {
+  Json(serde_json::Error),
+  Outline { line: usize, reason: String },
+}
+
+impl fmt::Display for FormatError {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match *self {
+      FormatError::Json(ref e) => write!(f, "invalid JSON tree: {}", e),
+      FormatError::Outline { line, ref reason } => write!(f, "line {}: {}", line, reason),
+    }
+  }
+}
+
+impl Error for FormatError {
+  fn source(&self) -> Option<&(dyn Error + 'static)> {
+    match *self {
+      FormatError::Json(ref e) => Some(e),
+      _ => None,
+    }
+  }
+}
+
+impl From<serde_json::Error> for FormatError {
+  fn from(error: serde_json::Error) -> FormatError {
+    FormatError::Json(error)
+  }
+}
+
+/// Serializes any node, with its descendants.
+struct Serialized<'a, T: 'a>(&'a dyn Node<T>);
+
+impl<'a, T: Serialize> Serialize for Serialized<'a, T> {
+  fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+    let node = self.0;
+    let mut map = serializer.serialize_map(Some(2))?;
+    map.serialize_entry("name", node.name())?;
+    if node.is_branch() {
+      let children: Vec<Serialized<T>> = node.children().into_iter().map(Serialized).collect();
+      map.serialize_entry("children", &children)?;
+    } else if let Some(value) = node.value() {
+      map.serialize_entry("value", value)?;
+    }
+    map.end()
+  }
+}
+
+impl<T: Serialize> Serialize for Tree<T> {
+  fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+    Serialized(self).serialize(serializer)
+  }
+}
+
+impl<T: Serialize> Serialize for Leaf<T> {
+  fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+    Serialized(self).serialize(serializer)
+  }
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct NodeFile<T> {
+  name: String,
+  value: Option<T>,
+  children: Option<Vec<NodeFile<T>>>,
+}
+
+impl<T: 'static> NodeFile<T> {
+  fn into_tree(self) -> Result<Tree<T>, String> {
+    let NodeFile { name, value, children } = self;
+    let children = match children {
+      Some(_) if value.is_some() => return Err(format!("`{}` has both a value and children", name)),
+      Some(children) => children,
+      None => return Err(format!("`{}` has no children", name)),
+    };
+    let mut tree = Tree::named(&name);
+    for child in children {
+      tree.add(child.into_node()?);
+    }
+    Ok(tree)
+  }
+
+  fn into_node(self) -> Result<Box<dyn Node<T>>, String> {
+    if self.children.is_some() {
+      return Ok(Box::new(self.into_tree()?));
+    }
+    match self.value {
+      Some(value) => Ok(Box::new(Leaf::named(&self.name, value))),
+      None => Err(format!("`{}` has neither a value nor children", self.name)),
+    }
+  }
+}
+
+impl<'de, T: Deserialize<'de> + 'static> Deserialize<'de> for Tree<T> {
+  fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Tree<T>, D::Error> {
+    NodeFile::deserialize(deserializer)?.into_tree().map_err(de::Error::custom)
+  }
+}
+
+pub fn to_json<T: Serialize>(node: &dyn Node<T>) -> Result<String, FormatError> {
+  Ok(serde_json::to_string(&Serialized(node))?)
+}
+
+pub fn to_json_pretty<T: Serialize>(node: &dyn Node<T>) -> Result<String, FormatError> {
+  Ok(serde_json::to_string_pretty(&Serialized(node))?)
+}
+
+/// Reads a tree from JSON; the root must have children.
+pub fn from_json<T: de::DeserializeOwned + 'static>(json: &str) -> Result<Tree<T>, FormatError> {
+  Ok(serde_json::from_str(json)?)
+}
+
+/// Writes `node` as an outline indented with two spaces per level.
+pub fn to_outline<T: Display>(node: &dyn Node<T>) -> String {
+  let mut outline = String::new();
+  write_outline(node, 0, &mut outline);
+  outline
+}
+
+fn write_outline<T: Display>(node: &dyn Node<T>, depth: usize, outline: &mut String) {
+  outline.push_str(&"  ".repeat(depth));
+  outline.push_str(&quote(node.name(), node.name().contains('=')));
+  match node.value() {
+    Some(value) if !node.is_branch() => {
+      outline.push_str(&format!(" = {}\n", quote(&value.to_string(), false)));
+    }
+    _ => outline.push('\n'),
+  }
+  for child in node.children() {
+    write_outline(child, depth + 1, outline);
+  }
+}
+
+/// `text` as a JSON string when it would not be read back as is.
+fn quote(text: &str, force: bool) -> String {
+  let ambiguous = text.is_empty() || text.starts_with('"') || text.trim() != text || text.chars().any(char::is_control);
+  if force || ambiguous {
+    serde_json::to_string(text).expect("strings always serialize")
+  } else {
+    String::from(text)
+  }
+}
+
+/// Splits an outline line into a name and, for a leaf, a value.
+fn split_entry(content: &str) -> Result<(String, Option<String>), String> {
+  if !content.starts_with('"') {
+    return Ok(match content.find(" = ") {
+      Some(at) => (String::from(&content[..at]), Some(unquote(content[at + 3..].trim())?)),
+      None => (String::from(content), None),
+    });
+  }
+  let mut strings = serde_json::Deserializer::from_str(content).into_iter::<String>();
+  let name = match strings.next() {
+    Some(Ok(name)) => name,
+    _ => return Err(format!("invalid quoted name `{}`", content)),
+  };
+  let rest = &content[strings.byte_offset()..];
+  if rest.is_empty() {
+    Ok((name, None))
+  } else if let Some(value) = rest.strip_prefix(" = ") {
+    Ok((name, Some(unquote(value.trim())?)))
+  } else {
+    Err(format!("unexpected `{}` after the name", rest))
+  }
+}
+
+fn unquote(text: &str) -> Result<String, String> {
+  if text.starts_with('"') {
+    serde_json::from_str(text).map_err(|_| format!("invalid quoted value `{}`", text))
+  } else {
+    Ok(String::from(text))
+  }
+}
+
+/// Reads a tree from an outline. Blank lines are skipped, the first line is
+/// the root and any deeper indentation opens a new level.
+pub fn from_outline<T>(text: &str) -> Result<Tree<T>, FormatError>
+where T: FromStr + 'static, T::Err: Display {
+  let mut stack: Vec<(usize, Tree<T>)> = Vec::new();
+  for (index, line) in text.lines().enumerate() {
+    let error = |reason: String| FormatError::Outline { line: index + 1, reason };
+    if line.trim().is_empty() {
+      continue;
+    }
+    let content = line.trim_start_matches(' ');
+    if content.starts_with('\t') {
+      return Err(error(String::from("indent with spaces, not tabs")));
+    }
+    let indent = line.len() - content.len();
+    let (name, value) = split_entry(content.trim_end()).map_err(&error)?;
+    if stack.is_empty() {
+      if indent > 0 || value.is_some() {
+        return Err(error(String::from("the root must be an unindented branch")));
+      }
+      stack.push((0, Tree::named(&name)));
+      continue;
+    }
+    if indent == 0 {
+      return Err(error(String::from("an outline has a single root")));
+    }
+    close_levels(&mut stack, indent);
+    match value {
+      Some(text) => {
+        let value = text.parse::<T>()
+          .map_err(|e| error(format!("invalid value `{}`: {}", text, e)))?;
+        let parent = &mut stack.last_mut().expect("the root is never closed").1;
+        parent.add(Box::new(Leaf::named(&name, value)));
+      }
+      None => stack.push((indent, Tree::named(&name))),
+    }
+  }
+  close_levels(&mut stack, 1);
+  stack.pop().map(|(_, root)| root).ok_or(FormatError::Outline {
+    line: 0,
+    reason: String::from("the outline is empty"),
+  })
+}
+
+/// Adds the branches indented at least `indent` to their parent.
+fn close_levels<T: 'static>(stack: &mut Vec<(usize, Tree<T>)>, indent: usize) {
+  while stack.len() > 1 && stack[stack.len() - 1].0 >= indent {
+    let (_, tree) = stack.pop().expect("checked above");
+    stack.last_mut().expect("checked above").1.add(Box::new(tree));
+  }
+}
@@ -0,0 +1,332 @@
+//! `fs` module.
+//!
+//! A directory on disk seen as a composite. A `DirNode` lists its entries
+//! the first time its children are asked for, so only the visited part of
+//! the tree is read. Files are leaves carrying their size in bytes;
+//! directories report the aggregated size of the files below them.
+//!
+//! Symbolic links are shown as empty leaves unless they are followed; a
+//! followed link leading back to one of its ancestors is shown as a loop,
+//! without children.
+//!
+//! # Example
+//!
+//! ```rust
+//! # #[cfg(unix)]
+//! # {
+//! use std::fs;
+//! use structural::composite::*;
+//! use structural::composite::fs::*;
+//!
+//! let root = std::env::temp_dir().join(format!("composite-fs-{}", std::process::id()));
+//! fs::create_dir_all(root.join("src/bin")).unwrap();
+//! fs::write(root.join("Cargo.toml"), "[package]\n").unwrap();
+//! fs::write(root.join("src/lib.rs"), "pub mod bin;\n").unwrap();
+//! fs::write(root.join("src/bin/main.rs"), "fn main() {}\n").unwrap();
+//! std::os::unix::fs::symlink(&root, root.join("src/bin/up")).unwrap();
+//!
+//! let dir = DirNode::open(&root).unwrap();
+//! assert_eq!(dir.size(), 10 + 13 + 13);
+//! assert_eq!(dir.lookup("src/bin/main.rs").and_then(|node| node.value()), Some(&13));
+//! assert_eq!(dir.sum::<u64>(), dir.size());
+//! assert_eq!(dir.lookup("src/bin/up").unwrap().is_branch(), false);
+//!
+//! let options = DirOptions::new().follow_links(true).glob("*.rs");
+//! let dir = DirNode::open_with(&root, options).unwrap();
+//! let names: Vec<&str> = dir.depth_first().map(|(_, node)| node.name()).collect();
+//! assert_eq!(names, vec![root.file_name().unwrap().to_str().unwrap(), "src", "bin", "main.rs", "up", "lib.rs"]);
+//! assert_eq!(dir.size(), 26);
+//! let up = dir.children()[0].children()[0].children()[1];
+//! assert!(up.is_branch() && up.children().is_empty());
+//! print!("{}", render::ascii_with(&dir, render::name_and_value));
+//! fs::remove_dir_all(&root).unwrap();
+//! # }
+//! ```
+//!
+//! Globs:
+//!
+//! ```rust
+//! use structural::composite::fs::Glob;
+//!
+//! assert!(Glob::new("src/**/*.rs").matches("src/bin/main.rs"));
+//! assert!(Glob::new("src/**/*.rs").matches("src/lib.rs"));
+//! assert!(Glob::new("src/**").matches("src/bin/main.rs"));
+//! assert!(!Glob::new("*.rs").matches("main.rsx"));
+//! assert!(Glob::new("[!.]*.t?ml").matches("Cargo.toml"));
+//! ```
+
+use std::cell::OnceCell;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+
+use composite::Node;
+
+/// A shell-style pattern: `*` and `?` never match `/`, `**` matches any
+/// number of directories and `[a-z]` or `[!a-z]` match one character.
+/// A pattern without `/` is matched against the file name only, otherwise
+/// against the path relative to the walked directory.
+#[derive(Debug, Clone)]
+pub struct Glob {
+  pattern: Vec<char>,
+}
+
+impl Glob {
+  pub fn new(pattern: &str) -> Glob {
+    Glob { pattern: pattern.chars().collect() }
+  }
+
+  pub fn matches(&self, path: &str) -> bool {
+    let text: Vec<char> = if self.pattern.contains(&'/') {
+      path.chars().collect()
+    } else {
+      path.rsplit('/').next().unwrap_or(path).chars().collect()
+    };
+    glob_match(&self.pattern, &text)
+  }
+}
+
+fn glob_match(pattern: &[char], text: &[char]) -> bool {
+  match pattern.first() {
+    None => text.is_empty(),
+    Some('*') if pattern.get(1) == Some(&'*') => {
+      let rest = &pattern[2..];
+      if rest.is_empty() {
+        // A trailing `**` matches everything below.
+        return true;
+      }
+      let rest = if rest.first() == Some(&'/') { &rest[1..] } else { rest };
+      (0..=text.len())
+        .filter(|&i| i == 0 || text[i - 1] == '/')
+        .any(|i| glob_match(rest, &text[i..]))
+    }
+    Some('*') => {
+      (0..=text.len())
+        .take_while(|&i| i == 0 || text[i - 1] != '/')
+        .any(|i| glob_match(&pattern[1..], &text[i..]))
+    }
+    Some('?') => text.first().is_some_and(|&c| c != '/') && glob_match(&pattern[1..], &text[1..]),
+    Some('[') => match pattern.iter().skip(2).position(|&c| c == ']') {
+      Some(end) => {
+        let class = &pattern[1..end + 2];
+        text.first().is_some_and(|&c| c != '/' && class_match(class, c))
+          && glob_match(&pattern[end + 3..], &text[1..])
+      }
+      None => text.first() == Some(&'[') && glob_match(&pattern[1..], &text[1..]),
+    },
+    Some(&c) => text.first() == Some(&c) && glob_match(&pattern[1..], &text[1..]),
+  }
+}
+
+/// Matches `c` against the inside of a `[...]` class.
+fn class_match(class: &[char], c: char) -> bool {
+  let (negated, class) = match class.first() {
+    Some('!') | Some('^') => (true, &class[1..]),
+    _ => (false, class),
+  };
+  let mut found = false;
+  let mut i = 0;
+  while i < class.len() {
+    if i + 2 < class.len() && class[i + 1] == '-' {
+      found |= class[i] <= c && c <= class[i + 2];
+      i += 3;
+    } else {
+      found |= class[i] == c;
+      i += 1;
+    }
+  }
+  found != negated
+}
+
+/// How a directory is walked.
+#[derive(Debug, Clone, Default)]
+pub struct DirOptions {
+  follow_links: bool,
+  globs: Vec<Glob>,
+}
+
+impl DirOptions {
+  pub fn new() -> DirOptions {
+    DirOptions::default()
+  }
+
+  /// Follows symbolic links to files and directories.
+  pub fn follow_links(mut self, follow: bool) -> DirOptions {
+    self.follow_links = follow;
+    self
+  }
+
+  /// Keeps only the files matching one of the patterns; directories are
+  /// always kept.
+  pub fn glob(mut self, pattern: &str) -> DirOptions {
+    self.globs.push(Glob::new(pattern));
+    self
+  }
+
+  fn keeps(&self, relative: &str) -> bool {
+    self.globs.is_empty() || self.globs.iter().any(|glob| glob.matches(relative))
+  }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EntryKind {
+  File,
+  Dir,
+  /// A link that is not followed, or whose target is missing.
+  Symlink,
+  /// A directory already open above this one.
+  Loop,
+}
+
+struct Listing {
+  children: Vec<DirNode>,
+  error: Option<io::Error>,
+}
+
+/// A file or directory, lazily walked.
+pub struct DirNode {
+  name: String,
+  path: PathBuf,
+  relative: String,
+  kind: EntryKind,
+  size: u64,
+  options: Rc<DirOptions>,
+  /// Canonical paths of this directory and its ancestors.
+  ancestors: Rc<Vec<PathBuf>>,
+  listing: OnceCell<Listing>,
+  total: OnceCell<u64>,
+}
+
+impl DirNode {
+  pub fn open<P: AsRef<Path>>(path: P) -> io::Result<DirNode> {
+    DirNode::open_with(path, DirOptions::new())
+  }
+
+  pub fn open_with<P: AsRef<Path>>(path: P, options: DirOptions) -> io::Result<DirNode> {
+    let path = path.as_ref();
+    let name = path.file_name()
+      .map(|name| name.to_string_lossy().into_owned())
+      .unwrap_or_else(|| path.display().to_string());
+    DirNode::entry(path.to_path_buf(), name, String::new(), Rc::new(options), Rc::new(Vec::new()))
+  }
+
+  fn entry(path: PathBuf, name: String, relative: String, options: Rc<DirOptions>, ancestors: Rc<Vec<PathBuf>>) -> io::Result<DirNode> {
+    let mut metadata = fs::symlink_metadata(&path)?;
+    let mut kind = EntryKind::File;
+    if metadata.file_type().is_symlink() {
+      match fs::metadata(&path) {
+        Ok(target) if options.follow_links => metadata = target,
+        _ => kind = EntryKind::Symlink,
+      }
+    }
+    let mut ancestors = ancestors;
+    if kind == EntryKind::File && metadata.is_dir() {
+      let canonical = fs::canonicalize(&path)?;
+      if ancestors.contains(&canonical) {
+        kind = EntryKind::Loop;
+      } else {
+        kind = EntryKind::Dir;
+        let mut inner = (*ancestors).clone();
+        inner.push(canonical);
+        ancestors = Rc::new(inner);
+      }
+    }
+    let size = if kind == EntryKind::File { metadata.len() } else { 0 };
+    Ok(DirNode {
+      name,
+      path,
+      relative,
+      kind,
+      size,
+      options,
+      ancestors,
+      listing: OnceCell::new(),
+      total: OnceCell::new(),
+    })
+  }
+
+  pub fn path(&self) -> &Path {
+    &self.path
+  }
+
+  pub fn kind(&self) -> EntryKind {
+    self.kind
+  }
+
+  /// Size of a file, or the total size of the files below a directory.
+  pub fn size(&self) -> u64 {
+    *self.total.get_or_init(|| match self.kind {
+      EntryKind::Dir => self.listing().children.iter().map(DirNode::size).sum(),
+      _ => self.size,
+    })
+  }
+
+  /// The first error met while listing the directory, if any. Entries
+  /// that could not be read are left out.
+  pub fn error(&self) -> Option<&io::Error> {
+    self.listing().error.as_ref()
+  }
+
+  fn listing(&self) -> &Listing {
+    self.listing.get_or_init(|| {
+      let mut listing = Listing { children: Vec::new(), error: None };
+      if self.kind != EntryKind::Dir {
+        return listing;
+      }
+      let entries = match fs::read_dir(&self.path) {
+        Ok(entries) => entries,
+        Err(e) => {
+          listing.error = Some(e);
+          return listing;
+        }
+      };
+      for entry in entries {
+        let child = entry.and_then(|entry| {
+          let name = entry.file_name().to_string_lossy().into_owned();
+          let relative = if self.relative.is_empty() {
+            name.clone()
+          } else {
+            format!("{}/{}", self.relative, name)
+          };
+          DirNode::entry(entry.path(), name, relative, self.options.clone(), self.ancestors.clone())
+        });
+        match child {
+          Ok(child) => {
+            if child.kind == EntryKind::Dir || child.kind == EntryKind::Loop || self.options.keeps(&child.relative) {
+              listing.children.push(child);
+            }
+          }
+          Err(e) => {
+            if listing.error.is_none() {
+              listing.error = Some(e);
+            }
+          }
+        }
+      }
+      listing.children.sort_by(|a, b| a.name.cmp(&b.name));
+      listing
+    })
+  }
+}
+
+impl Node<u64> for DirNode {
+  fn name(&self) -> &str {
+    &self.name
+  }
+
+  fn value(&self) -> Option<&u64> {
+    match self.kind {
+      EntryKind::File | EntryKind::Symlink => Some(&self.size),
+      _ => None,
+    }
+  }
+
+  fn is_branch(&self) -> bool {
+    self.kind == EntryKind::Dir || self.kind == EntryKind::Loop
+  }
+
+  fn children(&self) -> Vec<&dyn Node<u64>> {
+    self.listing().children.iter().map(|child| child as &dyn Node<u64>).collect()
+  }
+}
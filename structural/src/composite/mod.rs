@@ -1,77 +1,392 @@
 //! `composite` module.
 //!
+//! Leaves carry a typed value, trees carry children; both are `Node`s.
+//! Traversals, lookups and aggregates come with the `Traverse` trait,
+//! implemented for every node and for `dyn Node`. Trees are read and
+//! written by the `format` module and drawn by the `render` module; the
+//! `fs` module presents a directory on disk as a tree.
+//!
 //! # Example
-//! 
+//!
 //! ``` rust
 //! use structural::composite::*;
-//! 
+//!
 //! let mut tree = Tree::new();
 //! tree.add(Box::new(Leaf::new(String::from("leaf 1"))));
-//! 
+//!
 //! let mut subtree1 = Tree::new();
 //! subtree1.add(Box::new(Leaf::new(String::from("leaf 2"))));
-//! 
+//!
 //! let mut subtree2 = Tree::new();
 //! subtree2.add(Box::new(Leaf::new(String::from("leaf 3"))));
 //! subtree2.add(Box::new(Leaf::new(String::from("leaf 4"))));
 //! subtree1.add(Box::new(subtree2));
 //! tree.add(Box::new(subtree1));
-//! 
+//!
 //! let mut subtree3 = Tree::new();
 //! let mut subtree4 = Tree::new();
 //! subtree4.add(Box::new(Leaf::new(String::from("leaf 5"))));
 //! subtree4.add(Box::new(Leaf::new(String::from("leaf 6"))));
-//! 
+//!
 //! subtree3.add(Box::new(subtree4));
 //! tree.add(Box::new(subtree3));
-//! 
-//! tree.print(String::from("-")); 
+//!
+//! tree.print(String::from("-"));
 //! ```
- 
-pub trait Node {
-  fn print(&self, prefix: String);
+//!
+//! Typed leaves, named trees, traversals and aggregates:
+//!
+//! ``` rust
+//! use structural::composite::*;
+//!
+//! let mut fruits = Tree::named("fruits");
+//! fruits.add(Box::new(Leaf::named("apples", 3)));
+//! fruits.add(Box::new(Leaf::named("pears", 5)));
+//! let mut basket = Tree::named("basket");
+//! basket.add(Box::new(fruits));
+//! basket.add(Box::new(Leaf::named("eggs", 12)));
+//!
+//! let names: Vec<&str> = basket.depth_first().map(|(_, node)| node.name()).collect();
+//! assert_eq!(names, vec!["basket", "fruits", "apples", "pears", "eggs"]);
+//! let names: Vec<&str> = basket.breadth_first().map(|(_, node)| node.name()).collect();
+//! assert_eq!(names, vec!["basket", "fruits", "eggs", "apples", "pears"]);
+//!
+//! assert_eq!(basket.lookup("fruits/pears").and_then(|node| node.value()), Some(&5));
+//! assert!(basket.lookup("fruits/kiwis").is_none());
+//! assert_eq!(basket.get(&[0, 1]).map(|node| node.name()), Some("pears"));
+//!
+//! assert_eq!(basket.count(), 5);
+//! assert_eq!(basket.leaf_count(), 3);
+//! assert_eq!(basket.sum::<i32>(), 20);
+//! assert_eq!(basket.max_depth(), 2);
+//! assert_eq!(basket.fold(0, |total, _, node| total + node.name().len()), 27);
+//!
+//! assert_eq!(basket.remove_where(|node| node.value().is_some_and(|&n| n > 4)), 2);
+//! assert_eq!(basket.sum::<i32>(), 3);
+//! ```
+//!
+//! A visitor is called when entering and leaving each node:
+//!
+//! ``` rust
+//! use structural::composite::*;
+//!
+//! struct Outline(Vec<String>);
+//!
+//! impl Visitor<i32> for Outline {
+//!   fn enter(&mut self, node: &dyn Node<i32>, depth: usize) -> bool {
+//!     self.0.push(format!("{}{}", "  ".repeat(depth), node.name()));
+//!     node.name() != "skipped"
+//!   }
+//! }
+//!
+//! let mut skipped = Tree::named("skipped");
+//! skipped.add(Box::new(Leaf::named("hidden", 1)));
+//! let mut root = Tree::named("root");
+//! root.add(Box::new(Leaf::named("a", 1)));
+//! root.add(Box::new(skipped));
+//!
+//! let mut outline = Outline(Vec::new());
+//! root.accept(&mut outline);
+//! assert_eq!(outline.0, vec!["root", "  a", "  skipped"]);
+//! ```
+
+pub mod format;
+pub mod fs;
+pub mod render;
+
+use std::collections::VecDeque;
+use std::iter::Sum;
+
+/// A node of a tree holding values of type `T`.
+pub trait Node<T> {
+  fn name(&self) -> &str;
+
+  /// Value carried by a leaf.
+  fn value(&self) -> Option<&T> {
+    None
+  }
+
+  /// Tells if the node can have children, even when it has none.
+  fn is_branch(&self) -> bool {
+    false
+  }
+
+  fn children(&self) -> Vec<&dyn Node<T>> {
+    Vec::new()
+  }
+
+  /// Mutable access to an in-memory tree, used to remove descendants.
+  fn as_tree_mut(&mut self) -> Option<&mut Tree<T>> {
+    None
+  }
+}
+
+/// Hook called on each node of a traversal.
+pub trait Visitor<T> {
+  /// Called before the children of `node`; returning `false` skips them.
+  fn enter(&mut self, _node: &dyn Node<T>, _depth: usize) -> bool {
+    true
+  }
+
+  /// Called after the children of `node`, even when they were skipped.
+  fn leave(&mut self, _node: &dyn Node<T>, _depth: usize) {}
+}
+
+/// Traversals, lookups and aggregates of a node and its descendants.
+///
+/// Depths are counted from the node the method is called on, at depth 0.
+pub trait Traverse<T> {
+  fn as_node(&self) -> &dyn Node<T>;
+
+  /// Pre-order iterator on `(depth, node)`.
+  fn depth_first(&self) -> DepthFirst<'_, T> {
+    DepthFirst { stack: vec![(0, self.as_node())] }
+  }
+
+  /// Level-order iterator on `(depth, node)`.
+  fn breadth_first(&self) -> BreadthFirst<'_, T> {
+    let mut queue = VecDeque::new();
+    queue.push_back((0, self.as_node()));
+    BreadthFirst { queue }
+  }
+
+  /// Values of the leaves, depth first.
+  fn values(&self) -> Values<'_, T> {
+    Values { nodes: self.depth_first() }
+  }
+
+  /// Finds a descendant by the names on its path, separated by `/`. The
+  /// empty path is the node itself.
+  fn lookup(&self, path: &str) -> Option<&dyn Node<T>> {
+    path.split('/')
+      .filter(|name| !name.is_empty())
+      .try_fold(self.as_node(), |node, name| {
+        node.children().into_iter().find(|child| child.name() == name)
+      })
+  }
+
+  /// Finds a descendant by the indices of the children on its path.
+  fn get(&self, indices: &[usize]) -> Option<&dyn Node<T>> {
+    indices.iter().try_fold(self.as_node(), |node, &index| node.children().into_iter().nth(index))
+  }
+
+  /// Folds every node, depth first, with its depth.
+  fn fold<A, F: FnMut(A, usize, &dyn Node<T>) -> A>(&self, init: A, mut f: F) -> A {
+    self.depth_first().fold(init, |acc, (depth, node)| f(acc, depth, node))
+  }
+
+  /// Number of nodes, this one included.
+  fn count(&self) -> usize {
+    self.depth_first().count()
+  }
+
+  fn leaf_count(&self) -> usize {
+    self.depth_first().filter(|(_, node)| !node.is_branch()).count()
+  }
+
+  fn sum<S: for<'a> Sum<&'a T>>(&self) -> S {
+    self.values().sum()
+  }
+
+  fn max_depth(&self) -> usize {
+    self.depth_first().map(|(depth, _)| depth).max().unwrap_or(0)
+  }
+
+  /// Walks the nodes depth first, calling `visitor` on each of them.
+  fn accept(&self, visitor: &mut dyn Visitor<T>) {
+    walk(self.as_node(), 0, visitor);
+  }
+
+  /// Prints the tree, indenting each level with one more `prefix`.
+  fn print(&self, prefix: String) {
+    self.accept(&mut Printer { prefix });
+  }
+}
+
+impl<T, N: Node<T>> Traverse<T> for N {
+  fn as_node(&self) -> &dyn Node<T> {
+    self
+  }
+}
+
+impl<'n, T> Traverse<T> for dyn Node<T> + 'n {
+  fn as_node(&self) -> &dyn Node<T> {
+    self
+  }
+}
+
+fn walk<T>(node: &dyn Node<T>, depth: usize, visitor: &mut dyn Visitor<T>) {
+  if visitor.enter(node, depth) {
+    for child in node.children() {
+      walk(child, depth + 1, visitor);
+    }
+  }
+  visitor.leave(node, depth);
+}
+
+struct Printer {
+  prefix: String,
+}
+
+impl<T> Visitor<T> for Printer {
+  fn enter(&mut self, node: &dyn Node<T>, depth: usize) -> bool {
+    let prefix = self.prefix.repeat(depth + 1);
+    if node.is_branch() {
+      println!("{}{}(", prefix, node.name());
+    } else {
+      println!("{}{}", prefix, node.name());
+    }
+    true
+  }
+
+  fn leave(&mut self, node: &dyn Node<T>, depth: usize) {
+    if node.is_branch() {
+      println!("{})", self.prefix.repeat(depth + 1));
+    }
+  }
+}
+
+/// Depth-first iterator, see `Traverse::depth_first`.
+pub struct DepthFirst<'a, T: 'a> {
+  stack: Vec<(usize, &'a dyn Node<T>)>,
+}
+
+impl<'a, T> Iterator for DepthFirst<'a, T> {
+  type Item = (usize, &'a dyn Node<T>);
+
+  fn next(&mut self) -> Option<Self::Item> {
+    let (depth, node) = self.stack.pop()?;
+    self.stack.extend(node.children().into_iter().rev().map(|child| (depth + 1, child)));
+    Some((depth, node))
+  }
+}
+
+/// Breadth-first iterator, see `Traverse::breadth_first`.
+pub struct BreadthFirst<'a, T: 'a> {
+  queue: VecDeque<(usize, &'a dyn Node<T>)>,
+}
+
+impl<'a, T> Iterator for BreadthFirst<'a, T> {
+  type Item = (usize, &'a dyn Node<T>);
+
+  fn next(&mut self) -> Option<Self::Item> {
+    let (depth, node) = self.queue.pop_front()?;
+    self.queue.extend(node.children().into_iter().map(|child| (depth + 1, child)));
+    Some((depth, node))
+  }
 }
 
-pub struct Leaf {
-  data: String
+/// Iterator on leaf values, see `Traverse::values`.
+pub struct Values<'a, T: 'a> {
+  nodes: DepthFirst<'a, T>,
 }
 
-impl Leaf {
-  pub fn new(data: String) -> Leaf {
-    Leaf { data }
+impl<'a, T> Iterator for Values<'a, T> {
+  type Item = &'a T;
+
+  fn next(&mut self) -> Option<&'a T> {
+    self.nodes.by_ref().find_map(|(_, node)| node.value())
   }
 }
 
-impl Node for Leaf {
-  fn print(&self,  prefix: String) {
-    println!("{}{}", prefix, self.data);
+pub struct Leaf<T> {
+  name: String,
+  data: T,
+}
+
+impl<T: ToString> Leaf<T> {
+  /// Creates a leaf named after its value.
+  pub fn new(data: T) -> Leaf<T> {
+    Leaf { name: data.to_string(), data }
   }
 }
 
-pub struct Tree {
-  children: Vec<Box<Node>>,
+impl<T> Leaf<T> {
+  pub fn named(name: &str, data: T) -> Leaf<T> {
+    Leaf { name: String::from(name), data }
+  }
+
+  pub fn data(&self) -> &T {
+    &self.data
+  }
+
+  pub fn data_mut(&mut self) -> &mut T {
+    &mut self.data
+  }
 }
 
-impl Tree {
-  pub fn new() -> Tree {
+impl<T> Node<T> for Leaf<T> {
+  fn name(&self) -> &str {
+    &self.name
+  }
+
+  fn value(&self) -> Option<&T> {
+    Some(&self.data)
+  }
+}
+
+pub struct Tree<T> {
+  name: String,
+  children: Vec<Box<dyn Node<T>>>,
+}
+
+impl<T> Default for Tree<T> {
+  fn default() -> Tree<T> {
+    Tree::new()
+  }
+}
+
+impl<T> Tree<T> {
+  pub fn new() -> Tree<T> {
+    Tree::named("")
+  }
+  pub fn named(name: &str) -> Tree<T> {
     Tree {
+      name: String::from(name),
       children: Vec::new(),
     }
   }
-  pub fn add(&mut self, child: Box<Node>) {
+  pub fn add(&mut self, child: Box<dyn Node<T>>) {
     self.children.push(child);
   }
   pub fn remove(&mut self) {
     self.children.pop();
   }
-}
 
-impl Node for Tree {
-  fn print(&self, prefix: String) {
-    println!("{}(", prefix);
-    for c in self.children.iter() {
-      c.print(format!("{}{}", prefix, prefix));
+  /// Removes every descendant matching `predicate`, with its own
+  /// descendants, and returns how many were removed at the top of their
+  /// branch.
+  pub fn remove_where<P: FnMut(&dyn Node<T>) -> bool>(&mut self, mut predicate: P) -> usize {
+    self.remove_matching(&mut predicate)
+  }
+
+  fn remove_matching(&mut self, predicate: &mut dyn FnMut(&dyn Node<T>) -> bool) -> usize {
+    let before = self.children.len();
+    self.children.retain(|child| !predicate(&**child));
+    let mut removed = before - self.children.len();
+    for child in self.children.iter_mut() {
+      if let Some(tree) = child.as_tree_mut() {
+        removed += tree.remove_matching(predicate);
+      }
     }
-    println!("{})", prefix);
+    removed
+  }
+}
+
+impl<T> Node<T> for Tree<T> {
+  fn name(&self) -> &str {
+    &self.name
+  }
+
+  fn is_branch(&self) -> bool {
+    true
+  }
+
+  fn children(&self) -> Vec<&dyn Node<T>> {
+    self.children.iter().map(|child| &**child).collect()
+  }
+
+  fn as_tree_mut(&mut self) -> Option<&mut Tree<T>> {
+    Some(self)
   }
 }
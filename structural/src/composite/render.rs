@@ -0,0 +1,133 @@
+//! `render` module.
+//!
+//! Draws any `Node` as ASCII tree art, as a Graphviz DOT graph or as a
+//! Mermaid flowchart. Nodes are labelled with their name, or with a custom
+//! label through the `_with` variants.
+//!
+//! # Example
+//!
+//! ```rust
+//! use structural::composite::*;
+//! use structural::composite::render::*;
+//!
+//! let mut sales = Tree::named("sales");
+//! sales.add(Box::new(Leaf::named("Alice", 52)));
+//! sales.add(Box::new(Leaf::named("Carol", 38)));
+//! let mut company = Tree::named("company");
+//! company.add(Box::new(sales));
+//! company.add(Box::new(Leaf::named("Bob", 41)));
+//!
+//! assert_eq!(ascii(&company), "\
+//! company
+//! ├── sales
+//! │   ├── Alice
+//! │   └── Carol
+//! └── Bob
+//! ");
+//! assert!(ascii_with(&company, name_and_value).contains("└── Bob: 41"));
+//!
+//! let dot = dot(&company);
+//! assert!(dot.starts_with("digraph tree {\n  n0 [label=\"company\"];\n"));
+//! assert!(dot.contains("  n1 -> n3;\n"));
+//! assert!(dot.contains("  n4 [label=\"Bob\", shape=box];\n"));
+//!
+//! let mermaid = mermaid(&company);
+//! assert!(mermaid.starts_with("graph TD\n  n0[\"company\"]\n"));
+//! assert!(mermaid.contains("  n0 --> n4\n"));
+//! ```
+
+use std::fmt::Display;
+
+use composite::Node;
+
+/// Default label: the name of the node.
+pub fn name<T>(node: &dyn Node<T>) -> String {
+  String::from(node.name())
+}
+
+/// Label `name: value` for leaves and the name for branches.
+pub fn name_and_value<T: Display>(node: &dyn Node<T>) -> String {
+  match node.value() {
+    Some(value) => format!("{}: {}", node.name(), value),
+    None => String::from(node.name()),
+  }
+}
+
+pub fn ascii<T>(node: &dyn Node<T>) -> String {
+  ascii_with(node, name)
+}
+
+/// Draws the tree with `├──`, `└──` and `│` guides, one node per line.
+pub fn ascii_with<T, F: Fn(&dyn Node<T>) -> String>(node: &dyn Node<T>, label: F) -> String {
+  let mut art = format!("{}\n", label(node));
+  ascii_children(node, "", &label, &mut art);
+  art
+}
+
+fn ascii_children<T>(node: &dyn Node<T>, indent: &str, label: &dyn Fn(&dyn Node<T>) -> String, art: &mut String) {
+  let children = node.children();
+  let count = children.len();
+  for (index, child) in children.into_iter().enumerate() {
+    let last = index + 1 == count;
+    art.push_str(indent);
+    art.push_str(if last { "└── " } else { "├── " });
+    art.push_str(&label(child));
+    art.push('\n');
+    let indent = format!("{}{}", indent, if last { "    " } else { "│   " });
+    ascii_children(child, &indent, label, art);
+  }
+}
+
+pub fn dot<T>(node: &dyn Node<T>) -> String {
+  dot_with(node, name)
+}
+
+/// Writes a Graphviz `digraph`; nodes are numbered depth first and leaves
+/// are drawn as boxes.
+pub fn dot_with<T, F: Fn(&dyn Node<T>) -> String>(node: &dyn Node<T>, label: F) -> String {
+  let mut graph = String::from("digraph tree {\n");
+  let mut next = 0;
+  dot_node(node, &label, &mut next, &mut graph);
+  graph.push_str("}\n");
+  graph
+}
+
+fn dot_node<T>(node: &dyn Node<T>, label: &dyn Fn(&dyn Node<T>) -> String, next: &mut usize, graph: &mut String) -> usize {
+  let id = *next;
+  *next += 1;
+  let text = label(node).replace('\\', "\\\\").replace('"', "\\\"");
+  if node.is_branch() {
+    graph.push_str(&format!("  n{} [label=\"{}\"];\n", id, text));
+  } else {
+    graph.push_str(&format!("  n{} [label=\"{}\", shape=box];\n", id, text));
+  }
+  for child in node.children() {
+    let child_id = dot_node(child, label, next, graph);
+    graph.push_str(&format!("  n{} -> n{};\n", id, child_id));
+  }
+  id
+}
+
+pub fn mermaid<T>(node: &dyn Node<T>) -> String {
+  mermaid_with(node, name)
+}
+
+/// Writes a top-down Mermaid flowchart; nodes are numbered depth first.
+pub fn mermaid_with<T, F: Fn(&dyn Node<T>) -> String>(node: &dyn Node<T>, label: F) -> String {
+  let mut chart = String::from("graph TD\n");
+  let mut next = 0;
+  mermaid_node(node, &label, &mut next, &mut chart);
+  chart
+}
+
+fn mermaid_node<T>(node: &dyn Node<T>, label: &dyn Fn(&dyn Node<T>) -> String, next: &mut usize, chart: &mut String) -> usize {
+  let id = *next;
+  *next += 1;
+  let text = label(node).replace('"', "#quot;");
+  chart.push_str(&format!("  n{}[\"{}\"]\n", id, text));
+  for child in node.children() {
+    let child_id = mermaid_node(child, label, next, chart);
+    chart.push_str(&format!("  n{} --> n{}\n", id, child_id));
+  }
+  id
+}
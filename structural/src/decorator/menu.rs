@@ -0,0 +1,627 @@
+//! `menu` module.
+//!
+//! A data-driven catalog of desserts: base items and toppings are loaded
+//! from a JSON or CSV menu file, priced with exact decimal `Money`, and
+//! stacked as decorators checked against per-topping limits and
+//! incompatibility rules.
+//!
+//! A JSON menu looks like:
+//!
+//! ```json
+//! {
+//!   "currency": "EUR",
+//!   "bases": [{ "name": "Crepe", "price": "1.00" }],
+//!   "toppings": [{ "name": "Chantilly", "price": "0.30", "max": 1 }],
+//!   "incompatible": [["Chantilly", "Caramel"]]
+//! }
+//! ```
+//!
+//! A CSV menu has the columns `kind,name,price,currency,max,incompatible_with`
+//! where `kind` is `base` or `topping` and `incompatible_with` is a `;`
+//! separated list of item names.
+//!
+//! # Example
+//!
+//! ```rust
+//! use structural::decorator::desserts::Dessert;
+//! use structural::decorator::menu::*;
+//!
+//! let menu = Menu::from_json(r#"{
+//!   "currency": "EUR",
+//!   "bases": [{ "name": "Crepe", "price": "1.00" }, { "name": "Gauffre", "price": 1.5 }],
+//!   "toppings": [
+//!     { "name": "Chantilly", "price": "0.30", "max": 1 },
+//!     { "name": "Chocolat", "price": "0.20", "max": 2 },
+//!     { "name": "Caramel", "price": "0.25" }
+//!   ],
+//!   "incompatible": [["Chocolat", "Caramel"]]
+//! }"#).unwrap();
+//!
+//! let crepe = menu.dessert("Crepe").unwrap()
+//!   .topping("Chocolat").unwrap()
+//!   .topping("Chocolat").unwrap()
+//!   .topping("Chantilly").unwrap()
+//!   .build();
+//! assert_eq!(crepe.price().unwrap(), Money::parse("1.70", Currency::EUR).unwrap());
+//! assert_eq!(crepe.get_label(), "Crepe Chocolat Chocolat Chantilly");
+//! println!("{}", crepe.receipt().unwrap());
+//!
+//! let builder = menu.dessert("Gauffre").unwrap().topping("Chantilly").unwrap();
+//! assert!(builder.topping("Chantilly").is_err());
+//! let builder = menu.dessert("Gauffre").unwrap().topping("Caramel").unwrap();
+//! assert_eq!(
+//!   builder.topping("Chocolat").err().unwrap().to_string(),
+//!   "Chocolat cannot be combined with Caramel"
+//! );
+//!
+//! let menu = Menu::load("../ressources/menu.csv").unwrap();
+//! let gauffre = menu.dessert("Gauffre").unwrap().topping("Caramel").unwrap().build();
+//! assert_eq!(gauffre.receipt().unwrap().total.to_string(), "1.75 EUR");
+//! assert!(Money::parse("0.305", Currency::EUR).is_err());
+//! let max = Money::from_minor(i64::MAX, Currency::EUR);
+//! assert!(matches!(max.checked_add(Money::from_minor(1, Currency::EUR)), Err(MenuError::Overflow)));
+//! assert!(matches!(max.times(2), Err(MenuError::Overflow)));
+//!
+//! let menu = Menu::from_json(&format!(r#"{{
+//!   "currency": "EUR",
+//!   "bases": [{{ "name": "Crepe", "price": "{}" }}],
+//!   "toppings": [{{ "name": "Gold", "price": "0.01" }}]
+//! }}"#, max.amount())).unwrap();
+//! assert!(matches!(menu.dessert("Crepe").unwrap().topping("Gold"), Err(MenuError::Overflow)));
+//! ```
+
+use std::collections::HashMap;
+use std::convert::TryFrom;
+use std::error::Error;
+use std::fmt;
+use std::fs::File;
+use std::io::{self, Read};
+use std::path::Path;
+use csv;
+use serde::{Serialize, Serializer};
+use serde::ser::SerializeStruct;
+use serde_json;
+use decorator::desserts::Dessert;
+
+/// Currency of an amount, with its number of decimal digits.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Currency {
+  code: [u8; 3],
+  decimals: u32,
+}
+
+impl Currency {
+  pub const EUR: Currency = Currency { code: *b"EUR", decimals: 2 };
+  pub const USD: Currency = Currency { code: *b"USD", decimals: 2 };
+  pub const GBP: Currency = Currency { code: *b"GBP", decimals: 2 };
+  pub const CHF: Currency = Currency { code: *b"CHF", decimals: 2 };
+  pub const JPY: Currency = Currency { code: *b"JPY", decimals: 0 };
+
+  /// Creates a currency from a three uppercase letters code.
+  pub fn new(code: &str, decimals: u32) -> Option<Currency> {
+    let bytes = code.as_bytes();
+    if bytes.len() != 3 || !bytes.iter().all(u8::is_ascii_uppercase) || decimals > 6 {
+      return None;
+    }
+    Some(Currency { code: [bytes[0], bytes[1], bytes[2]], decimals })
+  }
+
+  /// Looks up one of the known currencies.
+  pub fn from_code(code: &str) -> Option<Currency> {
+    [Currency::EUR, Currency::USD, Currency::GBP, Currency::CHF, Currency::JPY].iter()
+      .find(|c| c.code() == code.trim())
+      .cloned()
+  }
+
+  pub fn code(&self) -> &str {
+    ::std::str::from_utf8(&self.code).unwrap_or("???")
+  }
+
+  pub fn decimals(&self) -> u32 {
+    self.decimals
+  }
+}
+
+impl fmt::Display for Currency {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    write!(f, "{}", self.code())
+  }
+}
+
+/// An exact amount of money, counted in minor units (cents for EUR).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Money {
+  minor: i64,
+  currency: Currency,
+}
+
+impl Money {
+  pub fn from_minor(minor: i64, currency: Currency) -> Money {
+    Money { minor, currency }
+  }
+
+  pub fn zero(currency: Currency) -> Money {
+    Money::from_minor(0, currency)
+  }
+
+  /// Parses a decimal amount such as `1.50`, refusing more decimal digits
+  /// than the currency has.
+  pub fn parse(text: &str, currency: Currency) -> Result<Money, MenuError> {
+    let invalid = || MenuError::InvalidAmount(String::from(text));
+    let trimmed = text.trim();
+    let (negative, digits) = match trimmed.strip_prefix('-') {
+      Some(digits) => (true, digits),
+      None => (false, trimmed),
+    };
+    let (units, fraction) = match digits.find('.') {
+      Some(dot) => (&digits[..dot], &digits[dot + 1..]),
+      None => (digits, ""),
+    };
+    let all_digits = |s: &str| s.bytes().all(|b| b.is_ascii_digit());
+    if units.is_empty() || !all_digits(units) || !all_digits(fraction)
+      || fraction.len() as u32 > currency.decimals || (digits.contains('.') && fraction.is_empty()) {
+      return Err(invalid());
+    }
+    let scale = 10i64.pow(currency.decimals);
+    let units: i64 = units.parse().map_err(|_| invalid())?;
+    let fraction: i64 = if fraction.is_empty() {
+      0
+    } else {
+      fraction.parse::<i64>().map_err(|_| invalid())? * 10i64.pow(currency.decimals - fraction.len() as u32)
+    };
+    let minor = units.checked_mul(scale).and_then(|u| u.checked_add(fraction)).ok_or_else(invalid)?;
+    Ok(Money::from_minor(if negative { -minor } else { minor }, currency))
+  }
+
+  pub fn minor(&self) -> i64 {
+    self.minor
+  }
+
+  pub fn currency(&self) -> Currency {
+    self.currency
+  }
+
+  /// Adds two amounts of the same currency, failing instead of overflowing.
+  pub fn checked_add(&self, other: Money) -> Result<Money, MenuError> {
+    if self.currency != other.currency {
+      return Err(MenuError::CurrencyMismatch { expected: self.currency, found: other.currency });
+    }
+    let minor = self.minor.checked_add(other.minor).ok_or(MenuError::Overflow)?;
+    Ok(Money::from_minor(minor, self.currency))
+  }
+
+  /// Approximates the amount as a float, for the `Dessert` trait.
+  pub fn to_f32(&self) -> f32 {
+    (self.minor as f64 / 10f64.powi(self.currency.decimals as i32)) as f32
+  }
+
+  /// Rounds a float price, as returned by `Dessert::get_price`, to the
+  /// nearest minor unit.
+  pub fn from_f32(amount: f32, currency: Currency) -> Money {
+    let minor = (amount as f64 * 10f64.powi(currency.decimals as i32)).round() as i64;
+    Money::from_minor(minor, currency)
+  }
+
+  /// Multiplies the amount, failing instead of overflowing.
+  pub fn times(&self, quantity: i64) -> Result<Money, MenuError> {
+    let minor = self.minor.checked_mul(quantity).ok_or(MenuError::Overflow)?;
+    Ok(Money::from_minor(minor, self.currency))
+  }
+
+  /// Applies a rate given in basis points (1/100 of a percent), rounding
+  /// half away from zero to the minor unit, failing if the result is out
+  /// of range.
+  pub fn basis_points(&self, rate: i64) -> Result<Money, MenuError> {
+    let product = self.minor as i128 * rate as i128;
+    let rounded = (product.abs() + 5_000) / 10_000;
+    let minor = i64::try_from(if product < 0 { -rounded } else { rounded }).map_err(|_| MenuError::Overflow)?;
+    Ok(Money::from_minor(minor, self.currency))
+  }
+
+  /// The amount without its currency, e.g. `1.50`.
+  pub fn amount(&self) -> String {
+    let text = self.to_string();
+    String::from(text.trim_end_matches(self.currency.code()).trim_end())
+  }
+}
+
+impl Serialize for Currency {
+  fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(self.code())
+  }
+}
+
+impl Serialize for Money {
+  fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+    let mut money = serializer.serialize_struct("Money", 2)?;
+    money.serialize_field("amount", &self.amount())?;
+    money.serialize_field("currency", &self.currency)?;
+    money.end()
+  }
+}
+
+impl fmt::Display for Money {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    let scale = 10u64.pow(self.currency.decimals);
+    let sign = if self.minor < 0 { "-" } else { "" };
+    let minor = self.minor.unsigned_abs();
+    if self.currency.decimals == 0 {
+      write!(f, "{}{} {}", sign, minor, self.currency)
+    } else {
+      write!(f, "{}{}.{:0width$} {}", sign, minor / scale, minor % scale, self.currency,
+        width = self.currency.decimals as usize)
+    }
+  }
+}
+
+/// Error raised while loading a menu or composing a dessert.
+#[derive(Debug)]
+pub enum MenuError {
+  Io(io::Error),
+  Json(serde_json::Error),
+  Csv(csv::Error),
+  /// The menu file extension is neither `json` nor `csv`.
+  UnsupportedFormat(String),
+  InvalidAmount(String),
+  UnknownCurrency(String),
+  CurrencyMismatch { expected: Currency, found: Currency },
+  /// An amount too large to be represented.
+  Overflow,
+  /// A CSV row whose kind is neither `base` nor `topping`.
+  InvalidKind(String),
+  DuplicateItem(String),
+  UnknownBase(String),
+  UnknownTopping(String),
+  ToppingLimit { topping: String, max: u32 },
+  Incompatible { topping: String, with: String },
+}
+
+impl fmt::Display for MenuError {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match *self {
+      MenuError::Io(ref e) => write!(f, "cannot read the menu: {}", e),
+      MenuError::Json(ref e) => write!(f, "invalid JSON menu: {}", e),
+      MenuError::Csv(ref e) => write!(f, "invalid CSV menu: {}", e),
+      MenuError::UnsupportedFormat(ref path) => write!(f, "unsupported menu format: {}", path),
+      MenuError::InvalidAmount(ref amount) => write!(f, "invalid amount `{}`", amount),
+      MenuError::UnknownCurrency(ref code) => write!(f, "unknown currency `{}`", code),
+      MenuError::CurrencyMismatch { expected, found } =>
+        write!(f, "expected an amount in {}, found {}", expected, found),
+      MenuError::Overflow => write!(f, "amount out of range"),
+      MenuError::InvalidKind(ref kind) => write!(f, "unknown item kind `{}`", kind),
+      MenuError::DuplicateItem(ref name) => write!(f, "{} is listed twice", name),
+      MenuError::UnknownBase(ref name) => write!(f, "{} is not on the menu", name),
+      MenuError::UnknownTopping(ref name) => write!(f, "{} is not a topping on the menu", name),
+      MenuError::ToppingLimit { ref topping, max } => write!(f, "{} can be added at most {} time(s)", topping, max),
+      MenuError::Incompatible { ref topping, ref with } => write!(f, "{} cannot be combined with {}", topping, with),
+    }
+  }
+}
+
+impl Error for MenuError {
+  fn source(&self) -> Option<&(dyn Error + 'static)> {
+    match *self {
+      MenuError::Io(ref e) => Some(e),
+      MenuError::Json(ref e) => Some(e),
+      MenuError::Csv(ref e) => Some(e),
+      _ => None,
+    }
+  }
+}
+
+impl From<io::Error> for MenuError {
+  fn from(error: io::Error) -> MenuError {
+    MenuError::Io(error)
+  }
+}
+
+impl From<serde_json::Error> for MenuError {
+  fn from(error: serde_json::Error) -> MenuError {
+    MenuError::Json(error)
+  }
+}
+
+impl From<csv::Error> for MenuError {
+  fn from(error: csv::Error) -> MenuError {
+    MenuError::Csv(error)
+  }
+}
+
+/// One line of a receipt: what a layer adds to the price.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ReceiptLine {
+  pub label: String,
+  pub amount: Money,
+}
+
+/// Itemized price of a dessert, one line per layer from the base outwards.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Receipt {
+  pub lines: Vec<ReceiptLine>,
+  pub total: Money,
+}
+
+impl fmt::Display for Receipt {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    for (i, line) in self.lines.iter().enumerate() {
+      let label = if i == 0 { line.label.clone() } else { format!("  + {}", line.label) };
+      writeln!(f, "{:<24}{:>14}", label, line.amount.to_string())?;
+    }
+    write!(f, "{:<24}{:>14}", "Total", self.total.to_string())
+  }
+}
+
+/// A dessert priced in exact money. Every layer of a menu dessert, from the
+/// base to the outermost topping, implements it.
+pub trait MenuItem: Dessert {
+  /// Total of every layer; fails if they overflow or mix currencies.
+  fn price(&self) -> Result<Money, MenuError>;
+
+  /// Contribution of every layer, from the base outwards.
+  fn layers(&self) -> Vec<ReceiptLine>;
+
+  fn receipt(&self) -> Result<Receipt, MenuError> {
+    Ok(Receipt { lines: self.layers(), total: self.price()? })
+  }
+}
+
+/// Base item of the menu, such as a crepe.
+pub struct Base {
+  name: String,
+  price: Money,
+}
+
+impl MenuItem for Base {
+  fn price(&self) -> Result<Money, MenuError> { Ok(self.price) }
+  fn layers(&self) -> Vec<ReceiptLine> {
+    vec![ReceiptLine { label: self.name.clone(), amount: self.price }]
+  }
+}
+
+impl Dessert for Base {
+  fn get_label(&self) -> String { self.name.clone() }
+  fn get_price(&self) -> f32 { self.price.to_f32() }
+  fn get_layers(&self) -> Vec<(String, f32)> {
+    self.layers().into_iter().map(|l| (l.label, l.amount.to_f32())).collect()
+  }
+}
+
+/// Topping decorator, priced from the menu.
+pub struct Topping {
+  item: Box<dyn MenuItem>,
+  name: String,
+  price: Money,
+}
+
+impl MenuItem for Topping {
+  fn price(&self) -> Result<Money, MenuError> {
+    self.item.price()?.checked_add(self.price)
+  }
+  fn layers(&self) -> Vec<ReceiptLine> {
+    let mut layers = self.item.layers();
+    layers.push(ReceiptLine { label: self.name.clone(), amount: self.price });
+    layers
+  }
+}
+
+impl Dessert for Topping {
+  fn get_label(&self) -> String {
+    format!("{} {}", self.item.get_label(), self.name)
+  }
+  /// Not a number if the price cannot be computed, which `DessertBuilder`
+  /// rules out.
+  fn get_price(&self) -> f32 {
+    MenuItem::price(self).map(|price| price.to_f32()).unwrap_or(f32::NAN)
+  }
+  fn get_layers(&self) -> Vec<(String, f32)> {
+    self.layers().into_iter().map(|l| (l.label, l.amount.to_f32())).collect()
+  }
+}
+
+#[derive(Debug, Clone)]
+struct ToppingEntry {
+  price: Money,
+  max: Option<u32>,
+}
+
+/// Catalog of base items and toppings, with the rules to combine them.
+#[derive(Debug, Clone)]
+pub struct Menu {
+  currency: Currency,
+  bases: HashMap<String, Money>,
+  toppings: HashMap<String, ToppingEntry>,
+  incompatible: Vec<(String, String)>,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum PriceFile {
+  Text(String),
+  Number(serde_json::Number),
+}
+
+impl PriceFile {
+  fn to_money(&self, currency: Currency) -> Result<Money, MenuError> {
+    match *self {
+      PriceFile::Text(ref text) => Money::parse(text, currency),
+      PriceFile::Number(ref number) => Money::parse(&number.to_string(), currency),
+    }
+  }
+}
+
+#[derive(Deserialize)]
+struct BaseFile {
+  name: String,
+  price: PriceFile,
+}
+
+#[derive(Deserialize)]
+struct ToppingFile {
+  name: String,
+  price: PriceFile,
+  #[serde(default)]
+  max: Option<u32>,
+}
+
+#[derive(Deserialize)]
+struct MenuFile {
+  currency: String,
+  bases: Vec<BaseFile>,
+  #[serde(default)]
+  toppings: Vec<ToppingFile>,
+  #[serde(default)]
+  incompatible: Vec<(String, String)>,
+}
+
+#[derive(Deserialize)]
+struct MenuRow {
+  kind: String,
+  name: String,
+  price: String,
+  currency: String,
+  #[serde(default)]
+  max: Option<u32>,
+  #[serde(default)]
+  incompatible_with: Option<String>,
+}
+
+impl Menu {
+  /// Creates an empty menu.
+  pub fn new(currency: Currency) -> Menu {
+    Menu {
+      currency,
+      bases: HashMap::new(),
+      toppings: HashMap::new(),
+      incompatible: Vec::new(),
+    }
+  }
+
+  /// Loads a menu, choosing the format from the `.json` or `.csv` extension.
+  pub fn load<P: AsRef<Path>>(path: P) -> Result<Menu, MenuError> {
+    let path = path.as_ref();
+    let mut text = String::new();
+    match path.extension().and_then(|e| e.to_str()) {
+      Some("json") => {
+        File::open(path)?.read_to_string(&mut text)?;
+        Menu::from_json(&text)
+      }
+      Some("csv") => Menu::from_csv(File::open(path)?),
+      _ => Err(MenuError::UnsupportedFormat(path.display().to_string())),
+    }
+  }
+
+  pub fn from_json(json: &str) -> Result<Menu, MenuError> {
+    let file: MenuFile = serde_json::from_str(json)?;
+    let currency = Currency::from_code(&file.currency).ok_or(MenuError::UnknownCurrency(file.currency))?;
+    let mut menu = Menu::new(currency);
+    for base in file.bases {
+      let price = base.price.to_money(currency)?;
+      menu.add_base(&base.name, price)?;
+    }
+    for topping in file.toppings {
+      let price = topping.price.to_money(currency)?;
+      menu.add_topping(&topping.name, price, topping.max)?;
+    }
+    for (a, b) in file.incompatible {
+      menu.add_incompatibility(&a, &b);
+    }
+    Ok(menu)
+  }
+
+  pub fn from_csv<R: Read>(reader: R) -> Result<Menu, MenuError> {
+    let mut menu: Option<Menu> = None;
+    for row in csv::Reader::from_reader(reader).deserialize() {
+      let row: MenuRow = row?;
+      let currency = Currency::from_code(&row.currency).ok_or_else(|| MenuError::UnknownCurrency(row.currency.clone()))?;
+      let menu = menu.get_or_insert_with(|| Menu::new(currency));
+      let price = Money::parse(&row.price, currency)?;
+      match row.kind.trim() {
+        "base" => menu.add_base(&row.name, price)?,
+        "topping" => menu.add_topping(&row.name, price, row.max)?,
+        kind => return Err(MenuError::InvalidKind(String::from(kind))),
+      }
+      for other in row.incompatible_with.iter().flat_map(|list| list.split(';')) {
+        if !other.trim().is_empty() {
+          menu.add_incompatibility(&row.name, other.trim());
+        }
+      }
+    }
+    Ok(menu.unwrap_or_else(|| Menu::new(Currency::EUR)))
+  }
+
+  pub fn currency(&self) -> Currency {
+    self.currency
+  }
+
+  pub fn add_base(&mut self, name: &str, price: Money) -> Result<(), MenuError> {
+    self.check_new_item(name, price)?;
+    self.bases.insert(String::from(name), price);
+    Ok(())
+  }
+
+  /// Adds a topping which can be put at most `max` times on a dessert.
+  pub fn add_topping(&mut self, name: &str, price: Money, max: Option<u32>) -> Result<(), MenuError> {
+    self.check_new_item(name, price)?;
+    self.toppings.insert(String::from(name), ToppingEntry { price, max });
+    Ok(())
+  }
+
+  /// Forbids `a` and `b` on the same dessert, whether bases or toppings.
+  pub fn add_incompatibility(&mut self, a: &str, b: &str) {
+    self.incompatible.push((String::from(a), String::from(b)));
+  }
+
+  fn check_new_item(&self, name: &str, price: Money) -> Result<(), MenuError> {
+    if price.currency() != self.currency {
+      return Err(MenuError::CurrencyMismatch { expected: self.currency, found: price.currency() });
+    }
+    if self.bases.contains_key(name) || self.toppings.contains_key(name) {
+      return Err(MenuError::DuplicateItem(String::from(name)));
+    }
+    Ok(())
+  }
+
+  fn incompatible_with(&self, a: &str, b: &str) -> bool {
+    self.incompatible.iter().any(|(x, y)| (x == a && y == b) || (x == b && y == a))
+  }
+
+  /// Starts a dessert from one of the base items.
+  pub fn dessert(&self, base: &str) -> Result<DessertBuilder<'_>, MenuError> {
+    let price = *self.bases.get(base).ok_or_else(|| MenuError::UnknownBase(String::from(base)))?;
+    Ok(DessertBuilder {
+      menu: self,
+      names: vec![String::from(base)],
+      item: Box::new(Base { name: String::from(base), price }),
+    })
+  }
+}
+
+/// Stacks toppings on a base, enforcing the menu rules at each step.
+pub struct DessertBuilder<'a> {
+  menu: &'a Menu,
+  names: Vec<String>,
+  item: Box<dyn MenuItem>,
+}
+
+impl<'a> DessertBuilder<'a> {
+  pub fn topping(mut self, name: &str) -> Result<DessertBuilder<'a>, MenuError> {
+    let entry = self.menu.toppings.get(name).ok_or_else(|| MenuError::UnknownTopping(String::from(name)))?;
+    if let Some(max) = entry.max {
+      if self.names.iter().filter(|n| *n == name).count() as u32 >= max {
+        return Err(MenuError::ToppingLimit { topping: String::from(name), max });
+      }
+    }
+    if let Some(other) = self.names.iter().find(|n| self.menu.incompatible_with(name, n)) {
+      return Err(MenuError::Incompatible { topping: String::from(name), with: other.clone() });
+    }
+    let item = Topping { item: self.item, name: String::from(name), price: entry.price };
+    item.price()?;
+    self.names.push(String::from(name));
+    self.item = Box::new(item);
+    Ok(self)
+  }
+
+  pub fn build(self) -> Box<dyn MenuItem> {
+    self.item
+  }
+}
@@ -1,6 +1,11 @@
 //! `decorator` module.
 //!
  
+pub mod menu;
+pub mod order;
+pub mod pipeline;
+pub mod stack;
+
 pub mod desserts {
   //! `desserts` module.
   //! 
@@ -22,12 +27,18 @@ pub mod desserts {
   //! println!("{}", dessert.to_string());
   //! ```
 
+  use decorator::stack::{DecoratorStack, Layer};
+
   pub trait Dessert {
     fn get_label(&self) -> String;
     fn get_price(&self) -> f32;
     fn to_string(&self) -> String {
       format!("{}: {}", self.get_label(), self.get_price())
     } 
+    /// Label and price added by each layer, from the base outwards.
+    fn get_layers(&self) -> Vec<(String, f32)> {
+      vec![(self.get_label(), self.get_price())]
+    }
   }
 
   struct ClassDessert {
@@ -76,7 +87,7 @@ pub mod desserts {
   }
   impl Crepe {
     pub fn new() -> Box<Dessert> {
-      Box::new(Gauffre{
+      Box::new(Crepe {
         dessert: ClassDessert::new(String::from("Crepe"), 1.0),
       })
     }
@@ -101,6 +112,9 @@ pub mod desserts {
           dessert,
         })
     }
+    pub fn layer() -> Layer<Box<dyn Dessert>> {
+      Layer::new::<Chantilly, _>("Chantilly", Chantilly::new).with_metadata("price", "0.3")
+    }
   }
 
   impl Dessert for Chantilly {
@@ -110,6 +124,11 @@ pub mod desserts {
     fn get_price(&self) -> f32 {
       self.dessert.get_price() + 0.3
     }
+    fn get_layers(&self) -> Vec<(String, f32)> {
+      let mut layers = self.dessert.get_layers();
+      layers.push((String::from("Chantilly"), 0.3));
+      layers
+    }
   }
 
 
@@ -124,6 +143,9 @@ pub mod desserts {
           dessert,
         })
     }
+    pub fn layer() -> Layer<Box<dyn Dessert>> {
+      Layer::new::<Chocolat, _>("Chocolat", Chocolat::new).with_metadata("price", "0.2")
+    }
   }
 
   impl Dessert for Chocolat {
@@ -133,105 +155,661 @@ pub mod desserts {
     fn get_price(&self) -> f32 {
       self.dessert.get_price() + 0.2
     }
+    fn get_layers(&self) -> Vec<(String, f32)> {
+      let mut layers = self.dessert.get_layers();
+      layers.push((String::from("Chocolat"), 0.2));
+      layers
+    }
+  }
+
+  impl Dessert for DecoratorStack<Box<dyn Dessert>> {
+    fn get_label(&self) -> String { self.build().get_label() }
+    fn get_price(&self) -> f32 { self.build().get_price() }
+    fn to_string(&self) -> String { self.build().to_string() }
+    fn get_layers(&self) -> Vec<(String, f32)> { self.build().get_layers() }
   }
 }
 
 pub mod input_readers {
   //! `imput_readers` module.
   //! 
+  //! Readers stream their input: each decorator pulls one line at a time
+  //! from the reader it wraps, so nothing is materialized unless
+  //! `read_lines` is called. I/O errors are handed down the chain as `Err`
+  //! lines instead of ending the input.
+  //! 
+  //! `AdvancedInputReader` reads from a file, a gzip-compressed file, a list
+  //! of files concatenated like `cat`, stdin or an in-memory string, decoding
+  //! invalid UTF-8 according to a `Utf8Policy`. Like the reader it
+  //! replaced, it trims its lines; `open_untrimmed` keeps their leading and
+  //! trailing whitespace.
+  //! 
+  //! Besides `CapitalizedInputReader` and `LenghtInputReader`, decorators
+  //! are provided to filter (`GrepInputReader`), number, trim, skip blank
+  //! lines, deduplicate, keep the head or the tail, sample and cut fields.
+  //! They all compose with each other.
+  //! 
   //! # Example
   //! 
+  //! ```rust
+  //! use structural::adapter::io::IteratorInputReader;
+  //! use structural::decorator::input_readers::*;
+  //! 
+  //! let log = vec!["  INFO;start ", "", "ERROR;disk full", "ERROR;disk full", "WARN;cpu", "error;net down"];
+  //! let reader = Box::new(IteratorInputReader::new(log.into_iter().map(String::from)));
+  //! let reader = SkipBlankInputReader::new(TrimInputReader::new(reader));
+  //! let reader = DedupInputReader::new(CapitalizedInputReader::new(reader));
+  //! let reader = GrepInputReader::new(reader, "^ERROR").unwrap();
+  //! let reader = FieldInputReader::new(reader, ";", vec![2]);
+  //! let mut reader = NumberedInputReader::new(HeadInputReader::new(reader, 2));
+  //! assert_eq!(reader.read_lines().unwrap(), vec!["1: DISK FULL", "2: NET DOWN"]);
+  //! 
+  //! let reader = AdvancedInputReader::from_string("caf\u{e9}\r\nbar");
+  //! let mut reader = LenghtInputReader::new(reader);
+  //! assert_eq!(reader.read_lines().unwrap(), vec!["caf\u{e9}: 5", "bar: 3"]);
+  //! let mut trimmed = CapitalizedInputReader::new(AdvancedInputReader::from_string("  padded \n"));
+  //! assert_eq!(trimmed.read_lines().unwrap(), vec!["PADDED"]);
+  //! let padded = AdvancedInputReader::open_untrimmed(Source::Text(String::from("  padded \n")), Utf8Policy::Strict);
+  //! assert_eq!(padded.unwrap().read_lines().unwrap(), vec!["  padded "]);
+  //! 
+  //! assert!(AdvancedInputReader::new("missing.txt").is_err());
+  //! 
+  //! let bytes = b"ok\n\xffoops\n".to_vec();
+  //! let mut strict = AdvancedInputReader::open(Source::Bytes(bytes.clone()), Utf8Policy::Strict).unwrap();
+  //! assert_eq!(strict.next_line().unwrap().unwrap(), "ok");
+  //! assert!(strict.next_line().unwrap().is_err());
+  //! let mut lossy = AdvancedInputReader::open(Source::Bytes(bytes), Utf8Policy::Lossy).unwrap();
+  //! assert_eq!(lossy.read_lines().unwrap(), vec!["ok", "\u{fffd}oops"]);
+  //! 
+  //! // An error on a skipped line is not lost.
+  //! let bytes = b"a\n\xff\nc\nd\n".to_vec();
+  //! let mut sample = SampleInputReader::new(AdvancedInputReader::open(Source::Bytes(bytes), Utf8Policy::Strict).unwrap(), 2);
+  //! assert_eq!(sample.next_line().unwrap().unwrap(), "a");
+  //! assert!(sample.next_line().unwrap().is_err());
+  //! assert_eq!(sample.next_line().unwrap().unwrap(), "c");
+  //! assert!(sample.next_line().is_none());
+  //! ```
+  //! 
+  //! ```rust
+  //! extern crate flate2;
+  //! extern crate structural;
+  //! 
+  //! use std::fs::File;
+  //! use std::io::Write;
+  //! use flate2::Compression;
+  //! use flate2::write::GzEncoder;
+  //! use structural::decorator::input_readers::*;
+  //! 
+  //! let gz = std::env::temp_dir().join("structural-input-readers-doc.txt.gz");
+  //! let mut encoder = GzEncoder::new(File::create(&gz).unwrap(), Compression::default());
+  //! encoder.write_all(b"I'm compressed\n").unwrap();
+  //! encoder.finish().unwrap();
+  //! 
+  //! let mut reader = AdvancedInputReader::concat(vec![
+  //!   gz.clone(),
+  //!   "../ressources/missing.txt".into(),
+  //!   "../ressources/file2.txt".into(),
+  //! ]).unwrap();
+  //! assert_eq!(reader.next_line().unwrap().unwrap(), "I'm compressed");
+  //! assert!(reader.next_line().unwrap().is_err());
+  //! assert_eq!(reader.next_line().unwrap().unwrap(), "I'm file2");
+  //! assert!(reader.next_line().is_none());
+  //! ```
+  //! 
   //! ```rust,ignore
   //! use structural::decorator::input_readers::*;
   //! 
-  //! let reader = AdvancedInputReader::new("input.txt");
+  //! let reader = AdvancedInputReader::concat(vec!["file1.txt", "file2.txt.gz"])?;
   //! let mut reader = CapitalizedInputReader::new(reader);
-  //! let res = reader.read_lines();
-  //! for r in res.iter() {
-  //!     println!("{}", r);
+  //! for r in reader.lines() {
+  //!     println!("{}", r?);
   //! }
   //! 
-  //! let reader = AdvancedInputReader::new("input.txt");
+  //! let reader = AdvancedInputReader::from_gzip("input.txt.gz")?;
   //! let reader = CapitalizedInputReader::new(reader);
   //! let mut reader = LenghtInputReader::new(reader);
-  //! let res = reader.read_lines();
+  //! let res = reader.read_lines()?;
   //! for r in res.iter() {
   //!     println!("{}", r);
   //! }
   //! ```
 
-  use std::io::BufReader;
+  // Decorators are handed out boxed, ready to be wrapped again.
+  #![allow(clippy::new_ret_no_self)]
+
+  use std::io::{self, BufReader, Cursor};
   use std::fs::File;
   use std::io::BufRead;
+  use std::path::PathBuf;
+  use std::collections::{HashSet, VecDeque};
+  use flate2::read::MultiGzDecoder;
+  use regex::{self, Regex};
+  use decorator::stack::Layer;
 
   pub trait InputReader {
-    fn read_lines(&mut self) -> Vec<String>;
+    /// Returns the next line, or `None` once the input is exhausted.
+    ///
+    /// An `Err` does not end the input: reading may go on with the
+    /// following lines.
+    fn next_line(&mut self) -> Option<io::Result<String>>;
+
+    /// Iterates over the remaining lines.
+    fn lines(&mut self) -> Lines<'_, Self> where Self: Sized {
+      Lines { input_reader: self }
+    }
+
+    /// Collects the remaining lines, stopping at the first error.
+    fn read_lines(&mut self) -> io::Result<Vec<String>> {
+      let mut v: Vec<String> = Vec::new();
+      while let Some(line) = self.next_line() {
+        v.push(line?);
+      }
+      Ok(v)
+    }
+  }
+
+  impl<R: InputReader + ?Sized> InputReader for Box<R> {
+    fn next_line(&mut self) -> Option<io::Result<String>> {
+      (**self).next_line()
+    }
+  }
+
+  /// Iterator over the lines of an `InputReader`, see `InputReader::lines`.
+  pub struct Lines<'a, R: InputReader + ?Sized + 'a> {
+    input_reader: &'a mut R,
+  }
+
+  impl<'a, R: InputReader + ?Sized> Iterator for Lines<'a, R> {
+    type Item = io::Result<String>;
+
+    fn next(&mut self) -> Option<io::Result<String>> {
+      self.input_reader.next_line()
+    }
+  }
+
+  /// How bytes which are not valid UTF-8 are handled.
+  #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+  pub enum Utf8Policy {
+    /// The line is reported as an `InvalidData` error.
+    Strict,
+    /// Invalid sequences are replaced with `U+FFFD`.
+    Lossy,
   }
 
-  #[derive(Debug)]
+  /// Where an `AdvancedInputReader` reads its lines from.
+  #[derive(Debug, Clone)]
+  pub enum Source {
+    File(PathBuf),
+    /// A gzip-compressed file, possibly made of several members.
+    Gzip(PathBuf),
+    /// Files read one after the other, like `cat`. Files ending in `.gz`
+    /// are decompressed.
+    Files(Vec<PathBuf>),
+    Stdin,
+    Text(String),
+    Bytes(Vec<u8>),
+  }
+
+  /// Reads trimmed lines, or only without their `\n` or `\r\n` ending
+  /// when opened with `open_untrimmed`.
   pub struct AdvancedInputReader {
-    pub reader: BufReader<File>,
+    reader: Option<Box<dyn BufRead>>,
+    pending: VecDeque<PathBuf>,
+    policy: Utf8Policy,
+    trim: bool,
+    line: Vec<u8>,
+    number: usize,
   }
 
   impl AdvancedInputReader {
-    pub fn new(path: &str) -> Box<InputReader> {
-      let f = File::open(path).unwrap();
-      Box::new(AdvancedInputReader { reader: BufReader::new(f) })
+    /// Reads the file at `path`, rejecting invalid UTF-8.
+    pub fn new(path: &str) -> io::Result<Box<dyn InputReader>> {
+      AdvancedInputReader::open(Source::File(PathBuf::from(path)), Utf8Policy::Strict)
+    }
+
+    pub fn from_gzip(path: &str) -> io::Result<Box<dyn InputReader>> {
+      AdvancedInputReader::open(Source::Gzip(PathBuf::from(path)), Utf8Policy::Strict)
+    }
+
+    /// Reads the files one after the other. A file which cannot be opened is
+    /// reported as an `Err` line when reached, then the next file is read.
+    pub fn concat<P: Into<PathBuf>>(paths: Vec<P>) -> io::Result<Box<dyn InputReader>> {
+      let paths = paths.into_iter().map(Into::into).collect();
+      AdvancedInputReader::open(Source::Files(paths), Utf8Policy::Strict)
+    }
+
+    pub fn from_stdin() -> Box<dyn InputReader> {
+      Box::new(AdvancedInputReader::with_reader(Box::new(BufReader::new(io::stdin())), Utf8Policy::Strict))
+    }
+
+    pub fn from_string(text: &str) -> Box<dyn InputReader> {
+      Box::new(AdvancedInputReader::with_reader(Box::new(Cursor::new(text.as_bytes().to_vec())), Utf8Policy::Strict))
+    }
+
+    /// Opens `source`, decoding its lines according to `policy`.
+    pub fn open(source: Source, policy: Utf8Policy) -> io::Result<Box<dyn InputReader>> {
+      Ok(Box::new(AdvancedInputReader::open_reader(source, policy)?))
+    }
+
+    /// Like `open`, but keeps the whitespace around the lines.
+    pub fn open_untrimmed(source: Source, policy: Utf8Policy) -> io::Result<Box<dyn InputReader>> {
+      let reader = AdvancedInputReader::open_reader(source, policy)?;
+      Ok(Box::new(AdvancedInputReader { trim: false, ..reader }))
+    }
+
+    fn open_reader(source: Source, policy: Utf8Policy) -> io::Result<AdvancedInputReader> {
+      let reader = match source {
+        Source::File(path) => AdvancedInputReader::with_reader(open_file(&path, false)?, policy),
+        Source::Gzip(path) => AdvancedInputReader::with_reader(open_file(&path, true)?, policy),
+        Source::Files(paths) => AdvancedInputReader {
+          reader: None,
+          pending: paths.into_iter().collect(),
+          policy,
+          trim: true,
+          line: Vec::new(),
+          number: 0,
+        },
+        Source::Stdin => AdvancedInputReader::with_reader(Box::new(BufReader::new(io::stdin())), policy),
+        Source::Text(text) => AdvancedInputReader::with_reader(Box::new(Cursor::new(text.into_bytes())), policy),
+        Source::Bytes(bytes) => AdvancedInputReader::with_reader(Box::new(Cursor::new(bytes)), policy),
+      };
+      Ok(reader)
+    }
+
+    fn with_reader(reader: Box<dyn BufRead>, policy: Utf8Policy) -> AdvancedInputReader {
+      AdvancedInputReader {
+        reader: Some(reader),
+        pending: VecDeque::new(),
+        policy,
+        trim: true,
+        line: Vec::new(),
+        number: 0,
+      }
+    }
+
+    fn decode(&mut self) -> io::Result<String> {
+      while self.line.last() == Some(&b'\n') || self.line.last() == Some(&b'\r') {
+        self.line.pop();
+      }
+      let line = match self.policy {
+        Utf8Policy::Lossy => String::from_utf8_lossy(&self.line).into_owned(),
+        Utf8Policy::Strict => String::from_utf8(self.line.clone()).map_err(|e| io::Error::new(
+          io::ErrorKind::InvalidData,
+          format!("invalid UTF-8 on line {}: {}", self.number, e),
+        ))?,
+      };
+      Ok(if self.trim { String::from(line.trim()) } else { line })
+    }
+  }
+
+  fn open_file(path: &PathBuf, gzip: bool) -> io::Result<Box<dyn BufRead>> {
+    let f = File::open(path).map_err(|e| io::Error::new(e.kind(), format!("{}: {}", path.display(), e)))?;
+    if gzip {
+      Ok(Box::new(BufReader::new(MultiGzDecoder::new(f))))
+    } else {
+      Ok(Box::new(BufReader::new(f)))
     }
   }
 
   impl InputReader for AdvancedInputReader {
-    fn read_lines(&mut self) -> Vec<String> {
-      let mut v: Vec<String> = Vec::new();
-      let mut line = String::new();
-      while let Ok(num) = self.reader.read_line(&mut line)  {
-        if num == 0 { break };
-        v.push(String::from(line.clone().trim()));
-        line = String::new();
-      };
-      v
+    fn next_line(&mut self) -> Option<io::Result<String>> {
+      loop {
+        if self.reader.is_none() {
+          let path = self.pending.pop_front()?;
+          let gzip = path.extension().is_some_and(|e| e == "gz");
+          match open_file(&path, gzip) {
+            Ok(reader) => self.reader = Some(reader),
+            Err(e) => return Some(Err(e)),
+          }
+        }
+        self.line.clear();
+        let read = match self.reader {
+          Some(ref mut reader) => reader.read_until(b'\n', &mut self.line),
+          None => continue,
+        };
+        match read {
+          Ok(0) => self.reader = None,
+          Ok(_) => {
+            self.number += 1;
+            return Some(self.decode());
+          }
+          Err(e) => {
+            // The source cannot be trusted anymore, move on to the next one.
+            self.reader = None;
+            return Some(Err(e));
+          }
+        }
+      }
     }
   }
 
   pub struct CapitalizedInputReader {
-    input_reader: Box<InputReader>,
+    input_reader: Box<dyn InputReader>,
   }
 
   impl CapitalizedInputReader {
-    pub fn new(input_reader: Box<InputReader>) -> Box<InputReader> {
+    pub fn new(input_reader: Box<dyn InputReader>) -> Box<dyn InputReader> {
       Box::new(CapitalizedInputReader { input_reader, })
     }
+
+    pub fn layer() -> Layer<Box<dyn InputReader>> {
+      Layer::new::<CapitalizedInputReader, _>("upper", CapitalizedInputReader::new)
+    }
   }
 
   impl InputReader for CapitalizedInputReader {
-    fn read_lines(&mut self) -> Vec<String> {
-      let mut out: Vec<String> = Vec::new();
-      let reader = self.input_reader.read_lines().clone();
-      for r in reader {
-        out.push(r.to_uppercase());
-      }
-      out
+    fn next_line(&mut self) -> Option<io::Result<String>> {
+      self.input_reader.next_line().map(|r| r.map(|r| r.to_uppercase()))
     }
   }
 
   pub struct LenghtInputReader {
-    input_reader: Box<InputReader>,
+    input_reader: Box<dyn InputReader>,
   }
 
   impl LenghtInputReader {
-    pub fn new(input_reader: Box<InputReader>) -> Box<InputReader> {
+    pub fn new(input_reader: Box<dyn InputReader>) -> Box<dyn InputReader> {
       Box::new(LenghtInputReader { input_reader, })
     }
+
+    pub fn layer() -> Layer<Box<dyn InputReader>> {
+      Layer::new::<LenghtInputReader, _>("length", LenghtInputReader::new)
+    }
   }
 
   impl InputReader for LenghtInputReader {
-    fn read_lines(&mut self) -> Vec<String> {
-      let mut out: Vec<String> = Vec::new();
-      let reader = self.input_reader.read_lines().clone();
-      for r in reader {
-        out.push(format!("{}: {}",r, r.len()));
+    fn next_line(&mut self) -> Option<io::Result<String>> {
+      self.input_reader.next_line().map(|r| r.map(|r| format!("{}: {}", r, r.len())))
+    }
+  }
+
+  /// Keeps the lines matching a regular expression, like `grep`.
+  pub struct GrepInputReader {
+    input_reader: Box<dyn InputReader>,
+    regex: Regex,
+    invert: bool,
+  }
+
+  impl GrepInputReader {
+    pub fn new(input_reader: Box<dyn InputReader>, pattern: &str) -> Result<Box<dyn InputReader>, regex::Error> {
+      Ok(Box::new(GrepInputReader { input_reader, regex: Regex::new(pattern)?, invert: false }))
+    }
+
+    /// Keeps the lines which do not match, like `grep -v`.
+    pub fn invert(input_reader: Box<dyn InputReader>, pattern: &str) -> Result<Box<dyn InputReader>, regex::Error> {
+      Ok(Box::new(GrepInputReader { input_reader, regex: Regex::new(pattern)?, invert: true }))
+    }
+
+    /// Checks `pattern` once, the layer can then be built at will.
+    pub fn layer(pattern: &str) -> Result<Layer<Box<dyn InputReader>>, regex::Error> {
+      let regex = Regex::new(pattern)?;
+      Ok(Layer::new::<GrepInputReader, _>("grep", move |input_reader| {
+        Box::new(GrepInputReader { input_reader, regex: regex.clone(), invert: false }) as Box<dyn InputReader>
+      }).with_metadata("pattern", pattern))
+    }
+  }
+
+  impl InputReader for GrepInputReader {
+    fn next_line(&mut self) -> Option<io::Result<String>> {
+      loop {
+        match self.input_reader.next_line()? {
+          Ok(ref r) if self.regex.is_match(r) == self.invert => continue,
+          r => return Some(r),
+        }
       }
-      out
     }
   }
-}
+
+  /// Prefixes each line with its number, starting at 1.
+  pub struct NumberedInputReader {
+    input_reader: Box<dyn InputReader>,
+    number: usize,
+  }
+
+  impl NumberedInputReader {
+    pub fn new(input_reader: Box<dyn InputReader>) -> Box<dyn InputReader> {
+      Box::new(NumberedInputReader { input_reader, number: 0 })
+    }
+
+    pub fn layer() -> Layer<Box<dyn InputReader>> {
+      Layer::new::<NumberedInputReader, _>("number", NumberedInputReader::new)
+    }
+  }
+
+  impl InputReader for NumberedInputReader {
+    fn next_line(&mut self) -> Option<io::Result<String>> {
+      let r = self.input_reader.next_line()?;
+      self.number += 1;
+      Some(r.map(|r| format!("{}: {}", self.number, r)))
+    }
+  }
+
+  /// Removes leading and trailing whitespace.
+  pub struct TrimInputReader {
+    input_reader: Box<dyn InputReader>,
+  }
+
+  impl TrimInputReader {
+    pub fn new(input_reader: Box<dyn InputReader>) -> Box<dyn InputReader> {
+      Box::new(TrimInputReader { input_reader })
+    }
+
+    pub fn layer() -> Layer<Box<dyn InputReader>> {
+      Layer::new::<TrimInputReader, _>("trim", TrimInputReader::new)
+    }
+  }
+
+  impl InputReader for TrimInputReader {
+    fn next_line(&mut self) -> Option<io::Result<String>> {
+      self.input_reader.next_line().map(|r| r.map(|r| String::from(r.trim())))
+    }
+  }
+
+  /// Skips the lines made only of whitespace.
+  pub struct SkipBlankInputReader {
+    input_reader: Box<dyn InputReader>,
+  }
+
+  impl SkipBlankInputReader {
+    pub fn new(input_reader: Box<dyn InputReader>) -> Box<dyn InputReader> {
+      Box::new(SkipBlankInputReader { input_reader })
+    }
+
+    pub fn layer() -> Layer<Box<dyn InputReader>> {
+      Layer::new::<SkipBlankInputReader, _>("skip-blank", SkipBlankInputReader::new)
+    }
+  }
+
+  impl InputReader for SkipBlankInputReader {
+    fn next_line(&mut self) -> Option<io::Result<String>> {
+      loop {
+        match self.input_reader.next_line()? {
+          Ok(ref r) if r.trim().is_empty() => continue,
+          r => return Some(r),
+        }
+      }
+    }
+  }
+
+  /// Drops the lines already seen, keeping the first occurrence.
+  ///
+  /// Every distinct line is remembered, so memory grows with the number of
+  /// distinct lines.
+  pub struct DedupInputReader {
+    input_reader: Box<dyn InputReader>,
+    seen: HashSet<String>,
+  }
+
+  impl DedupInputReader {
+    pub fn new(input_reader: Box<dyn InputReader>) -> Box<dyn InputReader> {
+      Box::new(DedupInputReader { input_reader, seen: HashSet::new() })
+    }
+
+    pub fn layer() -> Layer<Box<dyn InputReader>> {
+      Layer::new::<DedupInputReader, _>("dedup", DedupInputReader::new)
+    }
+  }
+
+  impl InputReader for DedupInputReader {
+    fn next_line(&mut self) -> Option<io::Result<String>> {
+      loop {
+        match self.input_reader.next_line()? {
+          Ok(ref r) if self.seen.contains(r) => continue,
+          Ok(r) => {
+            self.seen.insert(r.clone());
+            return Some(Ok(r));
+          }
+          Err(e) => return Some(Err(e)),
+        }
+      }
+    }
+  }
+
+  /// Keeps the first `count` lines, like `head -n`.
+  pub struct HeadInputReader {
+    input_reader: Box<dyn InputReader>,
+    remaining: usize,
+  }
+
+  impl HeadInputReader {
+    pub fn new(input_reader: Box<dyn InputReader>, count: usize) -> Box<dyn InputReader> {
+      Box::new(HeadInputReader { input_reader, remaining: count })
+    }
+
+    pub fn layer(count: usize) -> Layer<Box<dyn InputReader>> {
+      Layer::new::<HeadInputReader, _>("head", move |r| HeadInputReader::new(r, count))
+        .with_metadata("count", &count.to_string())
+    }
+  }
+
+  impl InputReader for HeadInputReader {
+    fn next_line(&mut self) -> Option<io::Result<String>> {
+      if self.remaining == 0 {
+        return None;
+      }
+      self.remaining -= 1;
+      self.input_reader.next_line()
+    }
+  }
+
+  /// Keeps the last `count` lines, like `tail -n`.
+  ///
+  /// The wrapped reader is drained on the first call, holding at most
+  /// `count` lines in memory. Errors met while draining are returned as
+  /// they come.
+  pub struct TailInputReader {
+    input_reader: Box<dyn InputReader>,
+    count: usize,
+    buffer: VecDeque<String>,
+    drained: bool,
+  }
+
+  impl TailInputReader {
+    pub fn new(input_reader: Box<dyn InputReader>, count: usize) -> Box<dyn InputReader> {
+      Box::new(TailInputReader { input_reader, count, buffer: VecDeque::with_capacity(count), drained: false })
+    }
+
+    pub fn layer(count: usize) -> Layer<Box<dyn InputReader>> {
+      Layer::new::<TailInputReader, _>("tail", move |r| TailInputReader::new(r, count))
+        .with_metadata("count", &count.to_string())
+    }
+  }
+
+  impl InputReader for TailInputReader {
+    fn next_line(&mut self) -> Option<io::Result<String>> {
+      while !self.drained {
+        match self.input_reader.next_line() {
+          Some(Ok(r)) => {
+            if self.buffer.len() == self.count {
+              self.buffer.pop_front();
+            }
+            if self.count > 0 {
+              self.buffer.push_back(r);
+            }
+          }
+          Some(Err(e)) => return Some(Err(e)),
+          None => self.drained = true,
+        }
+      }
+      self.buffer.pop_front().map(Ok)
+    }
+  }
+
+  /// Keeps one line out of `every`, starting with the first one. An error
+  /// met on a skipped line is returned after the line kept before it.
+  pub struct SampleInputReader {
+    input_reader: Box<dyn InputReader>,
+    every: usize,
+    /// An error met while skipping, returned by the following call.
+    error: Option<io::Error>,
+  }
+
+  impl SampleInputReader {
+    pub fn new(input_reader: Box<dyn InputReader>, every: usize) -> Box<dyn InputReader> {
+      Box::new(SampleInputReader { input_reader, every: every.max(1), error: None })
+    }
+
+    pub fn layer(every: usize) -> Layer<Box<dyn InputReader>> {
+      Layer::new::<SampleInputReader, _>("sample", move |r| SampleInputReader::new(r, every))
+        .with_metadata("every", &every.to_string())
+    }
+  }
+
+  impl InputReader for SampleInputReader {
+    fn next_line(&mut self) -> Option<io::Result<String>> {
+      if let Some(e) = self.error.take() {
+        return Some(Err(e));
+      }
+      let r = self.input_reader.next_line()?;
+      for _ in 1..self.every {
+        match self.input_reader.next_line() {
+          None => break,
+          Some(Ok(_)) => {}
+          Some(Err(e)) => {
+            self.error = Some(e);
+            break;
+          }
+        }
+      }
+      Some(r)
+    }
+  }
+
+  /// Extracts fields split on a delimiter, like `cut -d -f`.
+  ///
+  /// Fields are numbered from 1 and joined back with the delimiter; fields
+  /// missing from a line are left out.
+  pub struct FieldInputReader {
+    input_reader: Box<dyn InputReader>,
+    delimiter: String,
+    fields: Vec<usize>,
+  }
+
+  impl FieldInputReader {
+    pub fn new(input_reader: Box<dyn InputReader>, delimiter: &str, fields: Vec<usize>) -> Box<dyn InputReader> {
+      Box::new(FieldInputReader { input_reader, delimiter: String::from(delimiter), fields })
+    }
+
+    pub fn layer(delimiter: &str, fields: Vec<usize>) -> Layer<Box<dyn InputReader>> {
+      let list: Vec<String> = fields.iter().map(|f| f.to_string()).collect();
+      let d = String::from(delimiter);
+      Layer::new::<FieldInputReader, _>("cut", move |r| FieldInputReader::new(r, &d, fields.clone()))
+        .with_metadata("delimiter", delimiter)
+        .with_metadata("fields", &list.join(","))
+    }
+  }
+
+  impl InputReader for FieldInputReader {
+    fn next_line(&mut self) -> Option<io::Result<String>> {
+      let r = match self.input_reader.next_line()? {
+        Ok(r) => r,
+        Err(e) => return Some(Err(e)),
+      };
+      let columns: Vec<&str> = r.split(self.delimiter.as_str()).collect();
+      let selected: Vec<&str> = self.fields.iter()
+        .filter_map(|f| f.checked_sub(1).and_then(|i| columns.get(i)).cloned())
+        .collect();
+      Some(Ok(selected.join(&self.delimiter)))
+    }
+  }
+}
\ No newline at end of file
@@ -0,0 +1,418 @@
+//! `order` module.
+//!
+//! Orders group `Dessert`s with quantities and per-line tax rates. Promotion
+//! rules are applied to every line, in the order they were added, and all
+//! amounts are computed in exact `Money` so totals are deterministic: float
+//! prices from `Dessert` are rounded to the minor unit, discounts and taxes
+//! are rounded half away from zero on each line. Menu items keep their
+//! exact prices, but must be in the currency of the order, and a receipt
+//! whose amounts would overflow is refused.
+//!
+//! # Example
+//!
+//! ```rust
+//! use structural::decorator::desserts::*;
+//! use structural::decorator::menu::*;
+//! use structural::decorator::order::*;
+//!
+//! let mut order = Order::new(Currency::EUR, TimeOfDay::new(17, 30).unwrap());
+//! order.add(Chocolat::new(Chantilly::new(Chocolat::new(Crepe::new()))), 2, TaxRate::from_basis_points(550));
+//! order.add(Gauffre::new(), 1, TaxRate::from_basis_points(1000));
+//! order.add_promotion(ThirdToppingFree);
+//! order.add_promotion(HappyHour::new(TimeOfDay::new(17, 0).unwrap(), TimeOfDay::new(18, 0).unwrap(), 1000));
+//!
+//! let receipt = order.receipt().unwrap();
+//! // Crepe Chocolat Chantilly Chocolat: 1.70 x 2, the third topping (0.20) is free,
+//! // then 10% off for the happy hour: 3.40 - 0.40 - 0.30 = 2.70, plus 5.5% tax.
+//! assert_eq!(receipt.lines[0].net.to_string(), "2.70 EUR");
+//! assert_eq!(receipt.lines[0].tax.to_string(), "0.15 EUR");
+//! // Gauffre: 1.50 - 0.15 = 1.35, plus 10% tax.
+//! assert_eq!(receipt.lines[1].total.to_string(), "1.49 EUR");
+//! assert_eq!(receipt.total.to_string(), "4.34 EUR");
+//! println!("{}", receipt);
+//! println!("{}", order.to_json().unwrap());
+//! println!("{}", receipt.to_json().unwrap());
+//!
+//! let max = Money::from_minor(i64::MAX, Currency::EUR);
+//! let menu = Menu::from_json(&format!(r#"{{
+//!   "currency": "EUR",
+//!   "bases": [{{ "name": "Crepe", "price": "1.00" }}, {{ "name": "Gold", "price": "{}" }}],
+//!   "toppings": []
+//! }}"#, max.amount())).unwrap();
+//! let mut order = Order::new(Currency::USD, TimeOfDay::new(12, 0).unwrap());
+//! let crepe = menu.dessert("Crepe").unwrap().build();
+//! assert!(matches!(order.add_item(crepe, 1, TaxRate::from_basis_points(0)), Err(MenuError::CurrencyMismatch { .. })));
+//!
+//! let mut order = Order::new(Currency::EUR, TimeOfDay::new(12, 0).unwrap());
+//! order.add_item(menu.dessert("Crepe").unwrap().build(), u32::MAX, TaxRate::from_basis_points(0)).unwrap();
+//! assert_eq!(order.receipt().unwrap().total.to_string(), "4294967295.00 EUR");
+//! order.add_item(menu.dessert("Gold").unwrap().build(), 1, TaxRate::from_basis_points(0)).unwrap();
+//! assert!(matches!(order.receipt(), Err(MenuError::Overflow)));
+//! //! ```
+
+use std::fmt;
+use serde::{Serialize, Serializer};
+use serde_json;
+use decorator::desserts::Dessert;
+use decorator::menu::{Currency, MenuError, MenuItem, Money};
+
+/// Time of the day, to the minute.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct TimeOfDay {
+  minutes: u16,
+}
+
+impl TimeOfDay {
+  pub fn new(hour: u8, minute: u8) -> Option<TimeOfDay> {
+    if hour > 23 || minute > 59 {
+      return None;
+    }
+    Some(TimeOfDay { minutes: hour as u16 * 60 + minute as u16 })
+  }
+
+  pub fn hour(&self) -> u8 {
+    (self.minutes / 60) as u8
+  }
+
+  pub fn minute(&self) -> u8 {
+    (self.minutes % 60) as u8
+  }
+}
+
+impl fmt::Display for TimeOfDay {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    write!(f, "{:02}:{:02}", self.hour(), self.minute())
+  }
+}
+
+impl Serialize for TimeOfDay {
+  fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.collect_str(self)
+  }
+}
+
+/// Tax rate in basis points: `550` is 5.5%.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct TaxRate {
+  basis_points: u32,
+}
+
+impl TaxRate {
+  pub fn from_basis_points(basis_points: u32) -> TaxRate {
+    TaxRate { basis_points }
+  }
+
+  pub fn basis_points(&self) -> u32 {
+    self.basis_points
+  }
+}
+
+impl fmt::Display for TaxRate {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    let percent = format!("{}.{:02}", self.basis_points / 100, self.basis_points % 100);
+    write!(f, "{}%", percent.trim_end_matches('0').trim_end_matches('.'))
+  }
+}
+
+impl Serialize for TaxRate {
+  fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.collect_str(self)
+  }
+}
+
+/// A line of the order, priced before promotions.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PricedLine {
+  pub label: String,
+  /// Price of each layer of one dessert, from the base outwards.
+  pub layers: Vec<(String, Money)>,
+  pub quantity: u32,
+  pub unit_price: Money,
+  pub tax_rate: TaxRate,
+}
+
+/// A promotion rule, granting a discount on the lines of an order.
+pub trait Promotion {
+  fn name(&self) -> String;
+
+  /// Discount on `line`, given what is left to pay on it after the
+  /// previous promotions. The order caps the discount to `remaining`.
+  fn discount(&self, line: &PricedLine, remaining: Money, placed_at: TimeOfDay) -> Money;
+}
+
+/// The third topping of each dessert is free.
+pub struct ThirdToppingFree;
+
+impl Promotion for ThirdToppingFree {
+  fn name(&self) -> String {
+    String::from("Third topping free")
+  }
+
+  fn discount(&self, line: &PricedLine, remaining: Money, _: TimeOfDay) -> Money {
+    // Layer 0 is the base, layer 3 the third topping.
+    match line.layers.get(3) {
+      // Out of range, the discount covers the whole line.
+      Some(&(_, price)) => price.times(line.quantity as i64).unwrap_or(remaining),
+      None => Money::zero(remaining.currency()),
+    }
+  }
+}
+
+/// A percentage off every line.
+pub struct PercentageDiscount {
+  basis_points: u32,
+}
+
+impl PercentageDiscount {
+  /// `basis_points` of `1000` takes 10% off.
+  pub fn new(basis_points: u32) -> PercentageDiscount {
+    PercentageDiscount { basis_points }
+  }
+}
+
+impl Promotion for PercentageDiscount {
+  fn name(&self) -> String {
+    format!("{} off", TaxRate::from_basis_points(self.basis_points))
+  }
+
+  fn discount(&self, _: &PricedLine, remaining: Money, _: TimeOfDay) -> Money {
+    remaining.basis_points(self.basis_points as i64).unwrap_or(remaining)
+  }
+}
+
+/// A percentage off every line of the orders placed between `start`
+/// (included) and `end` (excluded). The window may span midnight.
+pub struct HappyHour {
+  start: TimeOfDay,
+  end: TimeOfDay,
+  discount: PercentageDiscount,
+}
+
+impl HappyHour {
+  pub fn new(start: TimeOfDay, end: TimeOfDay, basis_points: u32) -> HappyHour {
+    HappyHour { start, end, discount: PercentageDiscount::new(basis_points) }
+  }
+
+  pub fn is_open(&self, at: TimeOfDay) -> bool {
+    if self.start <= self.end {
+      self.start <= at && at < self.end
+    } else {
+      at >= self.start || at < self.end
+    }
+  }
+}
+
+impl Promotion for HappyHour {
+  fn name(&self) -> String {
+    format!("Happy hour {}-{}: {}", self.start, self.end, self.discount.name())
+  }
+
+  fn discount(&self, line: &PricedLine, remaining: Money, placed_at: TimeOfDay) -> Money {
+    if self.is_open(placed_at) {
+      self.discount.discount(line, remaining, placed_at)
+    } else {
+      Money::zero(remaining.currency())
+    }
+  }
+}
+
+struct OrderLine {
+  label: String,
+  layers: Vec<(String, Money)>,
+  quantity: u32,
+  tax_rate: TaxRate,
+}
+
+/// An order of desserts, placed at a given time of the day.
+pub struct Order {
+  currency: Currency,
+  placed_at: TimeOfDay,
+  lines: Vec<OrderLine>,
+  promotions: Vec<Box<dyn Promotion>>,
+}
+
+/// Serialized form of an order.
+#[derive(Serialize)]
+struct OrderSnapshot {
+  currency: Currency,
+  placed_at: TimeOfDay,
+  lines: Vec<PricedLine>,
+  promotions: Vec<String>,
+}
+
+impl Order {
+  pub fn new(currency: Currency, placed_at: TimeOfDay) -> Order {
+    Order {
+      currency,
+      placed_at,
+      lines: Vec::new(),
+      promotions: Vec::new(),
+    }
+  }
+
+  /// Adds a dessert, its float prices rounded to the currency of the order.
+  pub fn add(&mut self, dessert: Box<dyn Dessert>, quantity: u32, tax_rate: TaxRate) {
+    let layers = dessert.get_layers().into_iter()
+      .map(|(label, price)| (label, Money::from_f32(price, self.currency)))
+      .collect();
+    self.lines.push(OrderLine { label: dessert.get_label(), layers, quantity, tax_rate });
+  }
+
+  /// Adds a menu item at its exact prices, which must be in the currency
+  /// of the order.
+  pub fn add_item(&mut self, item: Box<dyn MenuItem>, quantity: u32, tax_rate: TaxRate) -> Result<(), MenuError> {
+    let price = item.price()?;
+    if price.currency() != self.currency {
+      return Err(MenuError::CurrencyMismatch { expected: self.currency, found: price.currency() });
+    }
+    let layers = item.layers().into_iter().map(|line| (line.label, line.amount)).collect();
+    self.lines.push(OrderLine { label: item.get_label(), layers, quantity, tax_rate });
+    Ok(())
+  }
+
+  pub fn add_promotion<P: Promotion + 'static>(&mut self, promotion: P) {
+    self.promotions.push(Box::new(promotion));
+  }
+
+  pub fn placed_at(&self) -> TimeOfDay {
+    self.placed_at
+  }
+
+  /// Prices the lines in exact money, before promotions.
+  pub fn priced_lines(&self) -> Result<Vec<PricedLine>, MenuError> {
+    self.lines.iter().map(|line| {
+      let mut unit_price = Money::zero(self.currency);
+      for &(_, price) in line.layers.iter() {
+        unit_price = unit_price.checked_add(price)?;
+      }
+      Ok(PricedLine {
+        label: line.label.clone(),
+        layers: line.layers.clone(),
+        quantity: line.quantity,
+        unit_price,
+        tax_rate: line.tax_rate,
+      })
+    }).collect()
+  }
+
+  /// Totals the order; fails if an amount overflows or a promotion grants
+  /// a discount in another currency.
+  pub fn receipt(&self) -> Result<OrderReceipt, MenuError> {
+    let zero = Money::zero(self.currency);
+    let mut receipt = OrderReceipt {
+      currency: self.currency,
+      placed_at: self.placed_at,
+      lines: Vec::new(),
+      subtotal: zero,
+      discounts: zero,
+      taxes: zero,
+      total: zero,
+    };
+    for line in self.priced_lines()? {
+      let gross = line.unit_price.times(line.quantity as i64)?;
+      let mut net = gross;
+      let mut discounts = Vec::new();
+      for promotion in self.promotions.iter() {
+        let discount = promotion.discount(&line, net, self.placed_at);
+        if discount.currency() != self.currency {
+          return Err(MenuError::CurrencyMismatch { expected: self.currency, found: discount.currency() });
+        }
+        // Capped to what is left, so the subtraction stays in range.
+        let amount = discount.minor().clamp(0, net.minor().max(0));
+        if amount > 0 {
+          net = Money::from_minor(net.minor() - amount, self.currency);
+          discounts.push(Discount { promotion: promotion.name(), amount: Money::from_minor(amount, self.currency) });
+        }
+      }
+      let tax = net.basis_points(line.tax_rate.basis_points() as i64)?;
+      let total = net.checked_add(tax)?;
+
+      // Gross and net are both non-negative, so their difference is in range.
+      let discounted = Money::from_minor(gross.minor() - net.minor(), self.currency);
+      receipt.subtotal = receipt.subtotal.checked_add(gross)?;
+      receipt.discounts = receipt.discounts.checked_add(discounted)?;
+      receipt.taxes = receipt.taxes.checked_add(tax)?;
+      receipt.total = receipt.total.checked_add(total)?;
+      receipt.lines.push(ReceiptLine {
+        label: line.label,
+        quantity: line.quantity,
+        unit_price: line.unit_price,
+        gross,
+        discounts,
+        net,
+        tax_rate: line.tax_rate,
+        tax,
+        total,
+      });
+    }
+    Ok(receipt)
+  }
+
+  /// Serializes the order: its lines, priced layer by layer, and the names
+  /// of its promotions.
+  pub fn to_json(&self) -> Result<String, MenuError> {
+    Ok(serde_json::to_string_pretty(&OrderSnapshot {
+      currency: self.currency,
+      placed_at: self.placed_at,
+      lines: self.priced_lines()?,
+      promotions: self.promotions.iter().map(|p| p.name()).collect(),
+    })?)
+  }
+}
+
+/// A discount granted on a line by a promotion.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Discount {
+  pub promotion: String,
+  pub amount: Money,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ReceiptLine {
+  pub label: String,
+  pub quantity: u32,
+  pub unit_price: Money,
+  /// `unit_price` times `quantity`.
+  pub gross: Money,
+  pub discounts: Vec<Discount>,
+  /// `gross` minus the discounts, before tax.
+  pub net: Money,
+  pub tax_rate: TaxRate,
+  pub tax: Money,
+  pub total: Money,
+}
+
+/// Detailed totals of an order.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct OrderReceipt {
+  pub currency: Currency,
+  pub placed_at: TimeOfDay,
+  pub lines: Vec<ReceiptLine>,
+  pub subtotal: Money,
+  pub discounts: Money,
+  pub taxes: Money,
+  pub total: Money,
+}
+
+impl OrderReceipt {
+  pub fn to_json(&self) -> serde_json::Result<String> {
+    serde_json::to_string_pretty(self)
+  }
+}
+
+impl fmt::Display for OrderReceipt {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    writeln!(f, "Order placed at {}", self.placed_at)?;
+    for line in self.lines.iter() {
+      writeln!(f, "{} x {:<36}{:>14}", line.quantity, line.label, line.gross.to_string())?;
+      for discount in line.discounts.iter() {
+        writeln!(f, "    - {:<34}{:>14}", discount.promotion, format!("-{}", discount.amount))?;
+      }
+      writeln!(f, "    tax {:<32}{:>14}", line.tax_rate.to_string(), line.tax.to_string())?;
+    }
+    writeln!(f, "{:<40}{:>14}", "Subtotal", self.subtotal.to_string())?;
+    writeln!(f, "{:<40}{:>14}", "Discounts", format!("-{}", self.discounts))?;
+    writeln!(f, "{:<40}{:>14}", "Taxes", self.taxes.to_string())?;
+    write!(f, "{:<40}{:>14}", "Total", self.total.to_string())
+  }
+}
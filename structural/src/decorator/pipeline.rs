@@ -0,0 +1,289 @@
+//! `pipeline` module.
+//!
+//! Builds `InputReader` decorator chains at runtime from a textual spec such
+//! as `file:input.txt | upper | grep:ERROR | number`.
+//!
+//! A spec is a list of stages separated by `|`. The first stage is a source,
+//! the following ones are decorators applied in order. A stage is a name,
+//! optionally followed by `:` and an argument; a literal `|` is written `\|`.
+//!
+//! Sources: `file:PATH`, `gzip:PATH`, `cat:PATH,PATH,...`, `stdin`,
+//! `text:TEXT`.
+//!
+//! Stages: `upper`, `length`, `grep:REGEX`, `grep-v:REGEX`, `number`,
+//! `trim`, `skip-blank`, `dedup`, `head:N`, `tail:N`, `sample:N` and
+//! `cut:DELIMITER:FIELDS` where `FIELDS` is a comma separated list.
+//!
+//! # Example
+//!
+//! ```rust
+//! use structural::decorator::input_readers::*;
+//! use structural::decorator::pipeline::*;
+//!
+//! let registry = Registry::new();
+//! let mut reader = registry.build("text:info ok\nerror a\\|b\nerror c | upper | grep:^ERROR | number").unwrap();
+//! assert_eq!(reader.read_lines().unwrap(), vec!["1: ERROR A|B", "2: ERROR C"]);
+//!
+//! let error = registry.build("text:x | uper").err().unwrap();
+//! assert_eq!(error.to_string(), "stage 2: unknown stage `uper`");
+//! let error = registry.build("text:x | head:ten").err().unwrap();
+//! assert_eq!(error.to_string(), "stage 2: bad argument `ten` for `head`: expected a number");
+//! ```
+//!
+//! Custom stages can be registered next to the built-in ones:
+//!
+//! ```rust
+//! use structural::decorator::input_readers::*;
+//! use structural::decorator::pipeline::*;
+//!
+//! let mut registry = Registry::new();
+//! registry.register_stage("shout", |reader, _| {
+//!   Ok(LenghtInputReader::new(CapitalizedInputReader::new(reader)))
+//! });
+//! let mut reader = registry.build("text:abc | shout").unwrap();
+//! assert_eq!(reader.read_lines().unwrap(), vec!["ABC: 3"]);
+//! ```
+
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+use std::io;
+use std::path::PathBuf;
+use decorator::input_readers::*;
+
+/// Error raised while parsing a spec or building its chain.
+#[derive(Debug)]
+pub enum PipelineError {
+  /// The spec holds no stage.
+  Empty,
+  /// A stage is empty, e.g. `upper || number`.
+  EmptyStage { position: usize },
+  UnknownSource { position: usize, name: String },
+  UnknownStage { position: usize, name: String },
+  /// The first stage is not a source.
+  MissingSource { name: String },
+  /// A source used after the first stage.
+  MisplacedSource { position: usize, name: String },
+  MissingArgument { position: usize, stage: String },
+  BadArgument { position: usize, stage: String, argument: String, reason: String },
+  /// The source could not be opened.
+  Io { position: usize, stage: String, error: io::Error },
+}
+
+impl fmt::Display for PipelineError {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match *self {
+      PipelineError::Empty => write!(f, "empty pipeline"),
+      PipelineError::EmptyStage { position } => write!(f, "stage {}: empty stage", position),
+      PipelineError::UnknownSource { position, ref name } =>
+        write!(f, "stage {}: unknown source `{}`", position, name),
+      PipelineError::UnknownStage { position, ref name } =>
+        write!(f, "stage {}: unknown stage `{}`", position, name),
+      PipelineError::MissingSource { ref name } =>
+        write!(f, "stage 1: the pipeline must start with a source, found `{}`", name),
+      PipelineError::MisplacedSource { position, ref name } =>
+        write!(f, "stage {}: source `{}` can only start the pipeline", position, name),
+      PipelineError::MissingArgument { position, ref stage } =>
+        write!(f, "stage {}: `{}` expects an argument", position, stage),
+      PipelineError::BadArgument { position, ref stage, ref argument, ref reason } =>
+        write!(f, "stage {}: bad argument `{}` for `{}`: {}", position, argument, stage, reason),
+      PipelineError::Io { position, ref stage, ref error } =>
+        write!(f, "stage {}: cannot open `{}`: {}", position, stage, error),
+    }
+  }
+}
+
+impl Error for PipelineError {
+  fn source(&self) -> Option<&(dyn Error + 'static)> {
+    match *self {
+      PipelineError::Io { ref error, .. } => Some(error),
+      _ => None,
+    }
+  }
+}
+
+/// Error returned by a stage builder, located by the registry.
+#[derive(Debug)]
+pub enum StageError {
+  MissingArgument,
+  BadArgument(String),
+  Io(io::Error),
+}
+
+impl From<io::Error> for StageError {
+  fn from(error: io::Error) -> StageError {
+    StageError::Io(error)
+  }
+}
+
+/// One parsed stage of a spec.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StageSpec {
+  /// Position in the spec, starting at 1.
+  pub position: usize,
+  pub name: String,
+  pub argument: Option<String>,
+}
+
+/// Splits a spec into its stages.
+pub fn parse(spec: &str) -> Result<Vec<StageSpec>, PipelineError> {
+  let mut parts: Vec<String> = vec![String::new()];
+  let mut chars = spec.chars().peekable();
+  while let Some(c) = chars.next() {
+    match c {
+      '\\' if chars.peek() == Some(&'|') => {
+        chars.next();
+        parts.last_mut().unwrap().push('|');
+      }
+      '|' => parts.push(String::new()),
+      c => parts.last_mut().unwrap().push(c),
+    }
+  }
+  if parts.len() == 1 && parts[0].trim().is_empty() {
+    return Err(PipelineError::Empty);
+  }
+  parts.iter().enumerate().map(|(i, part)| {
+    let part = part.trim();
+    if part.is_empty() {
+      return Err(PipelineError::EmptyStage { position: i + 1 });
+    }
+    let (name, argument) = match part.find(':') {
+      Some(colon) => (part[..colon].trim(), Some(String::from(&part[colon + 1..]))),
+      None => (part, None),
+    };
+    Ok(StageSpec { position: i + 1, name: String::from(name), argument })
+  }).collect()
+}
+
+pub type SourceBuilder = Box<dyn Fn(Option<&str>) -> Result<Box<dyn InputReader>, StageError>>;
+pub type StageBuilder = Box<dyn Fn(Box<dyn InputReader>, Option<&str>) -> Result<Box<dyn InputReader>, StageError>>;
+
+/// Named sources and stages a spec can refer to.
+pub struct Registry {
+  sources: HashMap<String, SourceBuilder>,
+  stages: HashMap<String, StageBuilder>,
+}
+
+impl Registry {
+  /// Creates a registry holding the built-in sources and stages.
+  pub fn new() -> Registry {
+    let mut registry = Registry::empty();
+
+    registry.register_source("file", |arg| Ok(AdvancedInputReader::new(required(arg)?)?));
+    registry.register_source("gzip", |arg| Ok(AdvancedInputReader::from_gzip(required(arg)?)?));
+    registry.register_source("cat", |arg| {
+      let paths: Vec<PathBuf> = required(arg)?.split(',').map(|p| PathBuf::from(p.trim())).collect();
+      Ok(AdvancedInputReader::concat(paths)?)
+    });
+    registry.register_source("stdin", |_| Ok(AdvancedInputReader::from_stdin()));
+    registry.register_source("text", |arg| Ok(AdvancedInputReader::from_string(required(arg)?)));
+
+    registry.register_stage("upper", |reader, _| Ok(CapitalizedInputReader::new(reader)));
+    registry.register_stage("length", |reader, _| Ok(LenghtInputReader::new(reader)));
+    registry.register_stage("grep", |reader, arg| {
+      GrepInputReader::new(reader, required(arg)?).map_err(|e| StageError::BadArgument(e.to_string()))
+    });
+    registry.register_stage("grep-v", |reader, arg| {
+      GrepInputReader::invert(reader, required(arg)?).map_err(|e| StageError::BadArgument(e.to_string()))
+    });
+    registry.register_stage("number", |reader, _| Ok(NumberedInputReader::new(reader)));
+    registry.register_stage("trim", |reader, _| Ok(TrimInputReader::new(reader)));
+    registry.register_stage("skip-blank", |reader, _| Ok(SkipBlankInputReader::new(reader)));
+    registry.register_stage("dedup", |reader, _| Ok(DedupInputReader::new(reader)));
+    registry.register_stage("head", |reader, arg| Ok(HeadInputReader::new(reader, number(arg)?)));
+    registry.register_stage("tail", |reader, arg| Ok(TailInputReader::new(reader, number(arg)?)));
+    registry.register_stage("sample", |reader, arg| Ok(SampleInputReader::new(reader, number(arg)?)));
+    registry.register_stage("cut", |reader, arg| {
+      let arg = required(arg)?;
+      let colon = arg.rfind(':').ok_or_else(|| StageError::BadArgument(String::from("expected DELIMITER:FIELDS")))?;
+      let (delimiter, fields) = (&arg[..colon], &arg[colon + 1..]);
+      if delimiter.is_empty() {
+        return Err(StageError::BadArgument(String::from("empty delimiter")));
+      }
+      let fields = fields.split(',')
+        .map(|f| f.trim().parse::<usize>().ok().filter(|f| *f > 0))
+        .collect::<Option<Vec<usize>>>()
+        .ok_or_else(|| StageError::BadArgument(String::from("fields are numbered from 1")))?;
+      Ok(FieldInputReader::new(reader, delimiter, fields))
+    });
+
+    registry
+  }
+
+  /// Creates a registry without any source or stage.
+  pub fn empty() -> Registry {
+    Registry {
+      sources: HashMap::new(),
+      stages: HashMap::new(),
+    }
+  }
+
+  /// Registers a source, replacing any source with the same name.
+  pub fn register_source<F>(&mut self, name: &str, builder: F)
+    where F: Fn(Option<&str>) -> Result<Box<dyn InputReader>, StageError> + 'static {
+    self.sources.insert(String::from(name), Box::new(builder));
+  }
+
+  /// Registers a stage, replacing any stage with the same name.
+  pub fn register_stage<F>(&mut self, name: &str, builder: F)
+    where F: Fn(Box<dyn InputReader>, Option<&str>) -> Result<Box<dyn InputReader>, StageError> + 'static {
+    self.stages.insert(String::from(name), Box::new(builder));
+  }
+
+  /// Parses `spec` and builds its decorator chain.
+  pub fn build(&self, spec: &str) -> Result<Box<dyn InputReader>, PipelineError> {
+    let stages = parse(spec)?;
+    let (first, rest) = stages.split_first().ok_or(PipelineError::Empty)?;
+
+    let source = match self.sources.get(&first.name) {
+      Some(source) => source,
+      None if self.stages.contains_key(&first.name) =>
+        return Err(PipelineError::MissingSource { name: first.name.clone() }),
+      None => return Err(PipelineError::UnknownSource { position: first.position, name: first.name.clone() }),
+    };
+    let mut reader = source(first.argument.as_deref()).map_err(|e| locate(e, first))?;
+
+    for stage in rest {
+      let builder = match self.stages.get(&stage.name) {
+        Some(builder) => builder,
+        None if self.sources.contains_key(&stage.name) =>
+          return Err(PipelineError::MisplacedSource { position: stage.position, name: stage.name.clone() }),
+        None => return Err(PipelineError::UnknownStage { position: stage.position, name: stage.name.clone() }),
+      };
+      reader = builder(reader, stage.argument.as_deref()).map_err(|e| locate(e, stage))?;
+    }
+    Ok(reader)
+  }
+}
+
+impl Default for Registry {
+  fn default() -> Registry {
+    Registry::new()
+  }
+}
+
+fn locate(error: StageError, stage: &StageSpec) -> PipelineError {
+  let position = stage.position;
+  let name = stage.name.clone();
+  match error {
+    StageError::MissingArgument => PipelineError::MissingArgument { position, stage: name },
+    StageError::BadArgument(reason) => PipelineError::BadArgument {
+      position,
+      stage: name,
+      argument: stage.argument.clone().unwrap_or_default(),
+      reason,
+    },
+    StageError::Io(error) => PipelineError::Io { position, stage: name, error },
+  }
+}
+
+fn required(argument: Option<&str>) -> Result<&str, StageError> {
+  match argument.map(str::trim) {
+    Some(argument) if !argument.is_empty() => Ok(argument),
+    _ => Err(StageError::MissingArgument),
+  }
+}
+
+fn number(argument: Option<&str>) -> Result<usize, StageError> {
+  required(argument)?.parse().map_err(|_| StageError::BadArgument(String::from("expected a number")))
+}
@@ -0,0 +1,223 @@
+//! `stack` module.
+//!
+//! Once decorators are wrapped into each other, the layers they form cannot
+//! be inspected or changed. A `DecoratorStack` keeps the base and the layers
+//! apart instead: layers can be listed with their name and metadata, tested
+//! by decorator type, removed or reordered, and the decorated object is
+//! built on demand.
+//!
+//! Layers are indexed from the innermost one, the closest to the base.
+//! Decorators of the `desserts` and `input_readers` modules provide a
+//! `layer` constructor.
+//!
+//! # Example
+//!
+//! ```rust
+//! use structural::decorator::desserts::*;
+//! use structural::decorator::stack::*;
+//!
+//! let mut dessert = DecoratorStack::new("Crepe", Crepe::new)
+//!   .with(Chocolat::layer())
+//!   .with(Chantilly::layer())
+//!   .with(Chocolat::layer());
+//! assert_eq!(dessert.names(), vec!["Chocolat", "Chantilly", "Chocolat"]);
+//! assert_eq!(dessert.get_label(), "Crepe Chocolat Chantilly Chocolat");
+//! assert!(dessert.contains::<Chantilly>());
+//!
+//! dessert.remove_all::<Chocolat>();
+//! assert_eq!(dessert.to_string(), "Crepe Chantilly: 1.3");
+//! assert!(!dessert.contains::<Chocolat>());
+//!
+//! dessert.push(Chocolat::layer());
+//! dessert.move_layer(1, 0);
+//! assert_eq!(dessert.get_label(), "Crepe Chocolat Chantilly");
+//! assert!(dessert.swap(0, 1));
+//! assert_eq!(dessert.get_label(), "Crepe Chantilly Chocolat");
+//! assert!(!dessert.swap(0, 2));
+//!
+//! assert!(dessert.insert(2, Chantilly::layer()));
+//! assert_eq!(dessert.get_label(), "Crepe Chantilly Chocolat Chantilly");
+//! assert!(!dessert.insert(4, Chocolat::layer()));
+//! assert_eq!(dessert.layers().len(), 3);
+//! ```
+//!
+//! ```rust
+//! use structural::decorator::input_readers::*;
+//! use structural::decorator::stack::*;
+//!
+//! let mut reader = DecoratorStack::new("text", || AdvancedInputReader::from_string("error a\ninfo b\nerror c"))
+//!   .with(GrepInputReader::layer("^error").unwrap())
+//!   .with(CapitalizedInputReader::layer())
+//!   .with(NumberedInputReader::layer());
+//! assert_eq!(reader.layers()[0].metadata(), &[(String::from("pattern"), String::from("^error"))]);
+//! assert_eq!(reader.build().read_lines().unwrap(), vec!["1: ERROR A", "2: ERROR C"]);
+//!
+//! let grep = reader.position::<GrepInputReader>().unwrap();
+//! reader.remove(grep);
+//! assert_eq!(reader.build().read_lines().unwrap(), vec!["1: ERROR A", "2: INFO B", "3: ERROR C"]);
+//! ```
+
+use std::any::TypeId;
+use std::fmt;
+
+/// One decorator of a stack, able to wrap a `T` again on every build.
+pub struct Layer<T> {
+  name: String,
+  metadata: Vec<(String, String)>,
+  type_id: TypeId,
+  wrap: Box<dyn Fn(T) -> T>,
+}
+
+impl<T> Layer<T> {
+  /// Creates a layer for the decorator type `D`, applied by `wrap`.
+  pub fn new<D: 'static, F: Fn(T) -> T + 'static>(name: &str, wrap: F) -> Layer<T> {
+    Layer {
+      name: String::from(name),
+      metadata: Vec::new(),
+      type_id: TypeId::of::<D>(),
+      wrap: Box::new(wrap),
+    }
+  }
+
+  pub fn with_metadata(mut self, key: &str, value: &str) -> Layer<T> {
+    self.metadata.push((String::from(key), String::from(value)));
+    self
+  }
+
+  pub fn name(&self) -> &str {
+    &self.name
+  }
+
+  pub fn metadata(&self) -> &[(String, String)] {
+    &self.metadata
+  }
+
+  /// Tells if the layer applies the decorator type `D`.
+  pub fn is<D: 'static>(&self) -> bool {
+    self.type_id == TypeId::of::<D>()
+  }
+
+  pub fn apply(&self, inner: T) -> T {
+    (self.wrap)(inner)
+  }
+}
+
+impl<T> fmt::Debug for Layer<T> {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    f.debug_struct("Layer")
+      .field("name", &self.name)
+      .field("metadata", &self.metadata)
+      .finish()
+  }
+}
+
+/// A base and the layers of decorators applied to it.
+pub struct DecoratorStack<T> {
+  base_name: String,
+  base: Box<dyn Fn() -> T>,
+  layers: Vec<Layer<T>>,
+}
+
+impl<T> DecoratorStack<T> {
+  /// Creates a stack without layers; `base` creates the object to decorate.
+  pub fn new<F: Fn() -> T + 'static>(base_name: &str, base: F) -> DecoratorStack<T> {
+    DecoratorStack {
+      base_name: String::from(base_name),
+      base: Box::new(base),
+      layers: Vec::new(),
+    }
+  }
+
+  /// Adds an outermost layer.
+  pub fn with(mut self, layer: Layer<T>) -> DecoratorStack<T> {
+    self.push(layer);
+    self
+  }
+
+  /// Adds an outermost layer.
+  pub fn push(&mut self, layer: Layer<T>) {
+    self.layers.push(layer);
+  }
+
+  /// Inserts a layer at `index`, shifting the outer layers. Returns
+  /// `false`, dropping the layer, when `index` is past the outermost one.
+  pub fn insert(&mut self, index: usize, layer: Layer<T>) -> bool {
+    if index > self.layers.len() {
+      return false;
+    }
+    self.layers.insert(index, layer);
+    true
+  }
+
+  pub fn base_name(&self) -> &str {
+    &self.base_name
+  }
+
+  /// Layers from the innermost to the outermost.
+  pub fn layers(&self) -> &[Layer<T>] {
+    &self.layers
+  }
+
+  pub fn names(&self) -> Vec<&str> {
+    self.layers.iter().map(Layer::name).collect()
+  }
+
+  pub fn contains<D: 'static>(&self) -> bool {
+    self.layers.iter().any(Layer::is::<D>)
+  }
+
+  /// Index of the innermost layer of decorator type `D`.
+  pub fn position<D: 'static>(&self) -> Option<usize> {
+    self.layers.iter().position(Layer::is::<D>)
+  }
+
+  pub fn remove(&mut self, index: usize) -> Option<Layer<T>> {
+    if index < self.layers.len() {
+      Some(self.layers.remove(index))
+    } else {
+      None
+    }
+  }
+
+  /// Removes every layer of decorator type `D`, returning how many were.
+  pub fn remove_all<D: 'static>(&mut self) -> usize {
+    let before = self.layers.len();
+    self.layers.retain(|layer| !layer.is::<D>());
+    before - self.layers.len()
+  }
+
+  /// Moves the layer at `from` to `to`, shifting the layers in between.
+  /// Returns `false` when an index is out of bounds.
+  pub fn move_layer(&mut self, from: usize, to: usize) -> bool {
+    if from >= self.layers.len() || to >= self.layers.len() {
+      return false;
+    }
+    let layer = self.layers.remove(from);
+    self.layers.insert(to, layer);
+    true
+  }
+
+  /// Swaps the layers at `a` and `b`. Returns `false` when an index is
+  /// out of bounds.
+  pub fn swap(&mut self, a: usize, b: usize) -> bool {
+    if a >= self.layers.len() || b >= self.layers.len() {
+      return false;
+    }
+    self.layers.swap(a, b);
+    true
+  }
+
+  /// Creates the base and wraps it in every layer.
+  pub fn build(&self) -> T {
+    self.layers.iter().fold((self.base)(), |inner, layer| layer.apply(inner))
+  }
+}
+
+impl<T> fmt::Debug for DecoratorStack<T> {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    f.debug_struct("DecoratorStack")
+      .field("base", &self.base_name)
+      .field("layers", &self.layers)
+      .finish()
+  }
+}
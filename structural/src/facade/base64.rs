@@ -0,0 +1,70 @@
+//! `base64` module.
+//!
+//! Standard base64 alphabet, with `=` padding.
+//!
+//! # Example
+//!
+//! ```rust
+//! use structural::facade::base64;
+//!
+//! assert_eq!(base64::encode(b"Ivan"), "SXZhbg==");
+//! assert_eq!(base64::decode("SXZh\nbg==").unwrap(), b"Ivan");
+//! assert_eq!(base64::decode("SXZhbg").unwrap(), b"Ivan");
+//! assert!(base64::decode("SXZ*bg==").is_err());
+//! ```
+
+use facade::DecodeError;
+
+const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+pub fn encode(data: &[u8]) -> String {
+  let mut text = String::with_capacity(data.len().div_ceil(3) * 4);
+  for chunk in data.chunks(3) {
+    let bytes = [chunk[0], *chunk.get(1).unwrap_or(&0), *chunk.get(2).unwrap_or(&0)];
+    let group = (u32::from(bytes[0]) << 16) | (u32::from(bytes[1]) << 8) | u32::from(bytes[2]);
+    for i in 0..4 {
+      if i <= chunk.len() {
+        text.push(ALPHABET[(group >> (18 - 6 * i) & 0x3f) as usize] as char);
+      } else {
+        text.push('=');
+      }
+    }
+  }
+  text
+}
+
+/// Decodes `text`, ignoring ASCII whitespace; the padding is optional.
+pub fn decode<T: AsRef<[u8]>>(text: T) -> Result<Vec<u8>, DecodeError> {
+  let text = text.as_ref();
+  let mut data = Vec::with_capacity(text.len() / 4 * 3);
+  let mut group = 0u32;
+  let mut count = 0;
+  let mut padding = 0;
+  for (position, &byte) in text.iter().enumerate() {
+    if byte.is_ascii_whitespace() {
+      continue;
+    }
+    if byte == b'=' {
+      padding += 1;
+      continue;
+    }
+    let value = match ALPHABET.iter().position(|&c| c == byte) {
+      Some(value) if padding == 0 => value as u32,
+      _ => return Err(DecodeError::InvalidByte { position, byte }),
+    };
+    group = (group << 6) | value;
+    count += 1;
+    if count == 4 {
+      data.extend_from_slice(&[(group >> 16) as u8, (group >> 8) as u8, group as u8]);
+      group = 0;
+      count = 0;
+    }
+  }
+  match count {
+    0 if padding == 0 => {}
+    2 if padding == 0 || padding == 2 => data.push((group >> 4) as u8),
+    3 if padding == 0 || padding == 1 => data.extend_from_slice(&[(group >> 10) as u8, (group >> 2) as u8]),
+    _ => return Err(DecodeError::InvalidLength(text.len())),
+  }
+  Ok(data)
+}
@@ -0,0 +1,191 @@
+//! `cache` module.
+//!
+//! Downloaded payloads kept in memory, with least recently used eviction,
+//! and optionally in a directory shared between runs. An entry is fresh
+//! for a time to live; once stale, it is revalidated with its validator,
+//! an HTTP `ETag` or the size and modification time of a file.
+//!
+//! See `facade::fetcher` for an example.
+
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{HashMap, VecDeque};
+use std::fs;
+use std::hash::{Hash, Hasher};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use serde_json;
+
+/// A cached payload.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Entry {
+  pub body: Vec<u8>,
+  pub validator: Option<String>,
+  pub stored_at: SystemTime,
+}
+
+impl Entry {
+  pub fn is_fresh(&self, ttl: Duration) -> bool {
+    self.stored_at.elapsed().map(|age| age < ttl).unwrap_or(true)
+  }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CacheStats {
+  /// Fresh entries served without a request.
+  pub hits: usize,
+  /// Stale entries confirmed unchanged by the source.
+  pub revalidated: usize,
+  /// Payloads downloaded in full.
+  pub misses: usize,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Meta {
+  url: String,
+  validator: Option<String>,
+  stored_at_ms: u64,
+}
+
+pub struct Cache {
+  capacity: usize,
+  ttl: Duration,
+  directory: Option<PathBuf>,
+  entries: HashMap<String, Entry>,
+  /// URLs from the least to the most recently used.
+  order: VecDeque<String>,
+  stats: CacheStats,
+}
+
+impl Cache {
+  /// Keeps at most `capacity` payloads in memory, fresh for `ttl`.
+  pub fn new(capacity: usize, ttl: Duration) -> Cache {
+    Cache {
+      capacity,
+      ttl,
+      directory: None,
+      entries: HashMap::new(),
+      order: VecDeque::new(),
+      stats: CacheStats::default(),
+    }
+  }
+
+  /// Also keeps every payload in `directory`, created when needed. Disk
+  /// errors are ignored: the cache only loses the entry.
+  pub fn with_directory<P: AsRef<Path>>(mut self, directory: P) -> Cache {
+    self.directory = Some(directory.as_ref().to_path_buf());
+    self
+  }
+
+  pub fn ttl(&self) -> Duration {
+    self.ttl
+  }
+
+  /// Number of payloads in memory.
+  pub fn len(&self) -> usize {
+    self.entries.len()
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.entries.is_empty()
+  }
+
+  pub fn contains(&self, url: &str) -> bool {
+    self.entries.contains_key(url)
+  }
+
+  pub fn stats(&self) -> CacheStats {
+    self.stats
+  }
+
+  /// Looks `url` up in memory, then on disk, marking it recently used.
+  pub fn get(&mut self, url: &str) -> Option<Entry> {
+    if !self.entries.contains_key(url) {
+      let entry = self.load(url)?;
+      self.insert(url, entry);
+    }
+    self.order.retain(|u| u != url);
+    self.order.push_back(String::from(url));
+    self.entries.get(url).cloned()
+  }
+
+  /// Stores a downloaded payload.
+  pub fn put(&mut self, url: &str, body: Vec<u8>, validator: Option<String>) {
+    let entry = Entry { body, validator, stored_at: SystemTime::now() };
+    self.save(url, &entry);
+    self.insert(url, entry);
+    self.stats.misses += 1;
+  }
+
+  /// Marks the entry of `url` fresh again, after the source confirmed it.
+  pub fn revalidate(&mut self, url: &str) {
+    let entry = match self.entries.get_mut(url) {
+      Some(entry) => {
+        entry.stored_at = SystemTime::now();
+        entry.clone()
+      }
+      None => return,
+    };
+    self.save(url, &entry);
+    self.stats.revalidated += 1;
+  }
+
+  pub(crate) fn record_hit(&mut self) {
+    self.stats.hits += 1;
+  }
+
+  fn insert(&mut self, url: &str, entry: Entry) {
+    self.order.retain(|u| u != url);
+    self.order.push_back(String::from(url));
+    self.entries.insert(String::from(url), entry);
+    while self.entries.len() > self.capacity {
+      match self.order.pop_front() {
+        Some(oldest) => {
+          self.entries.remove(&oldest);
+        }
+        None => break,
+      }
+    }
+  }
+
+  fn paths(&self, url: &str) -> Option<(PathBuf, PathBuf)> {
+    let directory = self.directory.as_ref()?;
+    let mut hasher = DefaultHasher::new();
+    url.hash(&mut hasher);
+    let stem = format!("{:016x}", hasher.finish());
+    Some((directory.join(format!("{}.body", stem)), directory.join(format!("{}.meta", stem))))
+  }
+
+  fn load(&self, url: &str) -> Option<Entry> {
+    let (body_path, meta_path) = self.paths(url)?;
+    let meta: Meta = serde_json::from_slice(&fs::read(meta_path).ok()?).ok()?;
+    if meta.url != url {
+      return None;
+    }
+    Some(Entry {
+      body: fs::read(body_path).ok()?,
+      validator: meta.validator,
+      stored_at: UNIX_EPOCH + Duration::from_millis(meta.stored_at_ms),
+    })
+  }
+
+  fn save(&self, url: &str, entry: &Entry) {
+    let (body_path, meta_path) = match self.paths(url) {
+      Some(paths) => paths,
+      None => return,
+    };
+    let meta = Meta {
+      url: String::from(url),
+      validator: entry.validator.clone(),
+      stored_at_ms: entry.stored_at.duration_since(UNIX_EPOCH).map(|d| d.as_millis() as u64).unwrap_or(0),
+    };
+    if let Some(directory) = body_path.parent() {
+      let _ = fs::create_dir_all(directory);
+    }
+    if fs::write(&body_path, &entry.body).is_ok() {
+      if let Ok(meta) = serde_json::to_vec(&meta) {
+        let _ = fs::write(meta_path, meta);
+      }
+    }
+  }
+}
@@ -0,0 +1,260 @@
+//! `codec` module.
+//!
+//! A payload often goes through several encodings: compressed, encrypted,
+//! then put in base64 for transport. Each step is a `Codec`, and a
+//! `CodecChain` lists them in the order they are undone when decoding.
+//! A failing step is reported with its position and name.
+//!
+//! # Example
+//!
+//! ```rust
+//! use structural::facade::codec::*;
+//!
+//! let key = [7u8; 32];
+//! let chain = CodecChain::new()
+//!   .then(Base64)
+//!   .then(Encryption::new(key))
+//!   .then(Gzip);
+//! assert_eq!(chain.names(), vec!["base64", "encryption", "gzip"]);
+//!
+//! let payload = chain.encode(b"Hello, facade!".to_vec()).unwrap();
+//! assert!(payload.iter().all(|b| b.is_ascii_alphanumeric() || b"+/=".contains(b)));
+//! assert_eq!(chain.decode(payload.clone()).unwrap(), b"Hello, facade!");
+//!
+//! let wrong_key = CodecChain::new().then(Base64).then(Encryption::new([8u8; 32])).then(Gzip);
+//! let error = wrong_key.decode(payload).unwrap_err();
+//! assert_eq!(error.stage, 1);
+//! assert_eq!(error.to_string(), "stage 1 (encryption): wrong key or corrupted data");
+//!
+//! let error = CodecChain::new().then(Hex).then(Deflate).decode(b"ffff".to_vec()).unwrap_err();
+//! assert_eq!((error.stage, error.codec.as_str()), (1, "deflate"));
+//! ```
+
+use std::error::Error;
+use std::fmt;
+use std::io;
+use std::io::{Read, Write};
+
+use chacha20poly1305::aead::{Aead, AeadCore, KeyInit, OsRng};
+use chacha20poly1305::{ChaCha20Poly1305, Key, Nonce};
+use flate2::read::{DeflateDecoder, MultiGzDecoder};
+use flate2::write::{DeflateEncoder, GzEncoder};
+use flate2::Compression;
+
+use facade::{base64, hex, DecodeError};
+
+#[derive(Debug)]
+pub enum CodecError {
+  /// Invalid text for a text encoding.
+  Decode(DecodeError),
+  Compression(io::Error),
+  Encryption(&'static str),
+}
+
+impl fmt::Display for CodecError {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match *self {
+      CodecError::Decode(ref e) => write!(f, "{}", e),
+      CodecError::Compression(ref e) => write!(f, "{}", e),
+      CodecError::Encryption(reason) => write!(f, "{}", reason),
+    }
+  }
+}
+
+impl Error for CodecError {
+  fn source(&self) -> Option<&(dyn Error + 'static)> {
+    match *self {
+      CodecError::Decode(ref e) => Some(e),
+      CodecError::Compression(ref e) => Some(e),
+      CodecError::Encryption(_) => None,
+    }
+  }
+}
+
+impl From<DecodeError> for CodecError {
+  fn from(error: DecodeError) -> CodecError {
+    CodecError::Decode(error)
+  }
+}
+
+impl From<io::Error> for CodecError {
+  fn from(error: io::Error) -> CodecError {
+    CodecError::Compression(error)
+  }
+}
+
+/// One encoding of a payload.
+pub trait Codec {
+  fn name(&self) -> &str;
+  fn decode(&self, data: Vec<u8>) -> Result<Vec<u8>, CodecError>;
+  fn encode(&self, data: Vec<u8>) -> Result<Vec<u8>, CodecError>;
+}
+
+pub struct Base64;
+impl Codec for Base64 {
+  fn name(&self) -> &str {
+    "base64"
+  }
+  fn decode(&self, data: Vec<u8>) -> Result<Vec<u8>, CodecError> {
+    Ok(base64::decode(data)?)
+  }
+  fn encode(&self, data: Vec<u8>) -> Result<Vec<u8>, CodecError> {
+    Ok(base64::encode(&data).into_bytes())
+  }
+}
+
+pub struct Hex;
+impl Codec for Hex {
+  fn name(&self) -> &str {
+    "hex"
+  }
+  fn decode(&self, data: Vec<u8>) -> Result<Vec<u8>, CodecError> {
+    Ok(hex::decode(data)?)
+  }
+  fn encode(&self, data: Vec<u8>) -> Result<Vec<u8>, CodecError> {
+    Ok(hex::encode(&data).into_bytes())
+  }
+}
+
+/// gzip streams, possibly concatenated.
+pub struct Gzip;
+impl Codec for Gzip {
+  fn name(&self) -> &str {
+    "gzip"
+  }
+  fn decode(&self, data: Vec<u8>) -> Result<Vec<u8>, CodecError> {
+    let mut decoded = Vec::new();
+    MultiGzDecoder::new(&data[..]).read_to_end(&mut decoded)?;
+    Ok(decoded)
+  }
+  fn encode(&self, data: Vec<u8>) -> Result<Vec<u8>, CodecError> {
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(&data)?;
+    Ok(encoder.finish()?)
+  }
+}
+
+/// Raw deflate, without gzip header.
+pub struct Deflate;
+impl Codec for Deflate {
+  fn name(&self) -> &str {
+    "deflate"
+  }
+  fn decode(&self, data: Vec<u8>) -> Result<Vec<u8>, CodecError> {
+    let mut decoded = Vec::new();
+    DeflateDecoder::new(&data[..]).read_to_end(&mut decoded)?;
+    Ok(decoded)
+  }
+  fn encode(&self, data: Vec<u8>) -> Result<Vec<u8>, CodecError> {
+    let mut encoder = DeflateEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(&data)?;
+    Ok(encoder.finish()?)
+  }
+}
+
+/// ChaCha20-Poly1305 with a 256-bit key. An encrypted payload is a random
+/// 12-byte nonce followed by the ciphertext and its tag, so a wrong key or
+/// a corrupted payload is detected.
+pub struct Encryption {
+  cipher: ChaCha20Poly1305,
+}
+
+impl Encryption {
+  pub fn new(key: [u8; 32]) -> Encryption {
+    Encryption { cipher: ChaCha20Poly1305::new(Key::from_slice(&key)) }
+  }
+
+  /// Reads the key from 64 hexadecimal digits.
+  pub fn from_hex(key: &str) -> Result<Encryption, DecodeError> {
+    let bytes = hex::decode(key)?;
+    if bytes.len() != 32 {
+      return Err(DecodeError::InvalidLength(bytes.len() * 2));
+    }
+    let mut key = [0u8; 32];
+    key.copy_from_slice(&bytes);
+    Ok(Encryption::new(key))
+  }
+}
+
+const NONCE_LEN: usize = 12;
+
+impl Codec for Encryption {
+  fn name(&self) -> &str {
+    "encryption"
+  }
+  fn decode(&self, data: Vec<u8>) -> Result<Vec<u8>, CodecError> {
+    if data.len() < NONCE_LEN {
+      return Err(CodecError::Encryption("payload shorter than a nonce"));
+    }
+    let (nonce, ciphertext) = data.split_at(NONCE_LEN);
+    self.cipher.decrypt(Nonce::from_slice(nonce), ciphertext)
+      .map_err(|_| CodecError::Encryption("wrong key or corrupted data"))
+  }
+  fn encode(&self, data: Vec<u8>) -> Result<Vec<u8>, CodecError> {
+    let nonce = ChaCha20Poly1305::generate_nonce(&mut OsRng);
+    let ciphertext = self.cipher.encrypt(&nonce, &data[..])
+      .map_err(|_| CodecError::Encryption("encryption failed"))?;
+    let mut payload = nonce.to_vec();
+    payload.extend_from_slice(&ciphertext);
+    Ok(payload)
+  }
+}
+
+/// The failure of one stage of a `CodecChain`.
+#[derive(Debug)]
+pub struct StageError {
+  /// Position of the stage, from 0.
+  pub stage: usize,
+  pub codec: String,
+  pub error: CodecError,
+}
+
+impl fmt::Display for StageError {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    write!(f, "stage {} ({}): {}", self.stage, self.codec, self.error)
+  }
+}
+
+impl Error for StageError {
+  fn source(&self) -> Option<&(dyn Error + 'static)> {
+    Some(&self.error)
+  }
+}
+
+/// Codecs in decoding order: the first one is undone first.
+#[derive(Default)]
+pub struct CodecChain {
+  codecs: Vec<Box<dyn Codec>>,
+}
+
+impl CodecChain {
+  pub fn new() -> CodecChain {
+    CodecChain::default()
+  }
+
+  pub fn then<C: Codec + 'static>(mut self, codec: C) -> CodecChain {
+    self.push(Box::new(codec));
+    self
+  }
+
+  pub fn push(&mut self, codec: Box<dyn Codec>) {
+    self.codecs.push(codec);
+  }
+
+  pub fn names(&self) -> Vec<&str> {
+    self.codecs.iter().map(|codec| codec.name()).collect()
+  }
+
+  pub fn decode(&self, data: Vec<u8>) -> Result<Vec<u8>, StageError> {
+    self.codecs.iter().enumerate().try_fold(data, |data, (stage, codec)| {
+      codec.decode(data).map_err(|error| StageError { stage, codec: String::from(codec.name()), error })
+    })
+  }
+
+  /// Applies the codecs in reverse order, producing what `decode` reads.
+  pub fn encode(&self, data: Vec<u8>) -> Result<Vec<u8>, StageError> {
+    self.codecs.iter().enumerate().rev().try_fold(data, |data, (stage, codec)| {
+      codec.encode(data).map_err(|error| StageError { stage, codec: String::from(codec.name()), error })
+    })
+  }
+}
@@ -0,0 +1,338 @@
+//! `fetcher` module.
+//!
+//! The download stage of the facade with its policies: a `Cache`, retries
+//! with exponential backoff for transient failures and a circuit breaker
+//! failing fast once the source keeps failing.
+//!
+//! # Example
+//!
+//! ```rust
+//! use std::time::Duration;
+//! use structural::facade::*;
+//! use structural::facade::cache::*;
+//! use structural::facade::fetcher::*;
+//!
+//! let server = http::StandIn::start().unwrap();
+//! server.serve("/a", b"A");
+//! server.serve("/b", b"B");
+//!
+//! // Fresh entries are served from memory, the least recently used evicted.
+//! let fetcher = Fetcher::new().cache(Cache::new(1, Duration::from_secs(60)));
+//! assert_eq!(fetcher.fetch(&server.url("/a")).unwrap(), b"A");
+//! assert_eq!(fetcher.fetch(&server.url("/a")).unwrap(), b"A");
+//! assert_eq!(server.requests(), 1);
+//! fetcher.fetch(&server.url("/b")).unwrap();
+//! fetcher.fetch(&server.url("/a")).unwrap();
+//! assert_eq!(server.requests(), 3);
+//! assert_eq!(fetcher.cache_stats(), Some(CacheStats { hits: 1, revalidated: 0, misses: 3 }));
+//!
+//! // Stale entries are revalidated with their ETag.
+//! let fetcher = Fetcher::new().cache(Cache::new(10, Duration::from_secs(0)));
+//! fetcher.fetch(&server.url("/a")).unwrap();
+//! fetcher.fetch(&server.url("/a")).unwrap();
+//! server.serve("/a", b"A2");
+//! assert_eq!(fetcher.fetch(&server.url("/a")).unwrap(), b"A2");
+//! assert_eq!(fetcher.cache_stats(), Some(CacheStats { hits: 0, revalidated: 1, misses: 2 }));
+//!
+//! // A cache directory outlives the fetcher.
+//! let directory = std::env::temp_dir().join(format!("facade-cache-{}", std::process::id()));
+//! let before = server.requests();
+//! Fetcher::new().cache(Cache::new(10, Duration::from_secs(60)).with_directory(&directory))
+//!   .fetch(&server.url("/b")).unwrap();
+//! let fetcher = Fetcher::new().cache(Cache::new(10, Duration::from_secs(60)).with_directory(&directory));
+//! assert_eq!(fetcher.fetch(&server.url("/b")).unwrap(), b"B");
+//! assert_eq!(server.requests(), before + 1);
+//! std::fs::remove_dir_all(&directory).unwrap();
+//! ```
+//!
+//! Retries and circuit breaker:
+//!
+//! ```rust
+//! use std::time::Duration;
+//! use structural::facade::*;
+//! use structural::facade::fetcher::*;
+//!
+//! let server = http::StandIn::start().unwrap();
+//! server.serve("/flaky", b"ok");
+//! server.fail_times("/flaky", 2, 503);
+//! let fetcher = Fetcher::new().retry(RetryPolicy::new(3, Duration::from_millis(1)));
+//! assert_eq!(fetcher.fetch(&server.url("/flaky")).unwrap(), b"ok");
+//! assert_eq!(server.requests(), 3);
+//!
+//! // Client errors are not retried.
+//! assert!(fetcher.fetch(&server.url("/missing")).is_err());
+//! assert_eq!(server.requests(), 4);
+//!
+//! server.fail("/down", 500);
+//! let fetcher = Fetcher::new().circuit_breaker(CircuitBreaker::new(2, Duration::from_millis(50)));
+//! assert!(fetcher.fetch(&server.url("/missing")).is_err());
+//! assert!(fetcher.fetch(&server.url("/missing")).is_err());
+//! assert_eq!(fetcher.breaker_state(), Some(BreakerState::Closed));
+//! assert!(fetcher.fetch(&server.url("/down")).is_err());
+//! assert!(fetcher.fetch(&server.url("/down")).is_err());
+//! assert_eq!(fetcher.breaker_state(), Some(BreakerState::Open));
+//! match fetcher.fetch(&server.url("/down")) {
+//!   Err(DownloadError::CircuitOpen { .. }) => {}
+//!   other => panic!("unexpected {:?}", other),
+//! }
+//! assert_eq!(server.requests(), 8);
+//!
+//! std::thread::sleep(Duration::from_millis(60));
+//! assert_eq!(fetcher.breaker_state(), Some(BreakerState::HalfOpen));
+//! server.serve("/down", b"up");
+//! assert_eq!(fetcher.fetch(&server.url("/down")).unwrap(), b"up");
+//! assert_eq!(fetcher.breaker_state(), Some(BreakerState::Closed));
+//! ```
+
+use std::cell::{Cell, RefCell};
+use std::fs;
+use std::thread;
+use std::time::{Duration, Instant, UNIX_EPOCH};
+
+use facade::cache::{Cache, CacheStats};
+use facade::{http, DownloadError};
+
+/// Outcome of a conditional download.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Fetched {
+  Modified { body: Vec<u8>, validator: Option<String> },
+  NotModified,
+}
+
+/// Fetches `url` unless its validator is still `validator`. The validator
+/// of a file is made of its size and modification time.
+pub fn fetch_if_modified(url: &str, validator: Option<&str>) -> Result<Fetched, DownloadError> {
+  let scheme = match url.find("://") {
+    Some(at) => &url[..at],
+    None => return Err(DownloadError::InvalidUrl(String::from(url))),
+  };
+  match scheme {
+    "file" => {
+      let path = &url["file://".len()..];
+      let path = path.strip_prefix("localhost").unwrap_or(path);
+      let metadata = fs::metadata(path)?;
+      let modified = metadata.modified()?.duration_since(UNIX_EPOCH).map(|d| d.as_nanos()).unwrap_or(0);
+      let current = format!("\"{}-{}\"", metadata.len(), modified);
+      if validator == Some(current.as_str()) {
+        return Ok(Fetched::NotModified);
+      }
+      Ok(Fetched::Modified { body: fs::read(path)?, validator: Some(current) })
+    }
+    "http" => {
+      let response = http::get_if_none_match(url, validator)?;
+      if response.status == 304 {
+        Ok(Fetched::NotModified)
+      } else {
+        Ok(Fetched::Modified { body: response.body, validator: response.etag })
+      }
+    }
+    _ => Err(DownloadError::UnsupportedScheme(String::from(scheme))),
+  }
+}
+
+/// How many times a transient failure is tried, waiting `initial`, then
+/// `factor` times longer after each attempt, up to `max`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RetryPolicy {
+  attempts: u32,
+  initial: Duration,
+  factor: u32,
+  max: Duration,
+}
+
+impl Default for RetryPolicy {
+  fn default() -> RetryPolicy {
+    RetryPolicy::none()
+  }
+}
+
+impl RetryPolicy {
+  /// Tries at most `attempts` times, doubling the wait from `initial`.
+  pub fn new(attempts: u32, initial: Duration) -> RetryPolicy {
+    RetryPolicy { attempts: attempts.max(1), initial, factor: 2, max: Duration::from_secs(60) }
+  }
+
+  /// Tries once.
+  pub fn none() -> RetryPolicy {
+    RetryPolicy::new(1, Duration::from_secs(0))
+  }
+
+  pub fn factor(mut self, factor: u32) -> RetryPolicy {
+    self.factor = factor;
+    self
+  }
+
+  pub fn max_backoff(mut self, max: Duration) -> RetryPolicy {
+    self.max = max;
+    self
+  }
+
+  pub fn attempts(&self) -> u32 {
+    self.attempts
+  }
+
+  /// Wait after the failed attempt `attempt`, counted from 1.
+  pub fn backoff(&self, attempt: u32) -> Duration {
+    let factor = self.factor.saturating_pow(attempt.saturating_sub(1));
+    self.initial.checked_mul(factor).unwrap_or(self.max).min(self.max)
+  }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BreakerState {
+  /// Requests go through.
+  Closed,
+  /// Requests fail fast.
+  Open,
+  /// The cooldown is over: the next request is a trial.
+  HalfOpen,
+}
+
+/// Opens after `threshold` failed downloads in a row and stays open for
+/// `cooldown`; then one trial download closes it again, or reopens it.
+pub struct CircuitBreaker {
+  threshold: u32,
+  cooldown: Duration,
+  failures: Cell<u32>,
+  opened_at: Cell<Option<Instant>>,
+}
+
+impl CircuitBreaker {
+  pub fn new(threshold: u32, cooldown: Duration) -> CircuitBreaker {
+    CircuitBreaker {
+      threshold: threshold.max(1),
+      cooldown,
+      failures: Cell::new(0),
+      opened_at: Cell::new(None),
+    }
+  }
+
+  pub fn state(&self) -> BreakerState {
+    match self.opened_at.get() {
+      None => BreakerState::Closed,
+      Some(at) if at.elapsed() < self.cooldown => BreakerState::Open,
+      Some(_) => BreakerState::HalfOpen,
+    }
+  }
+
+  fn check(&self) -> Result<(), DownloadError> {
+    match self.opened_at.get() {
+      Some(at) if at.elapsed() < self.cooldown => {
+        Err(DownloadError::CircuitOpen { retry_in: self.cooldown - at.elapsed() })
+      }
+      _ => Ok(()),
+    }
+  }
+
+  fn success(&self) {
+    self.failures.set(0);
+    self.opened_at.set(None);
+  }
+
+  fn failure(&self) {
+    let failures = self.failures.get() + 1;
+    self.failures.set(failures);
+    if failures >= self.threshold || self.opened_at.get().is_some() {
+      self.opened_at.set(Some(Instant::now()));
+    }
+  }
+}
+
+/// Downloads through an optional cache, with retries and an optional
+/// circuit breaker. Without any of them, it is a plain `facade::fetch`.
+#[derive(Default)]
+pub struct Fetcher {
+  cache: Option<RefCell<Cache>>,
+  retry: RetryPolicy,
+  breaker: Option<CircuitBreaker>,
+}
+
+impl Fetcher {
+  pub fn new() -> Fetcher {
+    Fetcher::default()
+  }
+
+  pub fn cache(mut self, cache: Cache) -> Fetcher {
+    self.cache = Some(RefCell::new(cache));
+    self
+  }
+
+  pub fn retry(mut self, policy: RetryPolicy) -> Fetcher {
+    self.retry = policy;
+    self
+  }
+
+  pub fn circuit_breaker(mut self, breaker: CircuitBreaker) -> Fetcher {
+    self.breaker = Some(breaker);
+    self
+  }
+
+  pub fn cache_stats(&self) -> Option<CacheStats> {
+    self.cache.as_ref().map(|cache| cache.borrow().stats())
+  }
+
+  pub fn breaker_state(&self) -> Option<BreakerState> {
+    self.breaker.as_ref().map(CircuitBreaker::state)
+  }
+
+  pub fn fetch(&self, url: &str) -> Result<Vec<u8>, DownloadError> {
+    let cached = self.cache.as_ref().and_then(|cache| {
+      let mut cache = cache.borrow_mut();
+      let ttl = cache.ttl();
+      let entry = cache.get(url)?;
+      if entry.is_fresh(ttl) {
+        cache.record_hit();
+      }
+      Some((entry, ttl))
+    });
+    if let Some((ref entry, ttl)) = cached {
+      if entry.is_fresh(ttl) {
+        return Ok(entry.body.clone());
+      }
+    }
+
+    if let Some(ref breaker) = self.breaker {
+      breaker.check()?;
+    }
+    let validator = cached.as_ref().and_then(|(entry, _)| entry.validator.as_deref());
+    let result = self.fetch_with_retries(url, validator);
+    if let Some(ref breaker) = self.breaker {
+      // A client error such as a 404 says nothing about the source health.
+      match result {
+        Err(ref error) if error.is_transient() => breaker.failure(),
+        _ => breaker.success(),
+      }
+    }
+
+    match result? {
+      Fetched::NotModified => {
+        let (entry, _) = cached.ok_or_else(|| {
+          DownloadError::InvalidResponse(format!("{} answered not modified to an uncached URL", url))
+        })?;
+        if let Some(ref cache) = self.cache {
+          cache.borrow_mut().revalidate(url);
+        }
+        Ok(entry.body)
+      }
+      Fetched::Modified { body, validator } => {
+        if let Some(ref cache) = self.cache {
+          cache.borrow_mut().put(url, body.clone(), validator);
+        }
+        Ok(body)
+      }
+    }
+  }
+
+  fn fetch_with_retries(&self, url: &str, validator: Option<&str>) -> Result<Fetched, DownloadError> {
+    let mut attempt = 1;
+    loop {
+      match fetch_if_modified(url, validator) {
+        Err(ref error) if error.is_transient() && attempt < self.retry.attempts() => {
+          thread::sleep(self.retry.backoff(attempt));
+          attempt += 1;
+        }
+        result => return result,
+      }
+    }
+  }
+}
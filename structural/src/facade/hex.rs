@@ -0,0 +1,43 @@
+//! `hex` module.
+//!
+//! Two hexadecimal digits per byte, in either case.
+//!
+//! # Example
+//!
+//! ```rust
+//! use structural::facade::hex;
+//!
+//! assert_eq!(hex::encode(b"Ivan"), "4976616e");
+//! assert_eq!(hex::decode("4976 616E\n").unwrap(), b"Ivan");
+//! assert!(hex::decode("497").is_err());
+//! assert!(hex::decode("49xx").is_err());
+//! ```
+
+use facade::DecodeError;
+
+pub fn encode(data: &[u8]) -> String {
+  data.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+/// Decodes `text`, ignoring ASCII whitespace.
+pub fn decode<T: AsRef<[u8]>>(text: T) -> Result<Vec<u8>, DecodeError> {
+  let text = text.as_ref();
+  let mut data = Vec::with_capacity(text.len() / 2);
+  let mut high = None;
+  let mut digits = 0;
+  for (position, &byte) in text.iter().enumerate() {
+    if byte.is_ascii_whitespace() {
+      continue;
+    }
+    let value = (byte as char).to_digit(16).ok_or(DecodeError::InvalidByte { position, byte })? as u8;
+    digits += 1;
+    match high.take() {
+      Some(high) => data.push(high << 4 | value),
+      None => high = Some(value),
+    }
+  }
+  if high.is_some() {
+    return Err(DecodeError::InvalidLength(digits));
+  }
+  Ok(data)
+}
@@ -0,0 +1,256 @@
+//! `http` module.
+//!
+//! A minimal HTTP/1.0 client for `http://` URLs and `StandIn`, a local
+//! server answering canned responses, to run the facade without a network.
+//! The stand-in tags each body with an `ETag` and answers `304 Not
+//! Modified` to a matching `If-None-Match`.
+//!
+//! # Example
+//!
+//! ```rust
+//! use structural::facade::http::*;
+//! use structural::facade::DownloadError;
+//!
+//! let server = StandIn::start().unwrap();
+//! server.serve("/hello", b"Hello!");
+//! server.fail("/broken", 503);
+//!
+//! assert_eq!(get(&server.url("/hello")).unwrap(), b"Hello!");
+//! match get(&server.url("/broken")) {
+//!   Err(DownloadError::Status { code, .. }) => assert_eq!(code, 503),
+//!   other => panic!("unexpected {:?}", other),
+//! }
+//! match get(&server.url("/missing")) {
+//!   Err(DownloadError::Status { code, .. }) => assert_eq!(code, 404),
+//!   other => panic!("unexpected {:?}", other),
+//! }
+//! assert_eq!(server.requests(), 3);
+//!
+//! let response = get_if_none_match(&server.url("/hello"), None).unwrap();
+//! let etag = response.etag.unwrap();
+//! assert_eq!(get_if_none_match(&server.url("/hello"), Some(&etag)).unwrap().status, 304);
+//! server.fail("/unchanged", 304);
+//! assert!(get(&server.url("/unchanged")).is_err());
+//!
+//! server.fail_times("/flaky", 1, 500);
+//! server.serve("/flaky", b"Back");
+//! assert!(get(&server.url("/flaky")).is_err());
+//! assert_eq!(get(&server.url("/flaky")).unwrap(), b"Back");
+//! ```
+
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+use std::io;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+use facade::DownloadError;
+
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+/// A successful or not-modified response.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Response {
+  pub status: u16,
+  pub etag: Option<String>,
+  pub body: Vec<u8>,
+}
+
+/// Fetches the body of an `http://host[:port]/path` URL.
+pub fn get(url: &str) -> Result<Vec<u8>, DownloadError> {
+  Ok(get_if_none_match(url, None)?.body)
+}
+
+/// Fetches `url` unless its `ETag` is still `etag`, in which case the
+/// response has the status 304 and no body. Other statuses than 2xx and
+/// 304 are errors, and so is a 304 when no `etag` was sent.
+pub fn get_if_none_match(url: &str, etag: Option<&str>) -> Result<Response, DownloadError> {
+  let rest = url.strip_prefix("http://").ok_or_else(|| DownloadError::InvalidUrl(String::from(url)))?;
+  let (authority, path) = match rest.find('/') {
+    Some(at) => (&rest[..at], &rest[at..]),
+    None => (rest, "/"),
+  };
+  if authority.is_empty() {
+    return Err(DownloadError::InvalidUrl(String::from(url)));
+  }
+  let address = if authority.contains(':') { String::from(authority) } else { format!("{}:80", authority) };
+
+  let mut stream = TcpStream::connect(&address)?;
+  stream.set_read_timeout(Some(TIMEOUT))?;
+  stream.set_write_timeout(Some(TIMEOUT))?;
+  let condition = etag.map(|etag| format!("If-None-Match: {}\r\n", etag)).unwrap_or_default();
+  write!(stream, "GET {} HTTP/1.0\r\nHost: {}\r\n{}Connection: close\r\n\r\n", path, authority, condition)?;
+
+  let mut response = Vec::new();
+  stream.read_to_end(&mut response)?;
+  let end = response.windows(4).position(|w| w == b"\r\n\r\n")
+    .ok_or_else(|| DownloadError::InvalidResponse(String::from("no end of headers")))?;
+  let head = String::from_utf8_lossy(&response[..end]).into_owned();
+  let mut lines = head.lines();
+  let status_line = lines.next().unwrap_or("");
+  let mut parts = status_line.splitn(3, ' ');
+  let code = match (parts.next(), parts.next()) {
+    (Some(version), Some(code)) if version.starts_with("HTTP/") => code.parse::<u16>().ok(),
+    _ => None,
+  };
+  let code = code.ok_or_else(|| DownloadError::InvalidResponse(String::from(status_line)))?;
+  if !(200..300).contains(&code) && code != 304 {
+    return Err(DownloadError::Status { code, reason: String::from(parts.next().unwrap_or("")) });
+  }
+  if code == 304 && etag.is_none() {
+    return Err(DownloadError::InvalidResponse(String::from("304 to an unconditional request")));
+  }
+  let etag = lines
+    .filter_map(|line| line.split_once(':'))
+    .find(|(name, _)| name.trim().eq_ignore_ascii_case("etag"))
+    .map(|(_, value)| String::from(value.trim()));
+  let body = if code == 304 { Vec::new() } else { response.split_off(end + 4) };
+  Ok(Response { status: code, etag, body })
+}
+
+#[derive(Default)]
+struct Routes {
+  responses: HashMap<String, (u16, Vec<u8>)>,
+  /// Failures to answer before the response, with their status.
+  failures: HashMap<String, (usize, u16)>,
+}
+
+/// A local HTTP server answering canned responses on a free port, until
+/// it is dropped.
+pub struct StandIn {
+  address: SocketAddr,
+  routes: Arc<Mutex<Routes>>,
+  requests: Arc<AtomicUsize>,
+  stop: Arc<AtomicBool>,
+  worker: Option<thread::JoinHandle<()>>,
+}
+
+impl StandIn {
+  pub fn start() -> io::Result<StandIn> {
+    let listener = TcpListener::bind("127.0.0.1:0")?;
+    let address = listener.local_addr()?;
+    let routes = Arc::new(Mutex::new(Routes::default()));
+    let requests = Arc::new(AtomicUsize::new(0));
+    let stop = Arc::new(AtomicBool::new(false));
+    let worker = {
+      let (routes, requests, stop) = (routes.clone(), requests.clone(), stop.clone());
+      thread::spawn(move || {
+        for stream in listener.incoming() {
+          if stop.load(Ordering::SeqCst) {
+            break;
+          }
+          if let Ok(stream) = stream {
+            requests.fetch_add(1, Ordering::SeqCst);
+            let _ = answer(stream, &routes);
+          }
+        }
+      })
+    };
+    Ok(StandIn { address, routes, requests, stop, worker: Some(worker) })
+  }
+
+  pub fn address(&self) -> SocketAddr {
+    self.address
+  }
+
+  /// URL of `path` on this server.
+  pub fn url(&self, path: &str) -> String {
+    format!("http://{}{}", self.address, path)
+  }
+
+  /// Answers `body` with a 200 status on `path`.
+  pub fn serve(&self, path: &str, body: &[u8]) {
+    self.routes.lock().unwrap().responses.insert(String::from(path), (200, body.to_vec()));
+  }
+
+  /// Answers an empty body with `status` on `path`.
+  pub fn fail(&self, path: &str, status: u16) {
+    self.routes.lock().unwrap().responses.insert(String::from(path), (status, Vec::new()));
+  }
+
+  /// Answers `status` to the next `times` requests on `path`, then goes
+  /// back to its response.
+  pub fn fail_times(&self, path: &str, times: usize, status: u16) {
+    self.routes.lock().unwrap().failures.insert(String::from(path), (times, status));
+  }
+
+  /// Number of requests received so far.
+  pub fn requests(&self) -> usize {
+    self.requests.load(Ordering::SeqCst)
+  }
+}
+
+impl Drop for StandIn {
+  fn drop(&mut self) {
+    self.stop.store(true, Ordering::SeqCst);
+    // Wakes the worker blocked on `accept`.
+    let _ = TcpStream::connect(self.address);
+    if let Some(worker) = self.worker.take() {
+      let _ = worker.join();
+    }
+  }
+}
+
+fn answer(stream: TcpStream, routes: &Mutex<Routes>) -> io::Result<()> {
+  stream.set_read_timeout(Some(TIMEOUT))?;
+  let mut reader = BufReader::new(stream);
+  let mut request_line = String::new();
+  reader.read_line(&mut request_line)?;
+  let mut if_none_match = None;
+  loop {
+    let mut header = String::new();
+    if reader.read_line(&mut header)? == 0 || header.trim().is_empty() {
+      break;
+    }
+    if let Some((name, value)) = header.split_once(':') {
+      if name.trim().eq_ignore_ascii_case("if-none-match") {
+        if_none_match = Some(String::from(value.trim()));
+      }
+    }
+  }
+  let path = request_line.split_whitespace().nth(1).unwrap_or("/");
+  let (status, body) = {
+    let mut routes = routes.lock().unwrap();
+    match routes.failures.get_mut(path) {
+      Some(failure) if failure.0 > 0 => {
+        failure.0 -= 1;
+        (failure.1, Vec::new())
+      }
+      _ => routes.responses.get(path).cloned().unwrap_or((404, Vec::new())),
+    }
+  };
+  let mut stream = reader.into_inner();
+  if status != 200 {
+    write!(stream, "HTTP/1.0 {} {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n", status, reason(status), body.len())?;
+  } else {
+    let etag = etag(&body);
+    if if_none_match.as_ref() == Some(&etag) {
+      write!(stream, "HTTP/1.0 304 Not Modified\r\nETag: {}\r\nConnection: close\r\n\r\n", etag)?;
+      return stream.flush();
+    }
+    write!(stream, "HTTP/1.0 200 OK\r\nETag: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n", etag, body.len())?;
+  }
+  stream.write_all(&body)?;
+  stream.flush()
+}
+
+fn etag(body: &[u8]) -> String {
+  let mut hasher = DefaultHasher::new();
+  body.hash(&mut hasher);
+  format!("\"{:016x}\"", hasher.finish())
+}
+
+fn reason(status: u16) -> &'static str {
+  match status {
+    200 => "OK",
+    404 => "Not Found",
+    500 => "Internal Server Error",
+    503 => "Service Unavailable",
+    _ => "Status",
+  }
+}
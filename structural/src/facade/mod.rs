@@ -1,106 +1,609 @@
 //! `facade` module.
 //!
+//! `DataReader` hides three stages behind one call: downloading a payload
+//! from a `file://` or `http://` URL, decoding it through a chain of codecs
+//! (base64 by default) and deserializing the JSON into a `Person`. Each stage has its own error
+//! type, gathered in `ReadError`. Downloads may go through a `Fetcher`,
+//! adding a cache, retries and a circuit breaker.
 
-  
-#[derive(Debug)]
+pub mod base64;
+pub mod cache;
+pub mod codec;
+pub mod fetcher;
+pub mod hex;
+pub mod http;
+
+use std::error::Error;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::string::FromUtf8Error;
+use std::time::Duration;
+
+use csv;
+use serde_json;
+
+use facade::codec::StageError;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Person {
   name: Option<String>,
   age: Option<i32>,
 }
 
+impl Person {
+  pub fn new(name: &str, age: i32) -> Person {
+    Person { name: Some(String::from(name)), age: Some(age) }
+  }
+
+  pub fn name(&self) -> Option<&str> {
+    self.name.as_deref()
+  }
+
+  pub fn age(&self) -> Option<i32> {
+    self.age
+  }
+}
+
+#[derive(Debug)]
+pub enum DownloadError {
+  /// The URL has no `scheme://` prefix.
+  InvalidUrl(String),
+  UnsupportedScheme(String),
+  Io(io::Error),
+  InvalidResponse(String),
+  Status { code: u16, reason: String },
+  /// The circuit breaker fails fast after repeated failures.
+  CircuitOpen { retry_in: Duration },
+}
+
+impl DownloadError {
+  /// Whether trying again later may succeed: lost connections, timeouts,
+  /// truncated responses, server errors and throttling.
+  pub fn is_transient(&self) -> bool {
+    match *self {
+      DownloadError::Io(ref e) => matches!(
+        e.kind(),
+        io::ErrorKind::ConnectionRefused
+          | io::ErrorKind::ConnectionReset
+          | io::ErrorKind::ConnectionAborted
+          | io::ErrorKind::TimedOut
+          | io::ErrorKind::WouldBlock
+          | io::ErrorKind::Interrupted
+          | io::ErrorKind::UnexpectedEof
+          | io::ErrorKind::BrokenPipe
+      ),
+      DownloadError::InvalidResponse(_) => true,
+      DownloadError::Status { code, .. } => code >= 500 || code == 429,
+      _ => false,
+    }
+  }
+}
+
+impl fmt::Display for DownloadError {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match *self {
+      DownloadError::InvalidUrl(ref url) => write!(f, "invalid URL `{}`", url),
+      DownloadError::UnsupportedScheme(ref scheme) => write!(f, "unsupported scheme `{}`", scheme),
+      DownloadError::Io(ref e) => write!(f, "{}", e),
+      DownloadError::InvalidResponse(ref line) => write!(f, "invalid HTTP response `{}`", line),
+      DownloadError::Status { code, ref reason } => write!(f, "HTTP status {} {}", code, reason),
+      DownloadError::CircuitOpen { retry_in } => {
+        write!(f, "circuit open, retry in {} ms", retry_in.as_millis())
+      }
+    }
+  }
+}
+
+impl Error for DownloadError {
+  fn source(&self) -> Option<&(dyn Error + 'static)> {
+    match *self {
+      DownloadError::Io(ref e) => Some(e),
+      _ => None,
+    }
+  }
+}
+
+impl From<io::Error> for DownloadError {
+  fn from(error: io::Error) -> DownloadError {
+    DownloadError::Io(error)
+  }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum DecodeError {
+  InvalidByte { position: usize, byte: u8 },
+  InvalidLength(usize),
+  InvalidUtf8(FromUtf8Error),
+}
+
+impl fmt::Display for DecodeError {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match *self {
+      DecodeError::InvalidByte { position, byte } => write!(f, "invalid byte 0x{:02x} at {}", byte, position),
+      DecodeError::InvalidLength(length) => write!(f, "invalid length {}", length),
+      DecodeError::InvalidUtf8(ref e) => write!(f, "{}", e),
+    }
+  }
+}
+
+impl Error for DecodeError {
+  fn source(&self) -> Option<&(dyn Error + 'static)> {
+    match *self {
+      DecodeError::InvalidUtf8(ref e) => Some(e),
+      _ => None,
+    }
+  }
+}
+
+impl From<FromUtf8Error> for DecodeError {
+  fn from(error: FromUtf8Error) -> DecodeError {
+    DecodeError::InvalidUtf8(error)
+  }
+}
+
+#[derive(Debug)]
+pub enum ParseError {
+  Json(serde_json::Error),
+  /// An invalid line of an NDJSON stream, numbered from 1.
+  Ndjson { line: usize, error: serde_json::Error },
+  Csv(csv::Error),
+  /// A single record was expected, the payload has none.
+  NoRecord,
+}
+
+impl fmt::Display for ParseError {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match *self {
+      ParseError::Json(ref e) => write!(f, "{}", e),
+      ParseError::Ndjson { line, ref error } => write!(f, "line {}: {}", line, error),
+      ParseError::Csv(ref e) => write!(f, "{}", e),
+      ParseError::NoRecord => write!(f, "no record"),
+    }
+  }
+}
+
+impl Error for ParseError {
+  fn source(&self) -> Option<&(dyn Error + 'static)> {
+    match *self {
+      ParseError::Json(ref e) => Some(e),
+      ParseError::Ndjson { ref error, .. } => Some(error),
+      ParseError::Csv(ref e) => Some(e),
+      ParseError::NoRecord => None,
+    }
+  }
+}
+
+impl From<serde_json::Error> for ParseError {
+  fn from(error: serde_json::Error) -> ParseError {
+    ParseError::Json(error)
+  }
+}
+
+impl From<csv::Error> for ParseError {
+  fn from(error: csv::Error) -> ParseError {
+    ParseError::Csv(error)
+  }
+}
+
+/// The stage of a `DataReader` that failed.
+#[derive(Debug)]
+pub enum ReadError {
+  Download(DownloadError),
+  Decode(DecodeError),
+  /// A stage of a codec chain failed.
+  Codec(StageError),
+  Parse(ParseError),
+}
+
+impl fmt::Display for ReadError {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match *self {
+      ReadError::Download(ref e) => write!(f, "download failed: {}", e),
+      ReadError::Decode(ref e) => write!(f, "decoding failed: {}", e),
+      ReadError::Codec(ref e) => write!(f, "decoding failed at {}", e),
+      ReadError::Parse(ref e) => write!(f, "parsing failed: {}", e),
+    }
+  }
+}
+
+impl Error for ReadError {
+  fn source(&self) -> Option<&(dyn Error + 'static)> {
+    match *self {
+      ReadError::Download(ref e) => Some(e),
+      ReadError::Decode(ref e) => Some(e),
+      ReadError::Codec(ref e) => Some(e),
+      ReadError::Parse(ref e) => Some(e),
+    }
+  }
+}
+
+impl From<DownloadError> for ReadError {
+  fn from(error: DownloadError) -> ReadError {
+    ReadError::Download(error)
+  }
+}
+
+impl From<DecodeError> for ReadError {
+  fn from(error: DecodeError) -> ReadError {
+    ReadError::Decode(error)
+  }
+}
+
+impl From<StageError> for ReadError {
+  fn from(error: StageError) -> ReadError {
+    ReadError::Codec(error)
+  }
+}
+
+impl From<ParseError> for ReadError {
+  fn from(error: ParseError) -> ReadError {
+    ReadError::Parse(error)
+  }
+}
+
+/// Fetches the content of a `file://` or `http://` URL.
+pub fn fetch(url: &str) -> Result<Vec<u8>, DownloadError> {
+  let scheme = match url.find("://") {
+    Some(at) => &url[..at],
+    None => return Err(DownloadError::InvalidUrl(String::from(url))),
+  };
+  match scheme {
+    "file" => {
+      let path = &url["file://".len()..];
+      let path = path.strip_prefix("localhost").unwrap_or(path);
+      Ok(fs::read(path)?)
+    }
+    "http" => http::get(url),
+    _ => Err(DownloadError::UnsupportedScheme(String::from(scheme))),
+  }
+}
+
 pub mod with_struct {
   //! `with_struct` module.
-  //! 
+  //!
   //! # Example
-  //! 
+  //!
   //! ``` rust
-  //!  use structural::facade::with_derive_trait::*;
-  //! 
-  //!  let url = String::from("google.com");
-  //!  let reader = DataReader;
-  //!  println!("{:?}", reader.read_person(url));
+  //! use structural::facade::*;
+  //! use structural::facade::codec::*;
+  //! use structural::facade::with_struct::*;
+  //!
+  //! let path = std::env::temp_dir().join(format!("person-{}.b64", std::process::id()));
+  //! std::fs::write(&path, base64::encode(br#"{ "name": "Ivan", "age": 26 }"#)).unwrap();
+  //! let reader = DataReader::new();
+  //! let person = reader.read_person(format!("file://{}", path.display())).unwrap();
+  //! assert_eq!(person, Person::new("Ivan", 26));
+  //! std::fs::remove_file(&path).unwrap();
+  //!
+  //! let server = http::StandIn::start().unwrap();
+  //! server.serve("/person", base64::encode(br#"{ "name": "Laurent Deleris", "age": 50 }"#).as_bytes());
+  //! server.serve("/garbage", b"not base64!");
+  //! server.serve("/not-json", base64::encode(b"name=Ivan").as_bytes());
+  //! let person = reader.read_person(server.url("/person")).unwrap();
+  //! assert_eq!(person.name(), Some("Laurent Deleris"));
+  //!
+  //! match reader.read_person(String::from("ftp://example.com/person")) {
+  //!   Err(ReadError::Download(DownloadError::UnsupportedScheme(scheme))) => assert_eq!(scheme, "ftp"),
+  //!   other => panic!("unexpected {:?}", other),
+  //! }
+  //! assert!(matches!(reader.read_person(server.url("/missing")), Err(ReadError::Download(_))));
+  //! assert!(matches!(reader.read_person(server.url("/garbage")), Err(ReadError::Codec(_))));
+  //! assert!(matches!(reader.read_person(server.url("/not-json")), Err(ReadError::Parse(_))));
+  //! ```
+  //!
+  //! The codecs are chosen per reader:
+  //!
+  //! ``` rust
+  //! use structural::facade::*;
+  //! use structural::facade::codec::*;
+  //! use structural::facade::with_struct::*;
+  //!
+  //! let key = Encryption::from_hex(&"2a".repeat(32)).unwrap();
+  //! let chain = CodecChain::new().then(Hex).then(key).then(Gzip);
+  //! let payload = chain.encode(br#"{ "name": "Ivan", "age": 26 }"#.to_vec()).unwrap();
+  //! let server = http::StandIn::start().unwrap();
+  //! server.serve("/person", &payload);
+  //!
+  //! let reader = DataReader::with_codecs(chain);
+  //! assert_eq!(reader.read_person(server.url("/person")).unwrap(), Person::new("Ivan", 26));
+  //!
+  //! let wrong_key = Encryption::new([0; 32]);
+  //! let reader = DataReader::with_codecs(CodecChain::new().then(Hex).then(wrong_key).then(Gzip));
+  //! match reader.read_person(server.url("/person")) {
+  //!   Err(ReadError::Codec(error)) => assert_eq!(error.codec, "encryption"),
+  //!   other => panic!("unexpected {:?}", other),
+  //! }
   //! ```
+  //!
+  //! Any deserializable type can be read, one record or a batch, from JSON,
+  //! NDJSON or CSV:
+  //!
+  //! ``` rust
+  //! #[macro_use]
+  //! extern crate serde_derive;
+  //! extern crate structural;
+  //!
+  //! use structural::facade::*;
+  //! use structural::facade::codec::*;
+  //! use structural::facade::with_struct::*;
+  //!
+  //! #[derive(Debug, PartialEq, Deserialize)]
+  //! struct City {
+  //!   name: String,
+  //!   population: u32,
+  //! }
+  //!
+  //! # fn main() {
+  //! let server = http::StandIn::start().unwrap();
+  //! server.serve("/cities.json", br#"[{ "name": "Albi", "population": 48970 }, { "name": "Rodez", "population": 24057 }]"#);
+  //! server.serve("/cities.ndjson", b"{ \"name\": \"Albi\", \"population\": 48970 }\n\n{ \"name\": \"Rodez\" }\n");
+  //! server.serve("/cities.csv", b"name,population\nAlbi,48970\nRodez,24057\n");
+  //!
+  //! let reader: DataReader<City> = DataReader::with_codecs(CodecChain::new());
+  //! let cities = reader.read_all(server.url("/cities.json")).unwrap();
+  //! assert_eq!(cities[1], City { name: String::from("Rodez"), population: 24057 });
+  //!
+  //! let reader = reader.format(Format::Csv);
+  //! assert_eq!(reader.read_all(server.url("/cities.csv")).unwrap(), cities);
+  //! assert_eq!(reader.read(server.url("/cities.csv")).unwrap().name, "Albi");
+  //!
+  //! let reader = reader.format(Format::Ndjson);
+  //! match reader.read_all(server.url("/cities.ndjson")) {
+  //!   Err(ReadError::Parse(ParseError::Ndjson { line, .. })) => assert_eq!(line, 3),
+  //!   other => panic!("unexpected {:?}", other),
+  //! }
+  //! assert_eq!(reader.read(server.url("/cities.ndjson")).unwrap().population, 48970);
+  //! # }
+  //! ```
+  //!
+  //! Downloads can be cached and retried:
+  //!
+  //! ``` rust
+  //! use std::time::Duration;
+  //! use structural::facade::*;
+  //! use structural::facade::cache::*;
+  //! use structural::facade::fetcher::*;
+  //! use structural::facade::with_struct::*;
+  //!
+  //! let server = http::StandIn::start().unwrap();
+  //! server.serve("/person", base64::encode(br#"{ "name": "Ivan", "age": 26 }"#).as_bytes());
+  //! server.fail_times("/person", 1, 503);
+  //! let reader = DataReader::new().fetcher(
+  //!   Fetcher::new()
+  //!     .cache(Cache::new(16, Duration::from_secs(60)))
+  //!     .retry(RetryPolicy::new(3, Duration::from_millis(1))),
+  //! );
+  //! assert_eq!(reader.read_person(server.url("/person")).unwrap(), Person::new("Ivan", 26));
+  //! assert_eq!(reader.read_person(server.url("/person")).unwrap(), Person::new("Ivan", 26));
+  //! assert_eq!(server.requests(), 2);
+  //! assert_eq!(reader.downloads().cache_stats().unwrap().hits, 1);
+  //! ```
+
+  use std::marker::PhantomData;
+
+  use csv;
+  use serde::de::DeserializeOwned;
+  use serde_json;
 
-  use facade::Person;
+  use facade::codec::{Base64, CodecChain, CodecError, StageError};
+  use facade::fetcher::Fetcher;
+  use facade::{DownloadError, ParseError, Person, ReadError};
 
-  struct DataDownloader;
-  impl DataDownloader {
-    fn download(&self, url: String) -> String {
-      println!("Downloading from: {}", url);
-      String::from("ew0KICAgICJuYW1lIjogIkl2YW4iLA0KICAgICJhZ2UiOiAyNg0KfQ==")
-    } 
+  /// Layout of the decoded payload.
+  #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+  pub enum Format {
+    /// One JSON value, or a JSON array for a batch.
+    Json,
+    /// One JSON value per line; blank lines are skipped.
+    Ndjson,
+    /// A header row naming the fields, then one record per row.
+    Csv,
+  }
+
+  struct DataDownloader<'a> {
+    fetcher: &'a Fetcher,
+  }
+  impl<'a> DataDownloader<'a> {
+    fn download(&self, url: String) -> Result<Vec<u8>, DownloadError> {
+      self.fetcher.fetch(&url)
+    }
   }
 
-  struct DataDecoder;
-  impl DataDecoder {
-    fn decode(&self, data: String) -> String {
-      println!("Decoding from: {}", data);
-      String::from("{\n\t\"name\": \"Laurent Deleris\",\n\t\"age\": 50\n}")
+  struct DataDecoder<'a> {
+    codecs: &'a CodecChain,
+  }
+  impl<'a> DataDecoder<'a> {
+    /// Runs the codecs, then checks the text is UTF-8, reported as one
+    /// more stage.
+    fn decode(&self, data: Vec<u8>) -> Result<String, StageError> {
+      let data = self.codecs.decode(data)?;
+      String::from_utf8(data).map_err(|e| StageError {
+        stage: self.codecs.names().len(),
+        codec: String::from("utf-8"),
+        error: CodecError::Decode(e.into()),
+      })
     }
   }
 
-  struct DataDeserializer;
+  struct DataDeserializer {
+    format: Format,
+  }
   impl DataDeserializer {
-    fn parse(&self, data: String) -> Option<Person> {
-      println!("Parsing from: {}", data);
-      Some(
-          Person {
-          name: Some(String::from("Laurent Deleris")),
-          age: Some(50),
-          }
-      )            
+    fn parse<T: DeserializeOwned>(&self, data: String) -> Result<T, ParseError> {
+      match self.format {
+        Format::Json => Ok(serde_json::from_str(&data)?),
+        Format::Ndjson => {
+          let (index, line) = data.lines()
+            .enumerate()
+            .find(|(_, line)| !line.trim().is_empty())
+            .ok_or(ParseError::NoRecord)?;
+          serde_json::from_str(line).map_err(|error| ParseError::Ndjson { line: index + 1, error })
+        }
+        Format::Csv => {
+          let mut reader = csv::ReaderBuilder::new().trim(csv::Trim::All).from_reader(data.as_bytes());
+          let record = reader.deserialize().next().ok_or(ParseError::NoRecord)?;
+          Ok(record?)
+        }
+      }
+    }
+
+    fn parse_all<T: DeserializeOwned>(&self, data: String) -> Result<Vec<T>, ParseError> {
+      match self.format {
+        Format::Json => Ok(serde_json::from_str(&data)?),
+        Format::Ndjson => data.lines()
+          .enumerate()
+          .filter(|(_, line)| !line.trim().is_empty())
+          .map(|(index, line)| {
+            serde_json::from_str(line).map_err(|error| ParseError::Ndjson { line: index + 1, error })
+          })
+          .collect(),
+        Format::Csv => {
+          let mut reader = csv::ReaderBuilder::new().trim(csv::Trim::All).from_reader(data.as_bytes());
+          let records: Result<Vec<T>, csv::Error> = reader.deserialize().collect();
+          Ok(records?)
+        }
+      }
     }
   }
 
-  pub struct DataReader;
+  /// Reads records of type `T`.
+  pub struct DataReader<T = Person> {
+    codecs: CodecChain,
+    format: Format,
+    fetcher: Fetcher,
+    target: PhantomData<fn() -> T>,
+  }
 
-  impl DataReader {
-    pub fn read_person(&self, url: String) -> Option<Person> {
-      let data = DataDownloader.download(url);
-      let json = DataDecoder.decode(data);
-      let person = DataDeserializer.parse(json);
-      person
+  impl<T: DeserializeOwned> Default for DataReader<T> {
+    fn default() -> DataReader<T> {
+      DataReader::new()
+    }
+  }
+
+  impl<T: DeserializeOwned> DataReader<T> {
+    /// A reader of base64 JSON payloads.
+    pub fn new() -> DataReader<T> {
+      DataReader::with_codecs(CodecChain::new().then(Base64))
+    }
+
+    /// A reader of JSON payloads decoded by `codecs`.
+    pub fn with_codecs(codecs: CodecChain) -> DataReader<T> {
+      DataReader { codecs, format: Format::Json, fetcher: Fetcher::new(), target: PhantomData }
+    }
+
+    pub fn format(mut self, format: Format) -> DataReader<T> {
+      self.format = format;
+      self
+    }
+
+    /// Downloads through `fetcher`, with its cache and retry policies.
+    pub fn fetcher(mut self, fetcher: Fetcher) -> DataReader<T> {
+      self.fetcher = fetcher;
+      self
+    }
+
+    pub fn downloads(&self) -> &Fetcher {
+      &self.fetcher
+    }
+
+    /// Reads one record; for NDJSON and CSV, the first one.
+    pub fn read(&self, url: String) -> Result<T, ReadError> {
+      let json = self.download_and_decode(url)?;
+      let record = DataDeserializer { format: self.format }.parse(json)?;
+      Ok(record)
+    }
+
+    pub fn read_all(&self, url: String) -> Result<Vec<T>, ReadError> {
+      let json = self.download_and_decode(url)?;
+      let records = DataDeserializer { format: self.format }.parse_all(json)?;
+      Ok(records)
+    }
+
+    fn download_and_decode(&self, url: String) -> Result<String, ReadError> {
+      let data = DataDownloader { fetcher: &self.fetcher }.download(url)?;
+      Ok(DataDecoder { codecs: &self.codecs }.decode(data)?)
+    }
+  }
+
+  impl DataReader<Person> {
+    pub fn read_person(&self, url: String) -> Result<Person, ReadError> {
+      self.read(url)
     }
   }
 }
 
 pub mod with_derive_trait {
   //! `with_derive_trait` module.
-  //! 
+  //!
+  //! The stages are derived, and configured by attributes.
+  //!
   //! # Example
-  //! 
+  //!
   //! ``` rust
-  //! use structural::facade::with_struct::*;
-  //! 
-  //! let url = String::from("google.com");
+  //! use structural::facade::*;
+  //! use structural::facade::with_derive_trait::*;
+  //!
+  //! let server = http::StandIn::start().unwrap();
+  //! server.serve("/person", base64::encode(br#"{ "name": "Ivan", "age": 26 }"#).as_bytes());
   //! let reader = DataReader;
-  //! println!("{:?}", reader.read_person(url));
+  //! assert_eq!(reader.read_person(server.url("/person")).unwrap(), Person::new("Ivan", 26));
+  //!
+  //! let path = std::env::temp_dir().join(format!("person-{}.hex", std::process::id()));
+  //! std::fs::write(&path, hex::encode(br#"{ "name": "Laurent Deleris", "age": 50 }"#)).unwrap();
+  //! let reader = HexFileReader;
+  //! let person = reader.read_person(format!("file://{}", path.display())).unwrap();
+  //! assert_eq!(person.name(), Some("Laurent Deleris"));
+  //! std::fs::remove_file(&path).unwrap();
+  //!
+  //! match reader.read_person(server.url("/person")) {
+  //!   Err(ReadError::Download(DownloadError::UnsupportedScheme(scheme))) => assert_eq!(scheme, "http"),
+  //!   other => panic!("unexpected {:?}", other),
+  //! }
   //! ```
 
-  use facade::Person;
+  use facade::{DecodeError, DownloadError, ParseError, Person, ReadError};
 
   trait DataDownloader {
-      fn download(&self, url: String) -> String;
+      fn download(&self, url: String) -> Result<Vec<u8>, DownloadError>;
   }
 
   trait DataDecoder {
-      fn decode(&self, data: String) -> String;
+      fn decode(&self, data: Vec<u8>) -> Result<String, DecodeError>;
   }
 
-  trait DataDeserializer {
-    fn parse(&self, data: String) -> Option<Person>;
+  trait DataDeserializer<T> {
+    fn parse(&self, data: String) -> Result<T, ParseError>;
   }
 
+  /// Reads base64 JSON from any supported URL.
   #[derive(DataDownloader, DataDecoder, DataDeserializer)]
+  #[decoder(base64)]
+  #[deserializer(json, target = "Person")]
   pub struct DataReader;
 
   impl DataReader {
-    pub fn read_person(&self, url: String) -> Option<Person> {
-      let data = self.download(url);
-      let json = self.decode(data);
-      let person = self.parse(json);
-      person
+    pub fn read_person(&self, url: String) -> Result<Person, ReadError> {
+      let data = self.download(url)?;
+      let json = self.decode(data)?;
+      let person = self.parse(json)?;
+      Ok(person)
     }
   }
-}
 
+  /// Reads hexadecimal JSON from local files only.
+  #[derive(DataDownloader, DataDecoder, DataDeserializer)]
+  #[downloader(scheme = "file")]
+  #[decoder(hex)]
+  #[deserializer(json, target = "Person")]
+  pub struct HexFileReader;
+
+  impl HexFileReader {
+    pub fn read_person(&self, url: String) -> Result<Person, ReadError> {
+      let data = self.download(url)?;
+      let json = self.decode(data)?;
+      let person = self.parse(json)?;
+      Ok(person)
+    }
+  }
+}
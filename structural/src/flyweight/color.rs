@@ -0,0 +1,459 @@
+//! `color` module.
+//!
+//! An RGBA color. It is read from `#rgb`, `#rgba`, `#rrggbb` or
+//! `#rrggbbaa` hex codes, from the CSS color names, or from the `rgb(...)`,
+//! `rgba(...)` and `hsl(...)` notations, so it can be written in a config
+//! file; it serializes to its name or hex code. Colors with the same
+//! components are equal, whatever the way they were written, so a
+//! flyweight factory keyed by color shares one object per value.
+//!
+//! # Example
+//!
+//! ```rust
+//! use structural::flyweight::Color;
+//!
+//! let pink: Color = "#ff00aa".parse().unwrap();
+//! assert_eq!((pink.r, pink.g, pink.b, pink.a), (255, 0, 170, 255));
+//! assert_eq!("fuchsia".parse::<Color>().unwrap(), Color::Magenta);
+//! assert_eq!("rgb(0, 128, 0)".parse::<Color>().unwrap(), Color::Green);
+//! assert_eq!("hsl(240, 100%, 50%)".parse::<Color>().unwrap(), Color::Blue);
+//! assert_eq!("#f00".parse::<Color>().unwrap(), Color::Red);
+//! assert_eq!("RebeccaPurple".parse::<Color>().unwrap().to_string(), "rebeccapurple");
+//! assert!("#12345".parse::<Color>().is_err());
+//! assert!("blurple".parse::<Color>().is_err());
+//!
+//! let veil = Color::from_rgba(0, 0, 255, 128);
+//! assert_eq!(veil.to_hex(), "#0000ff80");
+//! assert_eq!(veil.over(Color::White), Color::from_rgb(127, 127, 255));
+//! assert_eq!(Color::Red.mix(Color::Blue, 0.5), Color::from_rgb(128, 0, 128));
+//!
+//! let (h, s, l) = Color::Yellow.to_hsl();
+//! assert_eq!((h.round(), s, l), (60.0, 1.0, 0.5));
+//! assert_eq!(Color::from_hsl(h, s, l), Color::Yellow);
+//! ```
+//!
+//! In a config file:
+//!
+//! ```rust
+//! extern crate serde_json;
+//! extern crate structural;
+//!
+//! use std::collections::HashMap;
+//! use structural::flyweight::*;
+//!
+//! # fn main() {
+//! let config = r##"{ "background": "#fafafa", "ink": "rgba(0, 0, 0, 0.5)", "highlight": "red" }"##;
+//! let palette: HashMap<String, Color> = serde_json::from_str(config).unwrap();
+//! assert_eq!(palette["ink"].a, 128);
+//!
+//! let mut circles = CircleFactory::new();
+//! let a = circles.make_circle(palette["highlight"]);
+//! let b = circles.make_circle("#ff0000".parse().unwrap());
+//! assert!(std::rc::Rc::ptr_eq(&a, &b));
+//! assert_eq!(circles.circles_created(), 1);
+//!
+//! assert_eq!(serde_json::to_string(&palette["background"]).unwrap(), r##""#fafafa""##);
+//! # }
+//! ```
+
+use std::error::Error;
+use std::fmt;
+use std::str::FromStr;
+
+use serde::de::{self, Deserialize, Deserializer};
+use serde::ser::{Serialize, Serializer};
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Color {
+  pub r: u8,
+  pub g: u8,
+  pub b: u8,
+  /// Opacity, from 0 (transparent) to 255 (opaque).
+  pub a: u8,
+}
+
+// The five colors of the original example keep their names.
+#[allow(non_upper_case_globals)]
+impl Color {
+  pub const Red: Color = Color::from_rgb(255, 0, 0);
+  pub const Green: Color = Color::from_rgb(0, 128, 0);
+  pub const Blue: Color = Color::from_rgb(0, 0, 255);
+  pub const Yellow: Color = Color::from_rgb(255, 255, 0);
+  pub const Magenta: Color = Color::from_rgb(255, 0, 255);
+  pub const Black: Color = Color::from_rgb(0, 0, 0);
+  pub const White: Color = Color::from_rgb(255, 255, 255);
+  pub const Transparent: Color = Color::from_rgba(0, 0, 0, 0);
+}
+
+impl Color {
+  pub const fn from_rgb(r: u8, g: u8, b: u8) -> Color {
+    Color { r, g, b, a: 255 }
+  }
+
+  pub const fn from_rgba(r: u8, g: u8, b: u8, a: u8) -> Color {
+    Color { r, g, b, a }
+  }
+
+  /// From a hue in degrees, a saturation and a lightness from 0 to 1.
+  pub fn from_hsl(hue: f32, saturation: f32, lightness: f32) -> Color {
+    let (s, l) = (saturation.clamp(0.0, 1.0), lightness.clamp(0.0, 1.0));
+    let chroma = (1.0 - (2.0 * l - 1.0).abs()) * s;
+    let h = hue.rem_euclid(360.0) / 60.0;
+    let x = chroma * (1.0 - (h % 2.0 - 1.0).abs());
+    let (r, g, b) = match h as u32 {
+      0 => (chroma, x, 0.0),
+      1 => (x, chroma, 0.0),
+      2 => (0.0, chroma, x),
+      3 => (0.0, x, chroma),
+      4 => (x, 0.0, chroma),
+      _ => (chroma, 0.0, x),
+    };
+    let m = l - chroma / 2.0;
+    Color::from_rgb(channel(r + m), channel(g + m), channel(b + m))
+  }
+
+  /// Hue in degrees, saturation and lightness from 0 to 1.
+  pub fn to_hsl(&self) -> (f32, f32, f32) {
+    let (r, g, b) = (self.r as f32 / 255.0, self.g as f32 / 255.0, self.b as f32 / 255.0);
+    let (max, min) = (r.max(g).max(b), r.min(g).min(b));
+    let l = (max + min) / 2.0;
+    let chroma = max - min;
+    if chroma == 0.0 {
+      return (0.0, 0.0, l);
+    }
+    let s = chroma / (1.0 - (2.0 * l - 1.0).abs());
+    let h = if max == r {
+      ((g - b) / chroma).rem_euclid(6.0)
+    } else if max == g {
+      (b - r) / chroma + 2.0
+    } else {
+      (r - g) / chroma + 4.0
+    };
+    (h * 60.0, s, l)
+  }
+
+  pub fn rgb(&self) -> (u8, u8, u8) {
+    (self.r, self.g, self.b)
+  }
+
+  /// The opacity from 0 to 1.
+  pub fn alpha(&self) -> f32 {
+    self.a as f32 / 255.0
+  }
+
+  /// `#rrggbb`, or `#rrggbbaa` when not opaque.
+  pub fn to_hex(&self) -> String {
+    if self.a == 255 {
+      format!("#{:02x}{:02x}{:02x}", self.r, self.g, self.b)
+    } else {
+      format!("#{:02x}{:02x}{:02x}{:02x}", self.r, self.g, self.b, self.a)
+    }
+  }
+
+  /// The CSS name of the color, if it has one.
+  pub fn name(&self) -> Option<&'static str> {
+    if *self == Color::Transparent {
+      return Some("transparent");
+    }
+    NAMES.iter().find(|&&(_, rgb)| self.a == 255 && rgb == self.packed()).map(|&(name, _)| name)
+  }
+
+  /// Interpolates every component, from `self` at 0 to `other` at 1.
+  pub fn mix(self, other: Color, t: f32) -> Color {
+    let t = t.clamp(0.0, 1.0);
+    let lerp = |from: u8, to: u8| (from as f32 + (to as f32 - from as f32) * t).round() as u8;
+    Color::from_rgba(lerp(self.r, other.r), lerp(self.g, other.g), lerp(self.b, other.b), lerp(self.a, other.a))
+  }
+
+  /// Paints `self` over `background`, as an opaque result when the
+  /// background is opaque.
+  pub fn over(self, background: Color) -> Color {
+    let (alpha, below) = (self.alpha(), background.alpha() * (1.0 - self.alpha()));
+    let out = alpha + below;
+    if out == 0.0 {
+      return Color::Transparent;
+    }
+    let mix = |top: u8, bottom: u8| ((top as f32 * alpha + bottom as f32 * below) / out).round() as u8;
+    Color::from_rgba(mix(self.r, background.r), mix(self.g, background.g), mix(self.b, background.b), channel(out))
+  }
+
+  fn packed(&self) -> u32 {
+    (self.r as u32) << 16 | (self.g as u32) << 8 | self.b as u32
+  }
+}
+
+fn channel(value: f32) -> u8 {
+  (value.clamp(0.0, 1.0) * 255.0).round() as u8
+}
+
+impl fmt::Display for Color {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match self.name() {
+      Some(name) => write!(f, "{}", name),
+      None => write!(f, "{}", self.to_hex()),
+    }
+  }
+}
+
+impl fmt::Debug for Color {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    write!(f, "Color({})", self)
+  }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ColorError {
+  /// A `#` code without 3, 4, 6 or 8 hex digits.
+  InvalidHex(String),
+  UnknownName(String),
+  /// An `rgb(...)`, `rgba(...)` or `hsl(...)` with wrong arguments.
+  InvalidFunction(String),
+}
+
+impl fmt::Display for ColorError {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match *self {
+      ColorError::InvalidHex(ref text) => write!(f, "invalid hex color `{}`", text),
+      ColorError::UnknownName(ref text) => write!(f, "unknown color name `{}`", text),
+      ColorError::InvalidFunction(ref text) => write!(f, "invalid color function `{}`", text),
+    }
+  }
+}
+
+impl Error for ColorError {}
+
+impl FromStr for Color {
+  type Err = ColorError;
+
+  fn from_str(text: &str) -> Result<Color, ColorError> {
+    let trimmed = text.trim();
+    let lower = trimmed.to_ascii_lowercase();
+    if let Some(digits) = lower.strip_prefix('#') {
+      return parse_hex(digits).ok_or_else(|| ColorError::InvalidHex(String::from(trimmed)));
+    }
+    if let Some(open) = lower.find('(') {
+      return parse_function(&lower[..open], &lower[open + 1..])
+        .ok_or_else(|| ColorError::InvalidFunction(String::from(trimmed)));
+    }
+    if lower == "transparent" {
+      return Ok(Color::Transparent);
+    }
+    NAMES.iter()
+      .chain(ALIASES)
+      .find(|&&(name, _)| name == lower)
+      .map(|&(_, rgb)| Color::from_rgb((rgb >> 16) as u8, (rgb >> 8) as u8, rgb as u8))
+      .ok_or_else(|| ColorError::UnknownName(String::from(trimmed)))
+  }
+}
+
+fn parse_hex(digits: &str) -> Option<Color> {
+  if !digits.bytes().all(|b| b.is_ascii_hexdigit()) {
+    return None;
+  }
+  let nibble = |i: usize| u8::from_str_radix(&digits[i..=i], 16).ok().map(|n| n * 17);
+  let byte = |i: usize| u8::from_str_radix(&digits[i..i + 2], 16).ok();
+  match digits.len() {
+    3 => Some(Color::from_rgb(nibble(0)?, nibble(1)?, nibble(2)?)),
+    4 => Some(Color::from_rgba(nibble(0)?, nibble(1)?, nibble(2)?, nibble(3)?)),
+    6 => Some(Color::from_rgb(byte(0)?, byte(2)?, byte(4)?)),
+    8 => Some(Color::from_rgba(byte(0)?, byte(2)?, byte(4)?, byte(6)?)),
+    _ => None,
+  }
+}
+
+/// `name(arguments)`: `rgb(r, g, b)` and `rgba(r, g, b, a)` with channels
+/// from 0 to 255 and an opacity from 0 to 1; `hsl(h, s%, l%)` and
+/// `hsla(h, s%, l%, a)`.
+fn parse_function(name: &str, rest: &str) -> Option<Color> {
+  let arguments: Vec<&str> = rest.strip_suffix(')')?.split(',').map(str::trim).collect();
+  let number = |text: &str| text.parse::<f32>().ok().filter(|n| n.is_finite());
+  let percent = |text: &str| number(text.strip_suffix('%')?).map(|n| n / 100.0);
+  let alpha = |arguments: &[&str]| match arguments.get(3) {
+    Some(text) => number(text).map(channel),
+    None => Some(255),
+  };
+  let expected = if name.ends_with('a') { 4 } else { 3 };
+  if arguments.len() != expected {
+    return None;
+  }
+  match name {
+    "rgb" | "rgba" => {
+      let byte = |text: &str| number(text).filter(|n| (0.0..=255.0).contains(n)).map(|n| n.round() as u8);
+      Some(Color::from_rgba(byte(arguments[0])?, byte(arguments[1])?, byte(arguments[2])?, alpha(&arguments)?))
+    }
+    "hsl" | "hsla" => {
+      let color = Color::from_hsl(number(arguments[0])?, percent(arguments[1])?, percent(arguments[2])?);
+      Some(Color { a: alpha(&arguments)?, ..color })
+    }
+    _ => None,
+  }
+}
+
+impl Serialize for Color {
+  fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(&self.to_string())
+  }
+}
+
+impl<'de> Deserialize<'de> for Color {
+  fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Color, D::Error> {
+    let text = String::deserialize(deserializer)?;
+    text.parse().map_err(de::Error::custom)
+  }
+}
+
+/// Other spellings of named colors.
+const ALIASES: &[(&str, u32)] = &[
+  ("aqua", 0x00ffff),
+  ("fuchsia", 0xff00ff),
+  ("darkgrey", 0xa9a9a9),
+  ("darkslategrey", 0x2f4f4f),
+  ("dimgrey", 0x696969),
+  ("grey", 0x808080),
+  ("lightgrey", 0xd3d3d3),
+  ("lightslategrey", 0x778899),
+  ("slategrey", 0x708090),
+];
+
+/// The CSS named colors.
+const NAMES: &[(&str, u32)] = &[
+  ("aliceblue", 0xf0f8ff),
+  ("antiquewhite", 0xfaebd7),
+  ("aquamarine", 0x7fffd4),
+  ("azure", 0xf0ffff),
+  ("beige", 0xf5f5dc),
+  ("bisque", 0xffe4c4),
+  ("black", 0x000000),
+  ("blanchedalmond", 0xffebcd),
+  ("blue", 0x0000ff),
+  ("blueviolet", 0x8a2be2),
+  ("brown", 0xa52a2a),
+  ("burlywood", 0xdeb887),
+  ("cadetblue", 0x5f9ea0),
+  ("chartreuse", 0x7fff00),
+  ("chocolate", 0xd2691e),
+  ("coral", 0xff7f50),
+  ("cornflowerblue", 0x6495ed),
+  ("cornsilk", 0xfff8dc),
+  ("crimson", 0xdc143c),
+  ("cyan", 0x00ffff),
+  ("darkblue", 0x00008b),
+  ("darkcyan", 0x008b8b),
+  ("darkgoldenrod", 0xb8860b),
+  ("darkgray", 0xa9a9a9),
+  ("darkgreen", 0x006400),
+  ("darkkhaki", 0xbdb76b),
+  ("darkmagenta", 0x8b008b),
+  ("darkolivegreen", 0x556b2f),
+  ("darkorange", 0xff8c00),
+  ("darkorchid", 0x9932cc),
+  ("darkred", 0x8b0000),
+  ("darksalmon", 0xe9967a),
+  ("darkseagreen", 0x8fbc8f),
+  ("darkslateblue", 0x483d8b),
+  ("darkslategray", 0x2f4f4f),
+  ("darkturquoise", 0x00ced1),
+  ("darkviolet", 0x9400d3),
+  ("deeppink", 0xff1493),
+  ("deepskyblue", 0x00bfff),
+  ("dimgray", 0x696969),
+  ("dodgerblue", 0x1e90ff),
+  ("firebrick", 0xb22222),
+  ("floralwhite", 0xfffaf0),
+  ("forestgreen", 0x228b22),
+  ("gainsboro", 0xdcdcdc),
+  ("ghostwhite", 0xf8f8ff),
+  ("gold", 0xffd700),
+  ("goldenrod", 0xdaa520),
+  ("gray", 0x808080),
+  ("green", 0x008000),
+  ("greenyellow", 0xadff2f),
+  ("honeydew", 0xf0fff0),
+  ("hotpink", 0xff69b4),
+  ("indianred", 0xcd5c5c),
+  ("indigo", 0x4b0082),
+  ("ivory", 0xfffff0),
+  ("khaki", 0xf0e68c),
+  ("lavender", 0xe6e6fa),
+  ("lavenderblush", 0xfff0f5),
+  ("lawngreen", 0x7cfc00),
+  ("lemonchiffon", 0xfffacd),
+  ("lightblue", 0xadd8e6),
+  ("lightcoral", 0xf08080),
+  ("lightcyan", 0xe0ffff),
+  ("lightgoldenrodyellow", 0xfafad2),
+  ("lightgray", 0xd3d3d3),
+  ("lightgreen", 0x90ee90),
+  ("lightpink", 0xffb6c1),
+  ("lightsalmon", 0xffa07a),
+  ("lightseagreen", 0x20b2aa),
+  ("lightskyblue", 0x87cefa),
+  ("lightslategray", 0x778899),
+  ("lightsteelblue", 0xb0c4de),
+  ("lightyellow", 0xffffe0),
+  ("lime", 0x00ff00),
+  ("limegreen", 0x32cd32),
+  ("linen", 0xfaf0e6),
+  ("magenta", 0xff00ff),
+  ("maroon", 0x800000),
+  ("mediumaquamarine", 0x66cdaa),
+  ("mediumblue", 0x0000cd),
+  ("mediumorchid", 0xba55d3),
+  ("mediumpurple", 0x9370db),
+  ("mediumseagreen", 0x3cb371),
+  ("mediumslateblue", 0x7b68ee),
+  ("mediumspringgreen", 0x00fa9a),
+  ("mediumturquoise", 0x48d1cc),
+  ("mediumvioletred", 0xc71585),
+  ("midnightblue", 0x191970),
+  ("mintcream", 0xf5fffa),
+  ("mistyrose", 0xffe4e1),
+  ("moccasin", 0xffe4b5),
+  ("navajowhite", 0xffdead),
+  ("navy", 0x000080),
+  ("oldlace", 0xfdf5e6),
+  ("olive", 0x808000),
+  ("olivedrab", 0x6b8e23),
+  ("orange", 0xffa500),
+  ("orangered", 0xff4500),
+  ("orchid", 0xda70d6),
+  ("palegoldenrod", 0xeee8aa),
+  ("palegreen", 0x98fb98),
+  ("paleturquoise", 0xafeeee),
+  ("palevioletred", 0xdb7093),
+  ("papayawhip", 0xffefd5),
+  ("peachpuff", 0xffdab9),
+  ("peru", 0xcd853f),
+  ("pink", 0xffc0cb),
+  ("plum", 0xdda0dd),
+  ("powderblue", 0xb0e0e6),
+  ("purple", 0x800080),
+  ("rebeccapurple", 0x663399),
+  ("red", 0xff0000),
+  ("rosybrown", 0xbc8f8f),
+  ("royalblue", 0x4169e1),
+  ("saddlebrown", 0x8b4513),
+  ("salmon", 0xfa8072),
+  ("sandybrown", 0xf4a460),
+  ("seagreen", 0x2e8b57),
+  ("seashell", 0xfff5ee),
+  ("sienna", 0xa0522d),
+  ("silver", 0xc0c0c0),
+  ("skyblue", 0x87ceeb),
+  ("slateblue", 0x6a5acd),
+  ("slategray", 0x708090),
+  ("snow", 0xfffafa),
+  ("springgreen", 0x00ff7f),
+  ("steelblue", 0x4682b4),
+  ("tan", 0xd2b48c),
+  ("teal", 0x008080),
+  ("thistle", 0xd8bfd8),
+  ("tomato", 0xff6347),
+  ("turquoise", 0x40e0d0),
+  ("violet", 0xee82ee),
+  ("wheat", 0xf5deb3),
+  ("white", 0xffffff),
+  ("whitesmoke", 0xf5f5f5),
+  ("yellow", 0xffff00),
+  ("yellowgreen", 0x9acd32),
+];
@@ -0,0 +1,284 @@
+//! `factory` module.
+//!
+//! A `FlyweightFactory` keeps one value per key and hands out `Rc` handles
+//! to it, so every user of a key shares the same intrinsic state. The
+//! factory knows how many handles are alive, can drop the values nobody
+//! uses any more, and reports how much memory the sharing saves.
+//! `SyncFlyweightFactory` does the same with `Arc` handles behind a lock,
+//! for use from several threads.
+//!
+//! # Example
+//!
+//! ```rust
+//! use std::rc::Rc;
+//! use structural::flyweight::factory::*;
+//!
+//! let mut fonts = FlyweightFactory::new();
+//! let a = fonts.get_or_insert_with("serif", |name| format!("{} glyphs", name));
+//! let b = fonts.get_or_insert_with("serif", |_| unreachable!());
+//! assert!(Rc::ptr_eq(&a, &b));
+//! assert_eq!(fonts.ref_count(&"serif"), Some(2));
+//!
+//! let c = fonts.get_or_insert_with("mono", |name| format!("{} glyphs", name));
+//! drop(c);
+//! assert_eq!(fonts.ref_count(&"mono"), Some(0));
+//!
+//! let stats = fonts.stats();
+//! assert_eq!((stats.entries, stats.handles, stats.created), (2, 2, 2));
+//! assert_eq!(stats.shared_bytes, 2 * std::mem::size_of::<String>());
+//! assert_eq!(stats.unshared_bytes, 2 * std::mem::size_of::<String>());
+//!
+//! assert_eq!(fonts.evict_unused(), 1);
+//! assert!(!fonts.contains(&"mono"));
+//! assert_eq!(fonts.stats().evicted, 1);
+//! ```
+//!
+//! Shared between threads:
+//!
+//! ```rust
+//! use std::sync::Arc;
+//! use std::thread;
+//! use structural::flyweight::factory::*;
+//!
+//! let factory = Arc::new(SyncFlyweightFactory::new());
+//! let workers: Vec<_> = (0..4).map(|i| {
+//!   let factory = factory.clone();
+//!   thread::spawn(move || factory.get_or_insert_with(i % 2, |key| key * 100))
+//! }).collect();
+//! let handles: Vec<Arc<i32>> = workers.into_iter().map(|w| w.join().unwrap()).collect();
+//! assert_eq!(factory.len(), 2);
+//! assert_eq!(factory.ref_count(&0), Some(2));
+//! assert_eq!(factory.stats().created, 2);
+//! drop(handles);
+//! assert_eq!(factory.evict_unused(), 2);
+//! assert!(factory.is_empty());
+//! ```
+
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::marker::PhantomData;
+use std::mem;
+use std::ops::Deref;
+use std::rc::Rc;
+use std::sync::{Arc, Mutex, MutexGuard};
+
+/// A reference-counted pointer, `Rc` or `Arc`.
+pub trait Shared<V>: Clone + Deref<Target = V> {
+  fn share(value: V) -> Self;
+  fn strong_count(this: &Self) -> usize;
+}
+
+impl<V> Shared<V> for Rc<V> {
+  fn share(value: V) -> Rc<V> {
+    Rc::new(value)
+  }
+  fn strong_count(this: &Rc<V>) -> usize {
+    Rc::strong_count(this)
+  }
+}
+
+impl<V> Shared<V> for Arc<V> {
+  fn share(value: V) -> Arc<V> {
+    Arc::new(value)
+  }
+  fn strong_count(this: &Arc<V>) -> usize {
+    Arc::strong_count(this)
+  }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct FlyweightStats {
+  /// Values held by the factory.
+  pub entries: usize,
+  /// Handles alive outside the factory.
+  pub handles: usize,
+  /// Values created since the factory was made.
+  pub created: usize,
+  /// Values dropped by eviction.
+  pub evicted: usize,
+  /// Bytes of the shared values, `entries * size_of::<V>()`.
+  pub shared_bytes: usize,
+  /// Bytes the values would take without sharing, one per handle.
+  pub unshared_bytes: usize,
+}
+
+impl FlyweightStats {
+  pub fn saved_bytes(&self) -> usize {
+    self.unshared_bytes.saturating_sub(self.shared_bytes)
+  }
+}
+
+/// The bookkeeping common to both factories.
+struct Pool<K, V, P> {
+  entries: HashMap<K, P>,
+  created: usize,
+  evicted: usize,
+  auto_evict: bool,
+  value: PhantomData<V>,
+}
+
+impl<K: Eq + Hash + Clone, V, P: Shared<V>> Pool<K, V, P> {
+  fn new() -> Pool<K, V, P> {
+    Pool { entries: HashMap::new(), created: 0, evicted: 0, auto_evict: false, value: PhantomData }
+  }
+
+  fn get_or_insert_with<F: FnOnce(&K) -> V>(&mut self, key: K, make: F) -> P {
+    if let Some(shared) = self.entries.get(&key) {
+      return shared.clone();
+    }
+    if self.auto_evict {
+      self.evict_unused();
+    }
+    let shared = P::share(make(&key));
+    self.created += 1;
+    self.entries.insert(key, shared.clone());
+    shared
+  }
+
+  fn ref_count(&self, key: &K) -> Option<usize> {
+    self.entries.get(key).map(|shared| P::strong_count(shared) - 1)
+  }
+
+  fn evict_unused(&mut self) -> usize {
+    let before = self.entries.len();
+    self.entries.retain(|_, shared| P::strong_count(shared) > 1);
+    let evicted = before - self.entries.len();
+    self.evicted += evicted;
+    evicted
+  }
+
+  fn stats(&self) -> FlyweightStats {
+    let handles = self.entries.values().map(|shared| P::strong_count(shared) - 1).sum();
+    FlyweightStats {
+      entries: self.entries.len(),
+      handles,
+      created: self.created,
+      evicted: self.evicted,
+      shared_bytes: self.entries.len() * mem::size_of::<V>(),
+      unshared_bytes: handles * mem::size_of::<V>(),
+    }
+  }
+}
+
+/// Shares one `V` per key through `Rc` handles.
+pub struct FlyweightFactory<K, V> {
+  pool: Pool<K, V, Rc<V>>,
+}
+
+impl<K: Eq + Hash + Clone, V> Default for FlyweightFactory<K, V> {
+  fn default() -> FlyweightFactory<K, V> {
+    FlyweightFactory::new()
+  }
+}
+
+impl<K: Eq + Hash + Clone, V> FlyweightFactory<K, V> {
+  pub fn new() -> FlyweightFactory<K, V> {
+    FlyweightFactory { pool: Pool::new() }
+  }
+
+  /// Evicts the unused values before creating a new one.
+  pub fn auto_evict(mut self, enabled: bool) -> FlyweightFactory<K, V> {
+    self.pool.auto_evict = enabled;
+    self
+  }
+
+  /// The value of `key`, made by `make` the first time.
+  pub fn get_or_insert_with<F: FnOnce(&K) -> V>(&mut self, key: K, make: F) -> Rc<V> {
+    self.pool.get_or_insert_with(key, make)
+  }
+
+  pub fn get(&self, key: &K) -> Option<Rc<V>> {
+    self.pool.entries.get(key).cloned()
+  }
+
+  pub fn contains(&self, key: &K) -> bool {
+    self.pool.entries.contains_key(key)
+  }
+
+  pub fn len(&self) -> usize {
+    self.pool.entries.len()
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.pool.entries.is_empty()
+  }
+
+  /// Handles alive outside the factory for `key`.
+  pub fn ref_count(&self, key: &K) -> Option<usize> {
+    self.pool.ref_count(key)
+  }
+
+  /// Drops the values without handles; returns how many were dropped.
+  pub fn evict_unused(&mut self) -> usize {
+    self.pool.evict_unused()
+  }
+
+  pub fn stats(&self) -> FlyweightStats {
+    self.pool.stats()
+  }
+}
+
+/// Shares one `V` per key through `Arc` handles; usable from several
+/// threads at once.
+pub struct SyncFlyweightFactory<K, V> {
+  pool: Mutex<Pool<K, V, Arc<V>>>,
+}
+
+impl<K: Eq + Hash + Clone, V> Default for SyncFlyweightFactory<K, V> {
+  fn default() -> SyncFlyweightFactory<K, V> {
+    SyncFlyweightFactory::new()
+  }
+}
+
+impl<K: Eq + Hash + Clone, V> SyncFlyweightFactory<K, V> {
+  pub fn new() -> SyncFlyweightFactory<K, V> {
+    SyncFlyweightFactory { pool: Mutex::new(Pool::new()) }
+  }
+
+  /// Evicts the unused values before creating a new one.
+  pub fn auto_evict(self, enabled: bool) -> SyncFlyweightFactory<K, V> {
+    self.lock().auto_evict = enabled;
+    self
+  }
+
+  /// The value of `key`, made by `make` the first time. `make` runs with
+  /// the factory locked.
+  pub fn get_or_insert_with<F: FnOnce(&K) -> V>(&self, key: K, make: F) -> Arc<V> {
+    self.lock().get_or_insert_with(key, make)
+  }
+
+  pub fn get(&self, key: &K) -> Option<Arc<V>> {
+    self.lock().entries.get(key).cloned()
+  }
+
+  pub fn contains(&self, key: &K) -> bool {
+    self.lock().entries.contains_key(key)
+  }
+
+  pub fn len(&self) -> usize {
+    self.lock().entries.len()
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.lock().entries.is_empty()
+  }
+
+  /// Handles alive outside the factory for `key`.
+  pub fn ref_count(&self, key: &K) -> Option<usize> {
+    self.lock().ref_count(key)
+  }
+
+  /// Drops the values without handles; returns how many were dropped.
+  pub fn evict_unused(&self) -> usize {
+    self.lock().evict_unused()
+  }
+
+  pub fn stats(&self) -> FlyweightStats {
+    self.lock().stats()
+  }
+
+  fn lock(&self) -> MutexGuard<'_, Pool<K, V, Arc<V>>> {
+    // A panic in `make` leaves the pool consistent: nothing was inserted.
+    self.pool.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+  }
+}
@@ -21,18 +21,58 @@
 //! graphic.draw();
 //! 
 //! println!("Total number of circle objects created: {}", circles.circles_created());
+//! assert_eq!(circles.circles_created(), 5);
+//! assert_eq!(circles.stats().handles, 10);
+//! match (&graphic.items[2].shape, &graphic.items[6].shape) {
+//!   (Shape::Circle { circle: a, .. }, Shape::Circle { circle: b, .. }) => assert!(std::rc::Rc::ptr_eq(a, b)),
+//!   _ => unreachable!(),
+//! }
+//! ```
+//!
+//! Circles no longer drawn can be dropped from the factory:
+//!
+//! ``` rust
+//! use structural::flyweight::*;
+//!
+//! let mut circles = CircleFactory::new();
+//! let red = circles.make_circle(Color::Red);
+//! let blue = circles.make_circle(Color::Blue);
+//! drop(blue);
+//! assert_eq!(circles.evict_unused(), 1);
+//! assert_eq!(circles.circles_created(), 1);
+//! assert_eq!(circles.stats().evicted, 1);
+//! assert_eq!(red.color, Color::Red);
+//! ```
+//!
+//! Other shapes share a `Style`, and are drawn in z-order, lowest first:
+//!
+//! ``` rust
+//! use structural::flyweight::*;
+//!
+//! let mut graphic = Graphic::new();
+//! let mut styles = StyleFactory::new();
+//! let outline = styles.make_style(Style::new().stroke(Color::Blue, 0.1));
+//! let shade = styles.make_style(Style::new().fill(Color::Yellow).opacity(0.5));
+//! graphic.add(2, Shape::Rectangle { x: 0.0, y: 0.0, width: 2.0, height: 1.0, style: outline.clone() });
+//! graphic.add(1, Shape::Ellipse { cx: 1.0, cy: 1.0, rx: 1.0, ry: 0.5, style: shade });
+//! graphic.add(3, Shape::Line { x1: 0.0, y1: 0.0, x2: 2.0, y2: 1.0, style: outline });
+//! assert_eq!(styles.styles_created(), 2);
+//!
+//! let kinds: Vec<&str> = graphic.in_z_order().map(|item| item.shape.kind()).collect();
+//! assert_eq!(kinds, vec!["ellipse", "rectangle", "line"]);
 //! ```
- 
-use std::collections::HashMap;
 
-#[derive(PartialEq, Eq, Hash, Debug, Clone)]
-pub enum Color {
-  Red,
-  Green,
-  Blue,
-  Yellow,
-  Magenta,
-}
+pub mod color;
+pub mod factory;
+pub mod render;
+pub mod spatial;
+
+use std::hash::{Hash, Hasher};
+use std::rc::Rc;
+
+pub use flyweight::color::Color;
+use flyweight::factory::{FlyweightFactory, FlyweightStats};
+use flyweight::spatial::GridIndex;
 
 #[derive(Debug, Clone)]
 pub struct Circle {
@@ -40,46 +80,214 @@ pub struct Circle {
 }
 impl Circle {
   fn new(color: Color) -> Circle {
-    println!("Creating a circle with {:?} color.", color);
+    println!("Creating a circle with {} color.", color);
     Circle { color }
   }
+
+  /// A circle is filled with its color.
+  pub fn style(&self) -> Style {
+    Style::new().fill(self.color)
+  }
 }
 
 pub struct CircleFactory {
-  cache: HashMap<Color, Circle>,
+  cache: FlyweightFactory<Color, Circle>,
 }
 impl CircleFactory {
   pub fn new() -> CircleFactory {
     CircleFactory {
-      cache: HashMap::new(),
+      cache: FlyweightFactory::new(),
     }
   }
-  pub fn make_circle(&mut self, color: Color) -> Circle {
-    let circle = self.cache.entry(color.clone()).or_insert_with(|| Circle::new(color));
-    circle.clone()
+  pub fn make_circle(&mut self, color: Color) -> Rc<Circle> {
+    self.cache.get_or_insert_with(color, |color| Circle::new(*color))
   }
 
   pub fn circles_created(&self) -> usize {
     self.cache.len()
   }
+
+  /// Drops the circles no graphic uses any more.
+  pub fn evict_unused(&mut self) -> usize {
+    self.cache.evict_unused()
+  }
+
+  pub fn stats(&self) -> FlyweightStats {
+    self.cache.stats()
+  }
+}
+
+/// How a shape is painted: the intrinsic state shared between shapes.
+/// Widths are in scene units; the opacity goes from 0 to 1.
+#[derive(Debug, Clone)]
+pub struct Style {
+  pub fill: Option<Color>,
+  pub stroke: Option<Color>,
+  pub stroke_width: f32,
+  pub opacity: f32,
+}
+
+impl Style {
+  /// Neither filled nor stroked, fully opaque.
+  pub fn new() -> Style {
+    Style { fill: None, stroke: None, stroke_width: 0.0, opacity: 1.0 }
+  }
+
+  pub fn fill(mut self, color: Color) -> Style {
+    self.fill = Some(color);
+    self
+  }
+
+  pub fn stroke(mut self, color: Color, width: f32) -> Style {
+    self.stroke = Some(color);
+    self.stroke_width = width;
+    self
+  }
+
+  pub fn opacity(mut self, opacity: f32) -> Style {
+    self.opacity = opacity.clamp(0.0, 1.0);
+    self
+  }
+}
+
+impl Default for Style {
+  fn default() -> Style {
+    Style::new()
+  }
+}
+
+// Compared bit for bit, so that a style can key the flyweight factory.
+impl PartialEq for Style {
+  fn eq(&self, other: &Style) -> bool {
+    self.fill == other.fill
+      && self.stroke == other.stroke
+      && self.stroke_width.to_bits() == other.stroke_width.to_bits()
+      && self.opacity.to_bits() == other.opacity.to_bits()
+  }
+}
+
+impl Eq for Style {}
+
+impl Hash for Style {
+  fn hash<H: Hasher>(&self, state: &mut H) {
+    self.fill.hash(state);
+    self.stroke.hash(state);
+    self.stroke_width.to_bits().hash(state);
+    self.opacity.to_bits().hash(state);
+  }
+}
+
+#[derive(Default)]
+pub struct StyleFactory {
+  cache: FlyweightFactory<Style, Style>,
+}
+impl StyleFactory {
+  pub fn new() -> StyleFactory {
+    StyleFactory::default()
+  }
+
+  pub fn make_style(&mut self, style: Style) -> Rc<Style> {
+    self.cache.get_or_insert_with(style, Style::clone)
+  }
+
+  pub fn styles_created(&self) -> usize {
+    self.cache.len()
+  }
+
+  pub fn stats(&self) -> FlyweightStats {
+    self.cache.stats()
+  }
+}
+
+/// The extrinsic state of a shape, its geometry, with its shared intrinsic
+/// state.
+#[derive(Debug, Clone)]
+pub enum Shape {
+  Circle { x: f32, y: f32, radius: f32, circle: Rc<Circle> },
+  Rectangle { x: f32, y: f32, width: f32, height: f32, style: Rc<Style> },
+  Ellipse { cx: f32, cy: f32, rx: f32, ry: f32, style: Rc<Style> },
+  Line { x1: f32, y1: f32, x2: f32, y2: f32, style: Rc<Style> },
+  /// A closed polygon, filled with the even-odd rule.
+  Polygon { points: Vec<(f32, f32)>, style: Rc<Style> },
+}
+
+impl Shape {
+  pub fn kind(&self) -> &'static str {
+    match *self {
+      Shape::Circle { .. } => "circle",
+      Shape::Rectangle { .. } => "rectangle",
+      Shape::Ellipse { .. } => "ellipse",
+      Shape::Line { .. } => "line",
+      Shape::Polygon { .. } => "polygon",
+    }
+  }
+
+  pub fn style(&self) -> Style {
+    match *self {
+      Shape::Circle { ref circle, .. } => circle.style(),
+      Shape::Rectangle { ref style, .. }
+      | Shape::Ellipse { ref style, .. }
+      | Shape::Line { ref style, .. }
+      | Shape::Polygon { ref style, .. } => (**style).clone(),
+    }
+  }
+}
+
+/// Identifies an item of a `Graphic`; never reused.
+pub type ItemId = usize;
+
+#[derive(Debug, Clone)]
+pub struct Item {
+  pub id: ItemId,
+  /// Items with a higher z are drawn over the others.
+  pub z: i32,
+  pub shape: Shape,
 }
 
 pub struct Graphic {
-  pub items: Vec<(i32, i32, f32, Circle)>,
+  pub items: Vec<Item>,
+  next_id: ItemId,
 }
 impl Graphic {
   pub fn new() -> Graphic {
     Graphic {
       items: Vec::new(),
+      next_id: 0,
     }
   }
-  pub fn add_circle(&mut self, x: i32, y: i32, radius: f32, circle: Circle) {
-    self.items.push((x, y, radius, circle));
+  pub fn add_circle(&mut self, x: i32, y: i32, radius: f32, circle: Rc<Circle>) -> ItemId {
+    self.add(0, Shape::Circle { x: x as f32, y: y as f32, radius, circle })
+  }
+  pub fn add(&mut self, z: i32, shape: Shape) -> ItemId {
+    let id = self.next_id;
+    self.next_id += 1;
+    self.items.push(Item { id, z, shape });
+    id
+  }
+  pub fn remove(&mut self, id: ItemId) -> Option<Item> {
+    let position = self.items.iter().position(|item| item.id == id)?;
+    Some(self.items.remove(position))
+  }
+  /// A grid of the circles, to hit-test them. Panics if `cell_size` is
+  /// not a positive finite number, see `GridIndex::new`.
+  pub fn circle_index(&self, cell_size: f32) -> GridIndex {
+    let mut index = GridIndex::new(cell_size);
+    for item in &self.items {
+      if let Shape::Circle { x, y, radius, .. } = item.shape {
+        index.insert(item.id, x, y, radius);
+      }
+    }
+    index
+  }
+  /// The items from the lowest z; equal ones in the order they were added.
+  pub fn in_z_order(&self) -> impl Iterator<Item = &Item> {
+    let mut items: Vec<&Item> = self.items.iter().collect();
+    items.sort_by_key(|item| item.z);
+    items.into_iter()
   }
   pub fn draw(&self) {
-    for i in self.items.iter() {
-      println!("Drawing a circle : {:?}", i);
+    for i in self.in_z_order() {
+      println!("Drawing a {} : {:?}", i.shape.kind(), i);
     }
   }
 }
-
@@ -0,0 +1,351 @@
+//! `render` module.
+//!
+//! Draws a `Graphic` on a `Canvas`, as SVG text or as an `Image` saved in
+//! the PPM or PNG format. Scene units are multiplied by the canvas scale to
+//! get pixels; items are painted in z-order, fill then stroke, blended with
+//! their opacity.
+//!
+//! # Example
+//!
+//! ```rust
+//! use structural::flyweight::*;
+//! use structural::flyweight::render::*;
+//!
+//! let mut graphic = Graphic::new();
+//! let mut circles = CircleFactory::new();
+//! let mut styles = StyleFactory::new();
+//! graphic.add_circle(1, 1, 1.0, circles.make_circle(Color::Green));
+//! graphic.add_circle(3, 1, 1.0, circles.make_circle(Color::Red));
+//! let veil = styles.make_style(Style::new().fill(Color::Blue).opacity(0.5));
+//! graphic.add(1, Shape::Rectangle { x: 2.0, y: 0.0, width: 2.0, height: 2.0, style: veil });
+//!
+//! let canvas = Canvas::new(40, 20).scale(10.0);
+//! let svg = canvas.svg(&graphic);
+//! assert!(svg.starts_with("<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"40\" height=\"20\" viewBox=\"0 0 4 2\">"));
+//! assert!(svg.contains("<circle cx=\"1\" cy=\"1\" r=\"1\" fill=\"#008000\"/>"));
+//! assert!(svg.contains("<rect x=\"2\" y=\"0\" width=\"2\" height=\"2\" fill=\"#0000ff\" fill-opacity=\"0.5\"/>"));
+//!
+//! let image = canvas.rasterize(&graphic);
+//! assert_eq!(image.pixel(10, 10), [0, 128, 0]);
+//! assert_eq!(image.pixel(0, 0), [255, 255, 255]);
+//! // The red circle is under the half-transparent blue rectangle.
+//! assert_eq!(image.pixel(30, 10), [128, 0, 128]);
+//! assert_eq!(image.pixel(39, 0), [128, 128, 255]);
+//!
+//! assert!(image.to_ppm().starts_with(b"P6\n40 20\n255\n"));
+//! let png = image.to_png();
+//! assert_eq!(&png[..8], b"\x89PNG\r\n\x1a\n");
+//! assert_eq!(&png[12..24], b"IHDR\0\0\0\x28\0\0\0\x14");
+//!
+//! let path = std::env::temp_dir().join(format!("graphic-{}.png", std::process::id()));
+//! image.write_png(&path).unwrap();
+//! assert_eq!(std::fs::read(&path).unwrap(), png);
+//! std::fs::remove_file(&path).unwrap();
+//!
+//! // Both outputs leave the center of a star polygon empty.
+//! let mut star = Graphic::new();
+//! let ink = styles.make_style(Style::new().fill(Color::Black));
+//! let points = vec![(5.0, 0.0), (8.0, 9.0), (0.0, 3.0), (10.0, 3.0), (2.0, 9.0)];
+//! star.add(0, Shape::Polygon { points, style: ink });
+//! let canvas = Canvas::new(100, 100).scale(10.0);
+//! assert!(canvas.svg(&star).contains("<polygon points=\"5,0 8,9 0,3 10,3 2,9\" fill-rule=\"evenodd\" fill=\"#000000\"/>"));
+//! let image = canvas.rasterize(&star);
+//! assert_eq!(image.pixel(50, 50), [255, 255, 255]);
+//! assert_eq!(image.pixel(50, 10), [0, 0, 0]);
+//! ```
+
+use std::fs;
+use std::io;
+use std::io::Write;
+use std::ops::Range;
+use std::path::Path;
+
+use flate2::write::ZlibEncoder;
+use flate2::{Compression, Crc};
+
+use flyweight::{Color, Graphic, Shape, Style};
+
+/// Size in pixels and scale of the drawing.
+#[derive(Debug, Clone)]
+pub struct Canvas {
+  width: u32,
+  height: u32,
+  scale: f32,
+  background: Option<Color>,
+}
+
+impl Canvas {
+  /// A white canvas, one pixel per scene unit.
+  pub fn new(width: u32, height: u32) -> Canvas {
+    Canvas { width, height, scale: 1.0, background: None }
+  }
+
+  /// Pixels per scene unit.
+  pub fn scale(mut self, scale: f32) -> Canvas {
+    self.scale = scale;
+    self
+  }
+
+  /// The color behind the items; white when not set.
+  pub fn background(mut self, color: Color) -> Canvas {
+    self.background = Some(color);
+    self
+  }
+
+  pub fn svg(&self, graphic: &Graphic) -> String {
+    let mut svg = format!(
+      "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{}\" height=\"{}\" viewBox=\"0 0 {} {}\">\n",
+      self.width,
+      self.height,
+      self.width as f32 / self.scale,
+      self.height as f32 / self.scale
+    );
+    if let Some(ref background) = self.background {
+      svg += &format!("<rect width=\"100%\" height=\"100%\"{}/>\n", paint(&Style::new().fill(*background)));
+    }
+    for item in graphic.in_z_order() {
+      let paint = paint(&item.shape.style());
+      let element = match item.shape {
+        Shape::Circle { x, y, radius, .. } => format!("<circle cx=\"{}\" cy=\"{}\" r=\"{}\"", x, y, radius),
+        Shape::Rectangle { x, y, width, height, .. } => {
+          format!("<rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\"", x, y, width, height)
+        }
+        Shape::Ellipse { cx, cy, rx, ry, .. } => {
+          format!("<ellipse cx=\"{}\" cy=\"{}\" rx=\"{}\" ry=\"{}\"", cx, cy, rx, ry)
+        }
+        Shape::Line { x1, y1, x2, y2, .. } => format!("<line x1=\"{}\" y1=\"{}\" x2=\"{}\" y2=\"{}\"", x1, y1, x2, y2),
+        Shape::Polygon { ref points, .. } => {
+          let points: Vec<String> = points.iter().map(|(x, y)| format!("{},{}", x, y)).collect();
+          // The rasterizer fills with the even-odd rule, SVG defaults to nonzero.
+          format!("<polygon points=\"{}\" fill-rule=\"evenodd\"", points.join(" "))
+        }
+      };
+      svg += &format!("{}{}/>\n", element, paint);
+    }
+    svg + "</svg>\n"
+  }
+
+  pub fn rasterize(&self, graphic: &Graphic) -> Image {
+    let background = self.background.map(|color| color.over(Color::White).rgb()).unwrap_or((255, 255, 255));
+    let mut image = Image {
+      width: self.width,
+      height: self.height,
+      pixels: vec![[background.0, background.1, background.2]; self.width as usize * self.height as usize],
+    };
+    for item in graphic.in_z_order() {
+      let style = item.shape.style();
+      let half_width = style.stroke_width / 2.0;
+      let (x0, y0, x1, y1) = bounds(&item.shape, half_width);
+      let (rows, columns) = (self.pixel_range(y0, y1, self.height), self.pixel_range(x0, x1, self.width));
+      for py in rows {
+        for px in columns.clone() {
+          let x = (px as f32 + 0.5) / self.scale;
+          let y = (py as f32 + 0.5) / self.scale;
+          let (inside, distance) = coverage(&item.shape, x, y);
+          if let Some(ref fill) = style.fill {
+            if inside {
+              image.blend(px, py, fill, style.opacity);
+            }
+          }
+          if let Some(ref stroke) = style.stroke {
+            if distance <= half_width {
+              image.blend(px, py, stroke, style.opacity);
+            }
+          }
+        }
+      }
+    }
+    image
+  }
+
+  /// Pixels of a row or column, below `size`, whose center at
+  /// `(p + 0.5) / scale` lies between `low` and `high`.
+  fn pixel_range(&self, low: f32, high: f32, size: u32) -> Range<u32> {
+    // One more pixel on each side for rounding at the edges.
+    let first = ((low * self.scale - 0.5).ceil() - 1.0).max(0.0);
+    let end = ((high * self.scale - 0.5).floor() + 2.0).min(size as f32);
+    first as u32..end.max(first) as u32
+  }
+}
+
+/// The SVG paint attributes of a style; the alpha of a color goes to its
+/// opacity.
+fn paint(style: &Style) -> String {
+  let hex = |color: &Color| Color { a: 255, ..*color }.to_hex();
+  let opacity = |color: &Color| style.opacity * color.alpha();
+  let mut attributes = match style.fill {
+    Some(ref fill) => format!(" fill=\"{}\"", hex(fill)),
+    None => String::from(" fill=\"none\""),
+  };
+  if let Some(ref stroke) = style.stroke {
+    attributes += &format!(" stroke=\"{}\" stroke-width=\"{}\"", hex(stroke), style.stroke_width);
+  }
+  if let Some(ref fill) = style.fill {
+    if opacity(fill) < 1.0 {
+      attributes += &format!(" fill-opacity=\"{}\"", opacity(fill));
+    }
+  }
+  if let Some(ref stroke) = style.stroke {
+    if opacity(stroke) < 1.0 {
+      attributes += &format!(" stroke-opacity=\"{}\"", opacity(stroke));
+    }
+  }
+  attributes
+}
+
+/// The `(x0, y0, x1, y1)` box out of which `coverage` paints nothing for
+/// a stroke of `half_width`; empty, with `x0 > x1`, for a shape never
+/// painted.
+fn bounds(shape: &Shape, half_width: f32) -> (f32, f32, f32, f32) {
+  let h = half_width.max(0.0);
+  match *shape {
+    Shape::Circle { x, y, radius, .. } => {
+      let r = radius.abs() + h;
+      (x - r, y - r, x + r, y + r)
+    }
+    Shape::Rectangle { x, y, width, height, .. } => {
+      (x.min(x + width) - h, y.min(y + height) - h, x.max(x + width) + h, y.max(y + height) + h)
+    }
+    Shape::Ellipse { rx, ry, .. } if rx <= 0.0 || ry <= 0.0 => (0.0, 0.0, -1.0, -1.0),
+    Shape::Ellipse { cx, cy, rx, ry, .. } => {
+      // The distance to the outline is scaled by the smaller radius, so
+      // the stroke reaches further along the larger one.
+      let grow = 1.0 + h / rx.min(ry);
+      (cx - rx * grow, cy - ry * grow, cx + rx * grow, cy + ry * grow)
+    }
+    Shape::Line { x1, y1, x2, y2, .. } => (x1.min(x2) - h, y1.min(y2) - h, x1.max(x2) + h, y1.max(y2) + h),
+    Shape::Polygon { ref points, .. } => points.iter().fold(
+      (f32::INFINITY, f32::INFINITY, f32::NEG_INFINITY, f32::NEG_INFINITY),
+      |(x0, y0, x1, y1), &(x, y)| (x0.min(x - h), y0.min(y - h), x1.max(x + h), y1.max(y + h)),
+    ),
+  }
+}
+
+/// Whether `(x, y)` is inside the shape, and its distance to the outline.
+fn coverage(shape: &Shape, x: f32, y: f32) -> (bool, f32) {
+  match *shape {
+    Shape::Circle { x: cx, y: cy, radius, .. } => {
+      let d = (x - cx).hypot(y - cy);
+      (d <= radius, (d - radius).abs())
+    }
+    Shape::Rectangle { x: x0, y: y0, width, height, .. } => {
+      let (x1, y1) = (x0 + width, y0 + height);
+      let inside = x >= x0 && x <= x1 && y >= y0 && y <= y1;
+      let distance = if inside {
+        (x - x0).min(x1 - x).min(y - y0).min(y1 - y)
+      } else {
+        (x0 - x).max(x - x1).max(0.0).hypot((y0 - y).max(y - y1).max(0.0))
+      };
+      (inside, distance)
+    }
+    Shape::Ellipse { cx, cy, rx, ry, .. } => {
+      if rx <= 0.0 || ry <= 0.0 {
+        return (false, f32::INFINITY);
+      }
+      let n = ((x - cx) / rx).hypot((y - cy) / ry);
+      (n <= 1.0, (n - 1.0).abs() * rx.min(ry))
+    }
+    Shape::Line { x1, y1, x2, y2, .. } => (false, segment_distance((x, y), (x1, y1), (x2, y2))),
+    Shape::Polygon { ref points, .. } => {
+      let mut inside = false;
+      let mut distance = f32::INFINITY;
+      for (i, &a) in points.iter().enumerate() {
+        let b = points[(i + 1) % points.len()];
+        if (a.1 > y) != (b.1 > y) && x < a.0 + (y - a.1) * (b.0 - a.0) / (b.1 - a.1) {
+          inside = !inside;
+        }
+        distance = distance.min(segment_distance((x, y), a, b));
+      }
+      (inside, distance)
+    }
+  }
+}
+
+fn segment_distance(p: (f32, f32), a: (f32, f32), b: (f32, f32)) -> f32 {
+  let (dx, dy) = (b.0 - a.0, b.1 - a.1);
+  let length = dx * dx + dy * dy;
+  let t = if length == 0.0 { 0.0 } else { (((p.0 - a.0) * dx + (p.1 - a.1) * dy) / length).clamp(0.0, 1.0) };
+  (p.0 - a.0 - t * dx).hypot(p.1 - a.1 - t * dy)
+}
+
+/// RGB pixels, row by row from the top left corner.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Image {
+  width: u32,
+  height: u32,
+  pixels: Vec<[u8; 3]>,
+}
+
+impl Image {
+  pub fn width(&self) -> u32 {
+    self.width
+  }
+
+  pub fn height(&self) -> u32 {
+    self.height
+  }
+
+  pub fn pixel(&self, x: u32, y: u32) -> [u8; 3] {
+    self.pixels[y as usize * self.width as usize + x as usize]
+  }
+
+  fn blend(&mut self, x: u32, y: u32, color: &Color, opacity: f32) {
+    let (r, g, b) = color.rgb();
+    let opacity = opacity * color.alpha();
+    let pixel = &mut self.pixels[y as usize * self.width as usize + x as usize];
+    for (channel, value) in pixel.iter_mut().zip(&[r, g, b]) {
+      *channel = (*channel as f32 * (1.0 - opacity) + *value as f32 * opacity).round() as u8;
+    }
+  }
+
+  /// Binary PPM (`P6`).
+  pub fn to_ppm(&self) -> Vec<u8> {
+    let mut ppm = format!("P6\n{} {}\n255\n", self.width, self.height).into_bytes();
+    for pixel in &self.pixels {
+      ppm.extend_from_slice(pixel);
+    }
+    ppm
+  }
+
+  /// 8-bit RGB PNG, without filtering.
+  pub fn to_png(&self) -> Vec<u8> {
+    let mut rows = Vec::with_capacity(self.pixels.len() * 3 + self.height as usize);
+    for row in self.pixels.chunks(self.width.max(1) as usize) {
+      rows.push(0);
+      for pixel in row {
+        rows.extend_from_slice(pixel);
+      }
+    }
+    let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+    let data = encoder.write_all(&rows).and_then(|_| encoder.finish()).expect("writing to memory");
+
+    let mut header = Vec::new();
+    header.extend_from_slice(&self.width.to_be_bytes());
+    header.extend_from_slice(&self.height.to_be_bytes());
+    header.extend_from_slice(&[8, 2, 0, 0, 0]);
+
+    let mut png = b"\x89PNG\r\n\x1a\n".to_vec();
+    chunk(&mut png, b"IHDR", &header);
+    chunk(&mut png, b"IDAT", &data);
+    chunk(&mut png, b"IEND", &[]);
+    png
+  }
+
+  pub fn write_ppm<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+    fs::write(path, self.to_ppm())
+  }
+
+  pub fn write_png<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+    fs::write(path, self.to_png())
+  }
+}
+
+fn chunk(png: &mut Vec<u8>, kind: &[u8; 4], data: &[u8]) {
+  png.extend_from_slice(&(data.len() as u32).to_be_bytes());
+  png.extend_from_slice(kind);
+  png.extend_from_slice(data);
+  let mut crc = Crc::new();
+  crc.update(kind);
+  crc.update(data);
+  png.extend_from_slice(&crc.sum().to_be_bytes());
+}
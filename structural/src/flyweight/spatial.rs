@@ -0,0 +1,298 @@
+//! `spatial` module.
+//!
+//! A uniform grid over the circles of a scene, for hit-testing without
+//! going through every circle. A circle is registered in every cell its
+//! bounding box overlaps, so a query only looks at the cells it touches.
+//! The cell size should be about the typical circle diameter: circles
+//! spanning more cells are kept in a list that every query goes through,
+//! and queries touching more cells than there are circles go through the
+//! circles instead.
+//!
+//! `cargo bench -p structural --bench spatial_index` compares the grid with
+//! a linear scan.
+//!
+//! # Example
+//!
+//! ```rust
+//! use structural::flyweight::*;
+//! use structural::flyweight::spatial::*;
+//!
+//! let mut graphic = Graphic::new();
+//! let mut circles = CircleFactory::new();
+//! let a = graphic.add_circle(1, 1, 1.0, circles.make_circle(Color::Green));
+//! let b = graphic.add_circle(2, 1, 1.0, circles.make_circle(Color::Red));
+//! let c = graphic.add_circle(10, 10, 2.0, circles.make_circle(Color::Blue));
+//!
+//! let mut index = graphic.circle_index(2.0);
+//! assert_eq!(index.len(), 3);
+//! assert_eq!(index.at_point(1.5, 1.0), vec![a, b]);
+//! assert!(index.at_point(5.0, 5.0).is_empty());
+//! assert_eq!(index.in_rect(2.5, 0.0, 9.0, 9.0), vec![b, c]);
+//! let (id, distance) = index.nearest(6.0, 6.0).unwrap();
+//! assert_eq!(id, c);
+//! assert!((distance - (32f32.sqrt() - 2.0)).abs() < 1e-5);
+//! assert_eq!(index.nearest(1.0, 1.0), Some((a, 0.0)));
+//!
+//! assert!(index.remove(c));
+//! assert!(!index.remove(c));
+//! assert_eq!(index.nearest(6.0, 6.0).map(|(id, _)| id), Some(b));
+//! assert!(graphic.remove(c).is_some());
+//! assert_eq!(graphic.items.len(), 2);
+//!
+//! // Far away queries do not overflow the cell arithmetic.
+//! assert!(index.nearest(1e12, -1e12).is_some());
+//! assert!(index.nearest(f32::MAX, f32::MIN).is_some());
+//! assert!(index.in_rect(-1e12, -1e12, 1e12, 1e12).contains(&a));
+//!
+//! // Sparse scenes and large circles do not cost a cell each.
+//! let mut index = GridIndex::new(1.0);
+//! index.insert(a, 0.0, 0.0, 1.0);
+//! index.insert(b, 30000.0, 30000.0, 1.0);
+//! index.insert(c, 0.0, 30000.0, 3000.0);
+//! assert_eq!(index.in_rect(-1e6, -1e6, 1e6, 1e6), vec![a, b, c]);
+//! assert_eq!(index.at_point(100.0, 28000.0), vec![c]);
+//! assert_eq!(index.nearest(0.0, 20000.0), Some((c, 7000.0)));
+//! assert!(index.remove(c));
+//! assert_eq!(index.nearest(0.0, 20000.0).map(|(id, _)| id), Some(a));
+//! ```
+
+use std::collections::HashMap;
+
+use flyweight::ItemId;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Disc {
+  x: f32,
+  y: f32,
+  radius: f32,
+}
+
+impl Disc {
+  /// Distance from `(x, y)` to the disc, 0 inside.
+  fn distance(&self, x: f32, y: f32) -> f32 {
+    ((x - self.x).hypot(y - self.y) - self.radius).max(0.0)
+  }
+}
+
+type Cell = (i32, i32);
+
+/// Circles whose bounding box spans more cells are kept out of the cells.
+const MAX_CELLS: i64 = 64;
+
+pub struct GridIndex {
+  cell_size: f32,
+  cells: HashMap<Cell, Vec<ItemId>>,
+  discs: HashMap<ItemId, Disc>,
+  /// Circles spanning more than `MAX_CELLS` cells.
+  large: Vec<ItemId>,
+  /// Corners of the cells used since the cells were last all empty.
+  bounds: Option<(Cell, Cell)>,
+}
+
+impl GridIndex {
+  /// A grid of square cells of side `cell_size`.
+  ///
+  /// # Panics
+  ///
+  /// If `cell_size` is not a positive finite number.
+  pub fn new(cell_size: f32) -> GridIndex {
+    assert!(cell_size > 0.0 && cell_size.is_finite(), "the cell size must be positive");
+    GridIndex { cell_size, cells: HashMap::new(), discs: HashMap::new(), large: Vec::new(), bounds: None }
+  }
+
+  pub fn len(&self) -> usize {
+    self.discs.len()
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.discs.is_empty()
+  }
+
+  pub fn contains(&self, id: ItemId) -> bool {
+    self.discs.contains_key(&id)
+  }
+
+  /// Center and radius of the circle `id`.
+  pub fn circle(&self, id: ItemId) -> Option<(f32, f32, f32)> {
+    self.discs.get(&id).map(|disc| (disc.x, disc.y, disc.radius))
+  }
+
+  /// Adds a circle, replacing the one with the same id.
+  pub fn insert(&mut self, id: ItemId, x: f32, y: f32, radius: f32) {
+    self.remove(id);
+    let disc = Disc { x, y, radius: radius.abs() };
+    let (low, high) = self.cells_of(x - disc.radius, y - disc.radius, x + disc.radius, y + disc.radius);
+    self.discs.insert(id, disc);
+    if cell_count(low, high) > MAX_CELLS {
+      self.large.push(id);
+      return;
+    }
+    for cx in low.0..=high.0 {
+      for cy in low.1..=high.1 {
+        self.cells.entry((cx, cy)).or_default().push(id);
+      }
+    }
+    self.bounds = Some(match self.bounds {
+      None => (low, high),
+      Some((l, h)) => ((l.0.min(low.0), l.1.min(low.1)), (h.0.max(high.0), h.1.max(high.1))),
+    });
+  }
+
+  pub fn remove(&mut self, id: ItemId) -> bool {
+    let disc = match self.discs.remove(&id) {
+      Some(disc) => disc,
+      None => return false,
+    };
+    if let Some(position) = self.large.iter().position(|&other| other == id) {
+      self.large.swap_remove(position);
+      return true;
+    }
+    let (low, high) = self.cells_of(disc.x - disc.radius, disc.y - disc.radius, disc.x + disc.radius, disc.y + disc.radius);
+    for cx in low.0..=high.0 {
+      for cy in low.1..=high.1 {
+        if let Some(ids) = self.cells.get_mut(&(cx, cy)) {
+          ids.retain(|&other| other != id);
+          if ids.is_empty() {
+            self.cells.remove(&(cx, cy));
+          }
+        }
+      }
+    }
+    if self.cells.is_empty() {
+      self.bounds = None;
+    }
+    true
+  }
+
+  /// The circles containing `(x, y)`, by id.
+  pub fn at_point(&self, x: f32, y: f32) -> Vec<ItemId> {
+    let mut ids: Vec<ItemId> = self.cells.get(&self.cell_of(x, y)).into_iter().flatten()
+      .chain(self.large.iter())
+      .cloned()
+      .filter(|id| self.discs[id].distance(x, y) == 0.0)
+      .collect();
+    ids.sort_unstable();
+    ids
+  }
+
+  /// The circles overlapping the rectangle from `(x0, y0)` to `(x1, y1)`,
+  /// by id.
+  pub fn in_rect(&self, x0: f32, y0: f32, x1: f32, y1: f32) -> Vec<ItemId> {
+    let (x0, x1) = (x0.min(x1), x0.max(x1));
+    let (y0, y1) = (y0.min(y1), y0.max(y1));
+    let overlaps = |id: &ItemId| {
+      let disc = &self.discs[id];
+      disc.distance(disc.x.max(x0).min(x1), disc.y.max(y0).min(y1)) == 0.0
+    };
+    let (mut low, mut high) = self.cells_of(x0, y0, x1, y1);
+    if let Some((l, h)) = self.bounds {
+      low = (low.0.max(l.0), low.1.max(l.1));
+      high = (high.0.min(h.0), high.1.min(h.1));
+    }
+    let mut ids: Vec<ItemId> = if cell_count(low, high) > self.discs.len() as i64 {
+      // More cells to go through than circles.
+      self.discs.keys().cloned().filter(overlaps).collect()
+    } else {
+      let mut ids: Vec<ItemId> = self.large.iter().cloned().filter(overlaps).collect();
+      for cx in low.0..=high.0 {
+        for cy in low.1..=high.1 {
+          if let Some(cell) = self.cells.get(&(cx, cy)) {
+            ids.extend(cell.iter().cloned().filter(overlaps));
+          }
+        }
+      }
+      ids
+    };
+    ids.sort_unstable();
+    ids.dedup();
+    ids
+  }
+
+  /// The circle closest to `(x, y)`, with its distance, 0 inside. Ties go
+  /// to the lowest id.
+  pub fn nearest(&self, x: f32, y: f32) -> Option<(ItemId, f32)> {
+    let large = self.large.iter().fold(None, |best, &id| closer(best, id, self.discs[&id].distance(x, y)));
+    let (low, high) = match self.bounds {
+      Some(bounds) => bounds,
+      None => return large,
+    };
+    // Far away coordinates saturate to the edges of `i32`, and the gaps
+    // between cells may not fit in one: rings are counted in `i64`.
+    let center = self.cell_of(x, y);
+    let (cx, cy) = (center.0 as i64, center.1 as i64);
+    let ((x0, y0), (x1, y1)) = ((low.0 as i64, low.1 as i64), (high.0 as i64, high.1 as i64));
+    let reach = (cx - x0).abs().max((x1 - cx).abs()).max((cy - y0).abs()).max((cy - y1).abs());
+    // Rings closer than this one miss the grid.
+    let first = (x0 - cx).max(cx - x1).max(y0 - cy).max(cy - y1).max(0);
+    let saturated = [center.0, center.1].iter().any(|&c| c == i32::MIN || c == i32::MAX);
+    if saturated || (reach - first) as u64 > self.discs.len() as u64 {
+      // Cells no longer tell distances apart, or there are more rings to
+      // go through than circles.
+      return self.discs.iter().fold(None, |best, (&id, disc)| closer(best, id, disc.distance(x, y)));
+    }
+    let mut best = large;
+    for ring in first..=reach {
+      for cell in ring_cells(center, ring, (low, high)) {
+        for &id in self.cells.get(&cell).into_iter().flatten() {
+          best = closer(best, id, self.discs[&id].distance(x, y));
+        }
+      }
+      // Circles in no ring so far are at least `ring` cells away.
+      if let Some((_, distance)) = best {
+        if distance < ring as f32 * self.cell_size {
+          break;
+        }
+      }
+    }
+    best
+  }
+
+  fn cell_of(&self, x: f32, y: f32) -> Cell {
+    ((x / self.cell_size).floor() as i32, (y / self.cell_size).floor() as i32)
+  }
+
+  fn cells_of(&self, x0: f32, y0: f32, x1: f32, y1: f32) -> (Cell, Cell) {
+    (self.cell_of(x0, y0), self.cell_of(x1, y1))
+  }
+}
+
+/// Number of cells from `low` to `high`, both included.
+fn cell_count(low: Cell, high: Cell) -> i64 {
+  let columns = (high.0 as i64 - low.0 as i64 + 1).max(0);
+  let rows = (high.1 as i64 - low.1 as i64 + 1).max(0);
+  columns.saturating_mul(rows)
+}
+
+/// `best`, or `(id, distance)` if it is closer, ties going to the lowest id.
+fn closer(best: Option<(ItemId, f32)>, id: ItemId, distance: f32) -> Option<(ItemId, f32)> {
+  match best {
+    Some((best_id, best_distance)) if best_distance < distance || (best_distance == distance && best_id < id) => best,
+    _ => Some((id, distance)),
+  }
+}
+
+/// The cells at `ring` cells from `center`, going around its square, and
+/// within the `bounds` of the grid.
+fn ring_cells(center: Cell, ring: i64, bounds: (Cell, Cell)) -> Vec<Cell> {
+  let (x, y) = (center.0 as i64, center.1 as i64);
+  let ((x0, y0), (x1, y1)) = bounds;
+  let (x0, y0, x1, y1) = (x0 as i64, y0 as i64, x1 as i64, y1 as i64);
+  let mut rows = vec![y - ring];
+  let mut columns = vec![x - ring];
+  if ring > 0 {
+    rows.push(y + ring);
+    columns.push(x + ring);
+  }
+  let mut cells = Vec::new();
+  for &row in rows.iter().filter(|&&row| y0 <= row && row <= y1) {
+    for column in (x - ring).max(x0)..=(x + ring).min(x1) {
+      cells.push((column as i32, row as i32));
+    }
+  }
+  for &column in columns.iter().filter(|&&column| x0 <= column && column <= x1) {
+    for row in (y - ring + 1).max(y0)..=(y + ring - 1).min(y1) {
+      cells.push((column as i32, row as i32));
+    }
+  }
+  cells
+}
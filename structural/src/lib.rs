@@ -2,6 +2,8 @@
 //!
 #[macro_use]
 extern crate data_downloader_derive; 
+#[macro_use]
+extern crate serde_derive;
 
 pub mod adapter;
 pub mod decorator;
@@ -12,6 +14,12 @@ pub mod flyweight;
 pub mod proxy;
 
 extern crate colored;
+extern crate regex;
+extern crate flate2;
+extern crate serde;
+extern crate serde_json;
+extern crate csv;
+extern crate chacha20poly1305;
 
 use colored::*;
 
@@ -27,6 +35,12 @@ pub fn adapter() {
     logger.log_error(String::from("Show an error message."));
     logger.log_warning(String::from("About to finish."));
     logger.log_info(String::from("Bye!"));
+
+    use std::io::Write;
+    use adapter::io::LogWriter;
+
+    let mut writer = LogWriter::new(logger, Severity::Debug);
+    let _ = writeln!(writer, "Written through io::Write.");
 }
 
 /// decorator design patterns example.
@@ -48,38 +62,95 @@ pub fn decorator() {
     let dessert = Chocolat::new(Chantilly::new(Crepe::new()));
     println!("{}", dessert.to_string());
 
+    use decorator::menu::*;
+
+    let menu = Menu::load("./ressources/menu.json").unwrap();
+    let receipt = menu.dessert("Gauffre")
+        .and_then(|d| d.topping("Chocolat"))
+        .and_then(|d| d.topping("Chantilly"))
+        .and_then(|d| d.build().receipt());
+    match receipt {
+        Ok(receipt) => println!("{}", receipt),
+        Err(e) => println!("Error: {}", e),
+    }
+    if let Err(e) = menu.dessert("Crepe").and_then(|d| d.topping("Chocolat")).and_then(|d| d.topping("Caramel")) {
+        println!("Error: {}", e);
+    }
+
     use decorator::input_readers::*;
 
-    let reader = AdvancedInputReader::new("./ressources/input.txt");
+    let reader = AdvancedInputReader::new("./ressources/input.txt").unwrap();
     let mut reader = CapitalizedInputReader::new(reader);
-    let res = reader.read_lines();
-    for r in res.iter() {
-        println!("{}", r);
+    for r in reader.lines() {
+        match r {
+            Ok(r) => println!("{}", r),
+            Err(e) => println!("Error: {}", e),
+        }
     }
 
-    let reader = AdvancedInputReader::new("./ressources/input.txt");
+    let reader = AdvancedInputReader::new("./ressources/input.txt").unwrap();
     let reader = CapitalizedInputReader::new(reader);
     let mut reader = LenghtInputReader::new(reader);
-    let res = reader.read_lines();
-    for r in res.iter() {
-        println!("{}", r);
+    match reader.read_lines() {
+        Ok(res) => for r in res.iter() {
+            println!("{}", r);
+        },
+        Err(e) => println!("Error: {}", e),
+    }
+
+    if let Err(e) = AdvancedInputReader::new("./ressources/missing.txt") {
+        println!("Error: {}", e);
     }
 }
 
 /// bridge design patterns example.
 ///
 pub fn bridge() {
+    use bridge::access::*;
+    use bridge::door::*;
     use bridge::key::*;
 
     println!("Bridge");
-    let house = HouseOneDoor::new(Box::new(HouseDoorKey));
-    println!("{}", house.enter());
-    println!("{}", house.leave());
+    let mut store = CredentialStore::new();
+    store.fit_key("front door", HouseDoorKey::CUT);
+    store.fit_key("garage", CarDoorKey::CUT);
+    store.allow_badge(42, "front door");
+
+    let mut house = HouseOneDoor::new("front door", Box::new(HouseDoorKey));
+    match house.enter(&store) {
+        Ok(text) => println!("{}", text),
+        Err(e) => println!("Error: {}", e),
+    }
+    match house.leave(&store) {
+        Ok(text) => println!("{}", text),
+        Err(e) => println!("Error: {}", e),
+    }
 
-    let garage = CarOneDoor::new(Box::new(CarDoorKey));
-    println!("{}", garage.enter());
-    println!("{}", garage.leave());   
+    let mut garage = CarOneDoor::new("garage", Box::new(CarDoorKey));
+    match garage.enter(&store) {
+        Ok(text) => println!("{}", text),
+        Err(e) => println!("Error: {}", e),
+    }
+    match garage.leave(&store) {
+        Ok(text) => println!("{}", text),
+        Err(e) => println!("Error: {}", e),
+    }
 
+    let mut badge = HouseOneDoor::new("garage", Box::new(RfidBadge::new(42)));
+    if let Err(e) = badge.enter(&store) {
+        println!("Error: {}", e);
+    }
+
+    store.set_pin("lobby", "2468");
+    let mut building = Building::new("HQ");
+    building.add_door("lobby", Box::new(PinCode::new("2468")));
+    building.add_door("office", Box::new(RfidBadge::new(42)));
+    let _ = building.open("lobby", &store);
+    let _ = building.lock("lobby", &store);
+    let _ = building.open("office", &store);
+    for entry in building.audit() {
+        println!("{}", entry);
+    }
 }
 
 /// composite design patterns example.
@@ -110,6 +181,16 @@ pub fn composite() {
     tree.add(Box::new(subtree3));
 
     tree.print(String::from("-"));
+    print!("{}", composite::render::ascii(&tree));
+    match composite::format::to_json(&tree) {
+        Ok(json) => println!("{}", json),
+        Err(e) => println!("Error: {}", e),
+    }
+
+    match composite::fs::DirNode::open("./ressources") {
+        Ok(dir) => print!("{}", composite::render::ascii_with(&dir, composite::render::name_and_value)),
+        Err(e) => println!("Error: {}", e),
+    }
 }
 
 /// facade design patterns example.
@@ -117,10 +198,27 @@ pub fn composite() {
 pub fn facade() {
     use facade::with_struct::*;
 
-    println!("Facade with derive trait");
-    let url = String::from("google.com");
-    let reader = DataReader;
-    println!("{:?}", reader.read_person(url));
+    println!("Facade");
+    let reader = DataReader::new();
+    match std::fs::canonicalize("./ressources/person.b64") {
+        Ok(path) => match reader.read_person(format!("file://{}", path.display())) {
+            Ok(person) => println!("{:?}", person),
+            Err(e) => println!("Error: {}", e),
+        },
+        Err(e) => println!("Error: {}", e),
+    }
+    if let Err(e) = reader.read_person(String::from("google.com")) {
+        println!("Error: {}", e);
+    }
+
+    let reader: DataReader = DataReader::with_codecs(facade::codec::CodecChain::new());
+    match std::fs::canonicalize("./ressources/persons.json") {
+        Ok(path) => match reader.read_all(format!("file://{}", path.display())) {
+            Ok(persons) => println!("{:?}", persons),
+            Err(e) => println!("Error: {}", e),
+        },
+        Err(e) => println!("Error: {}", e),
+    }
 }
 
 /// facade (with derive trait) design patterns example.
@@ -129,9 +227,14 @@ pub fn facade_with_derive_trait() {
     use facade::with_derive_trait::*;
 
     println!("Facade with derive trait");
-    let url = String::from("google.com");
     let reader = DataReader;
-    println!("{:?}", reader.read_person(url));
+    match std::fs::canonicalize("./ressources/person.b64") {
+        Ok(path) => match reader.read_person(format!("file://{}", path.display())) {
+            Ok(person) => println!("{:?}", person),
+            Err(e) => println!("Error: {}", e),
+        },
+        Err(e) => println!("Error: {}", e),
+    }
 }
 
 /// flyweight design patterns example.
@@ -154,8 +257,11 @@ pub fn flyweight() {
     graphic.add_circle(4, 4, 1.0, circles.make_circle(Color::Red));
 
     graphic.draw();
+    print!("{}", flyweight::render::Canvas::new(60, 60).scale(12.0).svg(&graphic));
 
     println!("Total number of circle objects created: {}", circles.circles_created());
+    let stats = circles.stats();
+    println!("{} handles share {} circles, saving {} bytes", stats.handles, stats.entries, stats.saved_bytes());
 }
 
 /// proxy design patterns example.
@@ -169,11 +275,17 @@ pub fn proxy() {
     file_map.insert("file1.txt", ("FileReaderProxy", Box::new(FileReaderProxy::new(String::from("./ressources/file1.txt")))));
     file_map.insert("file2.txt", ("FileReaderProxy", Box::new(FileReaderProxy::new(String::from("./ressources/file2.txt")))));
     file_map.insert("file3.txt", ("FileReaderProxy", Box::new(FileReaderProxy::new(String::from("./ressources/file3.txt")))));
-    file_map.insert("file4.txt", ("FileReaderReal", Box::new(FileReaderReal::new(String::from("./ressources/file1.txt")))));
+    match FileReaderReal::new(String::from("./ressources/file1.txt")) {
+        Ok(reader) => { file_map.insert("file4.txt", ("FileReaderReal", Box::new(reader))); }
+        Err(e) => println!("Could not read file4.txt: {}", e),
+    }
     
     println!("Created the map. You should have seen file1.txt read because it wasn't used in a proxy.");
     for (k, v) in file_map.iter_mut() { 
-        println!("Reading {} from the {}: {}", k, v.0, v.1.read_file_contents()); 
+        match v.1.read_file_contents() {
+            Ok(contents) => println!("Reading {} from the {}: {}", k, v.0, contents),
+            Err(e) => println!("Reading {} from the {} failed: {}", k, v.0, e),
+        }
     }
 }
 
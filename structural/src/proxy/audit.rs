@@ -0,0 +1,172 @@
+//! `audit` module.
+//!
+//! `AuditProxy` records every read through it in an `AuditLog`: who read
+//! which file, when, and how many bytes, or why it failed. The log is kept
+//! in memory and can also be appended to a file, one line per read; control
+//! characters and backslashes are escaped so a record cannot span lines.
+//!
+//! See `proxy::protection` for a stack of proxies.
+//!
+//! # Example
+//!
+//! ```rust
+//! use structural::proxy::*;
+//! use structural::proxy::audit::*;
+//!
+//! let path = std::env::temp_dir().join(format!("audit-{}.log", std::process::id()));
+//! let log = AuditLog::to_file(&path).unwrap();
+//! let mut reader = AuditProxy::new("carol", log.clone(), FileReaderProxy::new(String::from("/no/such/file")));
+//! assert!(reader.read_file_contents().is_err());
+//! assert!(matches!(log.records()[0].outcome, AuditOutcome::Failed(_)));
+//!
+//! let line = std::fs::read_to_string(&path).unwrap();
+//! assert!(line.contains("] carol read /no/such/file: failed, "));
+//!
+//! let mut reader = AuditProxy::new("eve\n[0] eve", log.clone(), FileReaderProxy::new(String::from("a\nb\\c")));
+//! assert!(reader.read_file_contents().is_err());
+//! let lines = std::fs::read_to_string(&path).unwrap();
+//! assert_eq!(lines.lines().count(), 2);
+//! assert!(lines.contains("] eve\\n[0] eve read a\\nb\\\\c: failed, "));
+//! std::fs::remove_file(&path).unwrap();
+//! ```
+
+use std::fmt;
+use std::fs::{File, OpenOptions};
+use std::io;
+use std::io::Write;
+use std::path::Path;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use proxy::protection::Denial;
+use proxy::{FileReader, ReadError};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum AuditOutcome {
+  Read { bytes: usize },
+  Denied(Denial),
+  /// The file could not be read.
+  Failed(String),
+}
+
+/// One read through an `AuditProxy`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AuditRecord {
+  pub caller: String,
+  pub path: String,
+  pub at: SystemTime,
+  pub outcome: AuditOutcome,
+}
+
+impl fmt::Display for AuditRecord {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    let millis = self.at.duration_since(UNIX_EPOCH).map(|d| d.as_millis()).unwrap_or(0);
+    write!(f, "[{}] {} read {}: ", millis, escape(&self.caller), escape(&self.path))?;
+    match self.outcome {
+      AuditOutcome::Read { bytes } => write!(f, "{} bytes", bytes),
+      AuditOutcome::Denied(ref reason) => write!(f, "denied, {}", escape(&reason.to_string())),
+      AuditOutcome::Failed(ref error) => write!(f, "failed, {}", escape(error)),
+    }
+  }
+}
+
+/// `text` with its control characters and backslashes escaped, as in Rust
+/// string literals.
+fn escape(text: &str) -> String {
+  text.chars().map(|c| {
+    if c.is_control() || c == '\\' {
+      c.escape_default().to_string()
+    } else {
+      c.to_string()
+    }
+  }).collect()
+}
+
+#[derive(Default)]
+struct Inner {
+  records: Vec<AuditRecord>,
+  file: Option<File>,
+}
+
+/// A handle on the log; clones share it.
+#[derive(Clone, Default)]
+pub struct AuditLog {
+  inner: Arc<Mutex<Inner>>,
+}
+
+impl AuditLog {
+  /// A log kept in memory only.
+  pub fn new() -> AuditLog {
+    AuditLog::default()
+  }
+
+  /// Also appends every record to `path`, created if needed.
+  pub fn to_file<P: AsRef<Path>>(path: P) -> io::Result<AuditLog> {
+    let file = OpenOptions::new().create(true).append(true).open(path)?;
+    Ok(AuditLog { inner: Arc::new(Mutex::new(Inner { records: Vec::new(), file: Some(file) })) })
+  }
+
+  /// Adds a record. A failure to write the file is reported, the record
+  /// is kept in memory anyway.
+  pub fn record(&self, record: AuditRecord) -> io::Result<()> {
+    let mut inner = self.lock();
+    let written = match inner.file {
+      Some(ref mut file) => writeln!(file, "{}", record),
+      None => Ok(()),
+    };
+    inner.records.push(record);
+    written
+  }
+
+  pub fn records(&self) -> Vec<AuditRecord> {
+    self.lock().records.clone()
+  }
+
+  pub fn records_of<'a>(&self, caller: &'a str) -> impl Iterator<Item = AuditRecord> + 'a {
+    self.records().into_iter().filter(move |record| record.caller == caller)
+  }
+
+  fn lock(&self) -> MutexGuard<'_, Inner> {
+    self.inner.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+  }
+}
+
+/// Reads through `inner` on behalf of `caller`, recording every read.
+pub struct AuditProxy<R> {
+  caller: String,
+  log: AuditLog,
+  inner: R,
+}
+
+impl<R: FileReader> AuditProxy<R> {
+  pub fn new(caller: &str, log: AuditLog, inner: R) -> AuditProxy<R> {
+    AuditProxy { caller: String::from(caller), log, inner }
+  }
+
+  pub fn into_inner(self) -> R {
+    self.inner
+  }
+}
+
+impl<R: FileReader> FileReader for AuditProxy<R> {
+  fn file_name(&self) -> &str {
+    self.inner.file_name()
+  }
+
+  fn read_file_contents(&mut self) -> Result<String, ReadError> {
+    let result = self.inner.read_file_contents();
+    let outcome = match result {
+      Ok(ref contents) => AuditOutcome::Read { bytes: contents.len() },
+      Err(ReadError::Denied { ref reason, .. }) => AuditOutcome::Denied(reason.clone()),
+      Err(ref error) => AuditOutcome::Failed(error.to_string()),
+    };
+    // An audit trail that cannot be written must not hide the read.
+    let _ = self.log.record(AuditRecord {
+      caller: self.caller.clone(),
+      path: String::from(self.inner.file_name()),
+      at: SystemTime::now(),
+      outcome,
+    });
+    result
+  }
+}
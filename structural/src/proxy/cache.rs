@@ -0,0 +1,167 @@
+//! `cache` module.
+//!
+//! File contents shared by several `FileReaderProxy`s, bounded in bytes.
+//! When a new file does not fit, the least recently read ones are evicted.
+//! An entry remembers the size and modification time of its file, so a
+//! proxy can tell whether it is still valid.
+//!
+//! See `proxy` for an example.
+
+use std::collections::{HashMap, VecDeque};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::SystemTime;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CacheStats {
+  /// Reads served from the cache.
+  pub hits: usize,
+  /// Reads of the file itself.
+  pub misses: usize,
+  /// Entries dropped because their file changed.
+  pub invalidations: usize,
+  /// Entries dropped to make room.
+  pub evictions: usize,
+}
+
+/// What a cached file looked like when it was read.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Version {
+  pub size: u64,
+  pub modified: Option<SystemTime>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Lookup {
+  Hit(Arc<String>),
+  /// The file changed since it was cached.
+  Stale,
+  Absent,
+}
+
+struct Entry {
+  contents: Arc<String>,
+  version: Version,
+}
+
+#[derive(Default)]
+struct Inner {
+  entries: HashMap<PathBuf, Entry>,
+  /// Paths from the least to the most recently read.
+  order: VecDeque<PathBuf>,
+  bytes: usize,
+  stats: CacheStats,
+}
+
+/// A handle on the cache; clones share it.
+#[derive(Clone)]
+pub struct ProxyCache {
+  max_bytes: usize,
+  inner: Arc<Mutex<Inner>>,
+}
+
+impl ProxyCache {
+  /// Keeps at most `max_bytes` of contents.
+  pub fn new(max_bytes: usize) -> ProxyCache {
+    ProxyCache { max_bytes, inner: Arc::new(Mutex::new(Inner::default())) }
+  }
+
+  pub fn unbounded() -> ProxyCache {
+    ProxyCache::new(usize::MAX)
+  }
+
+  pub fn max_bytes(&self) -> usize {
+    self.max_bytes
+  }
+
+  /// Bytes of contents held.
+  pub fn bytes(&self) -> usize {
+    self.lock().bytes
+  }
+
+  pub fn len(&self) -> usize {
+    self.lock().entries.len()
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.lock().entries.is_empty()
+  }
+
+  pub fn contains<P: AsRef<Path>>(&self, path: P) -> bool {
+    self.lock().entries.contains_key(path.as_ref())
+  }
+
+  pub fn stats(&self) -> CacheStats {
+    self.lock().stats
+  }
+
+  /// Looks `path` up for the file at `version`; a stale entry is dropped.
+  /// Counts a hit or an invalidation; the miss is counted by `put`.
+  pub fn lookup(&self, path: &Path, version: Version) -> Lookup {
+    let mut inner = self.lock();
+    let contents = match inner.entries.get(path) {
+      None => return Lookup::Absent,
+      Some(entry) if entry.version == version => entry.contents.clone(),
+      Some(_) => {
+        inner.remove(path);
+        inner.stats.invalidations += 1;
+        return Lookup::Stale;
+      }
+    };
+    inner.touch(path);
+    inner.stats.hits += 1;
+    Lookup::Hit(contents)
+  }
+
+  /// Stores contents read from the file, evicting the least recently read
+  /// entries to fit; contents larger than the cache are not kept.
+  pub fn put(&self, path: &Path, version: Version, contents: Arc<String>) {
+    let mut inner = self.lock();
+    inner.stats.misses += 1;
+    inner.remove(path);
+    if contents.len() > self.max_bytes {
+      return;
+    }
+    while inner.bytes + contents.len() > self.max_bytes {
+      match inner.order.front().cloned() {
+        Some(oldest) => {
+          inner.remove(&oldest);
+          inner.stats.evictions += 1;
+        }
+        None => break,
+      }
+    }
+    inner.bytes += contents.len();
+    inner.order.push_back(path.to_path_buf());
+    inner.entries.insert(path.to_path_buf(), Entry { contents, version });
+  }
+
+  /// Drops the entry of `path`.
+  pub fn invalidate<P: AsRef<Path>>(&self, path: P) -> bool {
+    self.lock().remove(path.as_ref())
+  }
+
+  fn lock(&self) -> MutexGuard<'_, Inner> {
+    self.inner.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+  }
+}
+
+impl Inner {
+  fn touch(&mut self, path: &Path) {
+    if let Some(at) = self.order.iter().position(|p| p == path) {
+      let path = self.order.remove(at).unwrap();
+      self.order.push_back(path);
+    }
+  }
+
+  fn remove(&mut self, path: &Path) -> bool {
+    match self.entries.remove(path) {
+      Some(entry) => {
+        self.bytes -= entry.contents.len();
+        self.order.retain(|p| p != path);
+        true
+      }
+      None => false,
+    }
+  }
+}
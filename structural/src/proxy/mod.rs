@@ -1,29 +1,135 @@
 //! `proxy` module.
 //!
+//! `FileReaderProxy` stands for a `FileReaderReal` and only reads the file
+//! when its contents are asked for. It keeps them in a `ProxyCache`, which
+//! may be shared by several proxies and bounded in size, and reads the file
+//! again once its size or modification time changed. Protection and audit
+//! proxies wrap any `FileReader`, see `proxy::protection`, and
+//! `proxy::remote` reads files served by another process.
+//!
 //! # Example
-//! 
+//!
 //! ```rust,ignore
 //! use structural::proxy::*;
 //! use std::collections::HashMap;
-//! 
+//!
 //! let mut file_map: HashMap<&str, (&str, Box<FileReader>)> = HashMap::new();
 //! file_map.insert("file1.txt", ("FileReaderProxy", Box::new(FileReaderProxy::new(String::from("file1.txt")))));
 //! file_map.insert("file2.txt", ("FileReaderProxy", Box::new(FileReaderProxy::new(String::from("file2.txt")))));
 //! file_map.insert("file3.txt", ("FileReaderProxy", Box::new(FileReaderProxy::new(String::from("file3.txt")))));
-//! file_map.insert("file4.txt", ("FileReaderReal", Box::new(FileReaderReal::new(String::from("file1.txt")))));
-//! 
+//! file_map.insert("file4.txt", ("FileReaderReal", Box::new(FileReaderReal::new(String::from("file1.txt")).unwrap())));
+//!
 //! println!("Created the map. You should have seen file1.txt read because it wasn't used in a proxy.");
-//! for (k, v) in file_map.iter_mut() { 
-//!     println!("Reading {} from the {}: {}", k, v.0, v.1.read_file_contents()); 
+//! for (k, v) in file_map.iter_mut() {
+//!     println!("Reading {} from the {}: {}", k, v.0, v.1.read_file_contents().unwrap());
 //! }
 //! ```
-  
-use std::io::BufReader;
+//!
+//! Caching and invalidation:
+//!
+//! ```rust
+//! use std::fs;
+//! use structural::proxy::*;
+//! use structural::proxy::cache::*;
+//!
+//! let dir = std::env::temp_dir().join(format!("proxy-{}", std::process::id()));
+//! fs::create_dir_all(&dir).unwrap();
+//! let path = |name: &str| dir.join(name).to_str().unwrap().to_string();
+//! fs::write(path("a.txt"), "aaaa").unwrap();
+//! fs::write(path("b.txt"), "bbbb").unwrap();
+//!
+//! let mut a = FileReaderProxy::new(path("a.txt"));
+//! assert_eq!(a.read_file_contents().unwrap(), "aaaa");
+//! assert_eq!(a.read_file_contents().unwrap(), "aaaa");
+//! fs::write(path("a.txt"), "aaaaaa").unwrap();
+//! assert_eq!(a.read_file_contents().unwrap(), "aaaaaa");
+//! assert_eq!(a.stats(), CacheStats { hits: 1, misses: 2, invalidations: 1, evictions: 0 });
+//!
+//! // Same size, newer modification time.
+//! fs::write(path("a.txt"), "AAAAAA").unwrap();
+//! let later = std::time::SystemTime::now() + std::time::Duration::from_secs(5);
+//! fs::File::options().write(true).open(path("a.txt")).unwrap().set_modified(later).unwrap();
+//! assert_eq!(a.read_file_contents().unwrap(), "AAAAAA");
+//!
+//! // Two proxies share 6 bytes: reading b evicts a.
+//! let cache = ProxyCache::new(6);
+//! let mut a = FileReaderProxy::with_cache(path("a.txt"), cache.clone());
+//! let mut b = FileReaderProxy::with_cache(path("b.txt"), cache.clone());
+//! a.read_file_contents().unwrap();
+//! b.read_file_contents().unwrap();
+//! assert!(!cache.contains(path("a.txt")));
+//! assert_eq!((cache.len(), cache.bytes()), (1, 4));
+//! assert_eq!(cache.stats().evictions, 1);
+//!
+//! match FileReaderProxy::new(path("missing.txt")).read_file_contents() {
+//!   Err(ReadError::Io { path, .. }) => assert!(path.ends_with("missing.txt")),
+//!   other => panic!("unexpected {:?}", other),
+//! }
+//! assert!(FileReaderReal::new(path("missing.txt")).is_err());
+//! fs::remove_dir_all(&dir).unwrap();
+//! ```
+
+pub mod audit;
+pub mod cache;
+pub mod protection;
+pub mod remote;
+
+use std::error::Error;
+use std::fmt;
+use std::fs;
 use std::fs::File;
+use std::io;
+use std::io::BufReader;
 use std::io::Read;
+use std::path::Path;
+use std::sync::Arc;
+
+use proxy::cache::{CacheStats, Lookup, ProxyCache, Version};
+use proxy::protection::Denial;
+
+#[derive(Debug)]
+pub enum ReadError {
+  Io { path: String, error: io::Error },
+  /// A `ProtectionProxy` refused the read.
+  Denied { path: String, caller: String, reason: Denial },
+}
+
+impl ReadError {
+  fn io(path: &str, error: io::Error) -> ReadError {
+    ReadError::Io { path: String::from(path), error }
+  }
+}
+
+impl fmt::Display for ReadError {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match *self {
+      ReadError::Io { ref path, ref error } => write!(f, "{}: {}", path, error),
+      ReadError::Denied { ref path, ref caller, ref reason } => write!(f, "{} may not read {}: {}", caller, path, reason),
+    }
+  }
+}
+
+impl Error for ReadError {
+  fn source(&self) -> Option<&(dyn Error + 'static)> {
+    match *self {
+      ReadError::Io { ref error, .. } => Some(error),
+      ReadError::Denied { .. } => None,
+    }
+  }
+}
 
 pub trait FileReader {
-  fn read_file_contents(&mut self) -> String;
+  fn file_name(&self) -> &str;
+  fn read_file_contents(&mut self) -> Result<String, ReadError>;
+}
+
+impl<R: FileReader + ?Sized> FileReader for Box<R> {
+  fn file_name(&self) -> &str {
+    (**self).file_name()
+  }
+  fn read_file_contents(&mut self) -> Result<String, ReadError> {
+    (**self).read_file_contents()
+  }
 }
 
 #[derive(Clone)]
@@ -32,49 +138,77 @@ pub struct FileReaderReal {
   contents: String,
 }
 impl FileReaderReal {
-  pub fn new(file_name: String) -> FileReaderReal {
+  pub fn new(file_name: String) -> Result<FileReaderReal, ReadError> {
+    let contents = content(&file_name).map_err(|error| ReadError::io(&file_name, error))?;
     println!("Finished reading the actual file: {}", file_name);
-    FileReaderReal {
-      contents: content(file_name.clone()),
+    Ok(FileReaderReal {
+      contents,
       file_name,
-    }
+    })
   }
 }
 
-fn content(path: String) -> String {
-  let path = File::open(path).unwrap();
+fn content(path: &str) -> io::Result<String> {
+  let path = File::open(path)?;
   let mut reader = BufReader::new(path);
   let mut buf = String::new();
-  let _ = reader.read_to_string(&mut buf);
-  buf
+  reader.read_to_string(&mut buf)?;
+  Ok(buf)
 }
 
 impl FileReader for FileReaderReal {
-  fn read_file_contents(&mut self) -> String {
-    self.contents.clone()
+  fn file_name(&self) -> &str {
+    &self.file_name
+  }
+  fn read_file_contents(&mut self) -> Result<String, ReadError> {
+    Ok(self.contents.clone())
   }
 }
 
 pub struct FileReaderProxy {
   file_name: String,
-  file_reader: Option<FileReaderReal>
+  cache: ProxyCache,
+  /// Reads through this proxy; evictions are only counted by the cache.
+  stats: CacheStats,
 }
 impl FileReaderProxy {
+  /// A proxy with a cache of its own.
   pub fn new(file_name: String) -> FileReaderProxy {
+    FileReaderProxy::with_cache(file_name, ProxyCache::unbounded())
+  }
+
+  pub fn with_cache(file_name: String, cache: ProxyCache) -> FileReaderProxy {
     FileReaderProxy {
       file_name,
-      file_reader: None,
+      cache,
+      stats: CacheStats::default(),
     }
   }
+
+  pub fn stats(&self) -> CacheStats {
+    self.stats
+  }
 }
 impl FileReader for FileReaderProxy {
-  fn read_file_contents(&mut self) -> String {
-    if let Some(ref mut file_reader) = self.file_reader {
-      file_reader.read_file_contents()
-    } else {
-      let mut file_reader = FileReaderReal::new(self.file_name.clone());
-      self.file_reader = Some(file_reader.clone());
-      file_reader.read_file_contents()
-    } 
+  fn file_name(&self) -> &str {
+    &self.file_name
   }
-}
\ No newline at end of file
+  fn read_file_contents(&mut self) -> Result<String, ReadError> {
+    let path = Path::new(&self.file_name);
+    let metadata = fs::metadata(path).map_err(|error| ReadError::io(&self.file_name, error))?;
+    let version = Version { size: metadata.len(), modified: metadata.modified().ok() };
+    match self.cache.lookup(path, version) {
+      Lookup::Hit(contents) => {
+        self.stats.hits += 1;
+        return Ok((*contents).clone());
+      }
+      Lookup::Stale => self.stats.invalidations += 1,
+      Lookup::Absent => {}
+    }
+    self.stats.misses += 1;
+    let mut file_reader = FileReaderReal::new(self.file_name.clone())?;
+    let contents = file_reader.read_file_contents()?;
+    self.cache.put(path, version, Arc::new(contents.clone()));
+    Ok(contents)
+  }
+}
@@ -0,0 +1,331 @@
+//! `protection` module.
+//!
+//! `ProtectionProxy` lets a caller read a file only if a `Policy` allows
+//! it. A policy has path allow and deny lists and per-caller grants, all
+//! shell-style globs (see `composite::fs::Glob`), and is usually read from
+//! a file:
+//!
+//! ```text
+//! # Denied to everyone, whatever the other rules.
+//! deny *.secret
+//! # Only paths matching an allow rule, if there is any.
+//! allow /srv/docs/**
+//! # With grants, a caller only reads the paths granted to it, or to `*`.
+//! grant alice /srv/docs/**
+//! grant * /srv/docs/public/*
+//! ```
+//!
+//! Paths are normalized before matching, so `docs/../secret` is `secret`,
+//! and a path whose `..` climbs above its start is denied. Rules see both
+//! the absolute path and, inside the current directory, the relative one,
+//! so `deny /srv/secret/**` also covers `secret/x` read from `/srv`. The
+//! file the path leads to, through symbolic links, must pass the rules
+//! too; a path which cannot be resolved, for instance because the file is
+//! missing, is denied.
+//! The proxies stack: a protection proxy may guard a lazy
+//! `FileReaderProxy`, and be wrapped in an `AuditProxy`.
+//!
+//! # Example
+//!
+//! ```rust
+//! use std::sync::Arc;
+//! use structural::proxy::*;
+//! use structural::proxy::audit::*;
+//! use structural::proxy::protection::*;
+//!
+//! let dir = std::env::temp_dir().join(format!("protection-{}", std::process::id()));
+//! std::fs::create_dir_all(dir.join("public")).unwrap();
+//! let path = |name: &str| dir.join(name).to_str().unwrap().to_string();
+//! std::fs::write(path("public/notice.txt"), "Hello").unwrap();
+//! std::fs::write(path("report.txt"), "Figures").unwrap();
+//! std::fs::write(path("keys.secret"), "hunter2").unwrap();
+//!
+//! let rules = format!("deny *.secret\nallow {0}/**\ngrant alice {0}/**\ngrant * {0}/public/*\n", dir.display());
+//! std::fs::write(path("policy"), rules).unwrap();
+//! let policy = Arc::new(Policy::from_file(path("policy")).unwrap());
+//!
+//! let log = AuditLog::new();
+//! let reader = |caller: &str, name: &str| {
+//!   AuditProxy::new(caller, log.clone(),
+//!     ProtectionProxy::new(caller, policy.clone(), FileReaderProxy::new(path(name))))
+//! };
+//! assert_eq!(reader("alice", "report.txt").read_file_contents().unwrap(), "Figures");
+//! assert_eq!(reader("bob", "public/notice.txt").read_file_contents().unwrap(), "Hello");
+//! match reader("bob", "report.txt").read_file_contents() {
+//!   Err(ReadError::Denied { caller, reason, .. }) => {
+//!     assert_eq!(caller, "bob");
+//!     assert_eq!(reason, Denial::NotGranted);
+//!   }
+//!   other => panic!("unexpected {:?}", other),
+//! }
+//! assert!(matches!(reader("alice", "keys.secret").read_file_contents(),
+//!   Err(ReadError::Denied { reason: Denial::DenyRule(_), .. })));
+//! assert!(matches!(reader("alice", "public/../keys.secret").read_file_contents(),
+//!   Err(ReadError::Denied { .. })));
+//! assert!(matches!(reader("alice", "/etc/hostname").read_file_contents(),
+//!   Err(ReadError::Denied { reason: Denial::NotAllowed, .. })));
+//!
+//! let records = log.records();
+//! assert_eq!(records.len(), 6);
+//! assert_eq!(records[0].outcome, AuditOutcome::Read { bytes: 7 });
+//! assert_eq!(log.records_of("bob").count(), 2);
+//! for record in &records {
+//!   println!("{}", record);
+//! }
+//!
+//! assert!(matches!("grant alice".parse::<Policy>(), Err(PolicyError::Syntax { line: 1, .. })));
+//! assert_eq!(Policy::new().allow("docs/**").check("alice", "../docs/x"), Err(Denial::Escapes));
+//! assert!(matches!(reader("alice", "missing.txt").read_file_contents(),
+//!   Err(ReadError::Denied { reason: Denial::Unresolved(_), .. })));
+//! #[cfg(unix)]
+//! {
+//!   std::os::unix::fs::symlink(path("keys.secret"), path("public/keys.txt")).unwrap();
+//!   assert!(matches!(reader("bob", "public/keys.txt").read_file_contents(),
+//!     Err(ReadError::Denied { reason: Denial::DenyRule(_), .. })));
+//! }
+//!
+//! std::env::set_current_dir(&dir).unwrap();
+//! let policy = Policy::new().deny(&format!("{}/public/**", dir.display()));
+//! assert!(matches!(policy.check("bob", "public/notice.txt"), Err(Denial::DenyRule(_))));
+//! assert!(matches!(policy.check("bob", "./public/notice.txt"), Err(Denial::DenyRule(_))));
+//! assert_eq!(policy.check("bob", "report.txt"), Ok(()));
+//! std::fs::remove_dir_all(&dir).unwrap();
+//! ```
+
+use std::env;
+use std::error::Error;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Component, Path, PathBuf};
+use std::str::FromStr;
+use std::sync::Arc;
+
+use composite::fs::Glob;
+use proxy::{FileReader, ReadError};
+
+/// Why a read was refused.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Denial {
+  /// The path matches this deny rule.
+  DenyRule(String),
+  /// The path matches no allow rule.
+  NotAllowed,
+  /// The path is granted neither to the caller nor to everyone.
+  NotGranted,
+  /// A `..` of the path climbs above where it starts.
+  Escapes,
+  /// The path cannot be resolved to a file, for this reason.
+  Unresolved(String),
+}
+
+impl fmt::Display for Denial {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match *self {
+      Denial::DenyRule(ref pattern) => write!(f, "denied by `{}`", pattern),
+      Denial::NotAllowed => write!(f, "not in the allowed paths"),
+      Denial::NotGranted => write!(f, "not granted to the caller"),
+      Denial::Escapes => write!(f, "climbs above its start"),
+      Denial::Unresolved(ref reason) => write!(f, "cannot be resolved: {}", reason),
+    }
+  }
+}
+
+#[derive(Debug)]
+pub enum PolicyError {
+  Io(io::Error),
+  Syntax { line: usize, reason: String },
+}
+
+impl fmt::Display for PolicyError {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match *self {
+      PolicyError::Io(ref e) => write!(f, "{}", e),
+      PolicyError::Syntax { line, ref reason } => write!(f, "line {}: {}", line, reason),
+    }
+  }
+}
+
+impl Error for PolicyError {
+  fn source(&self) -> Option<&(dyn Error + 'static)> {
+    match *self {
+      PolicyError::Io(ref e) => Some(e),
+      _ => None,
+    }
+  }
+}
+
+impl From<io::Error> for PolicyError {
+  fn from(error: io::Error) -> PolicyError {
+    PolicyError::Io(error)
+  }
+}
+
+/// A glob with the text it was made from.
+#[derive(Debug, Clone)]
+struct Rule {
+  pattern: String,
+  glob: Glob,
+}
+
+impl Rule {
+  fn new(pattern: &str) -> Rule {
+    Rule { pattern: String::from(pattern), glob: Glob::new(pattern) }
+  }
+}
+
+/// Which caller may read which path.
+#[derive(Debug, Clone, Default)]
+pub struct Policy {
+  allow: Vec<Rule>,
+  deny: Vec<Rule>,
+  grants: Vec<(String, Rule)>,
+}
+
+impl Policy {
+  /// Allows everything.
+  pub fn new() -> Policy {
+    Policy::default()
+  }
+
+  pub fn allow(mut self, pattern: &str) -> Policy {
+    self.allow.push(Rule::new(pattern));
+    self
+  }
+
+  pub fn deny(mut self, pattern: &str) -> Policy {
+    self.deny.push(Rule::new(pattern));
+    self
+  }
+
+  /// Grants `pattern` to `caller`, or to every caller with `*`.
+  pub fn grant(mut self, caller: &str, pattern: &str) -> Policy {
+    self.grants.push((String::from(caller), Rule::new(pattern)));
+    self
+  }
+
+  pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Policy, PolicyError> {
+    fs::read_to_string(path)?.parse()
+  }
+
+  /// Checks `path` as written, then the file it resolves to on disk.
+  pub fn check(&self, caller: &str, path: &str) -> Result<(), Denial> {
+    let normalized = normalize(path).ok_or(Denial::Escapes)?;
+    let unresolved = |error: io::Error| Denial::Unresolved(error.to_string());
+    let current = env::current_dir().map_err(unresolved)?;
+    self.check_forms(caller, &forms(&normalized, &current))?;
+    let resolved = fs::canonicalize(path).map_err(unresolved)?;
+    let current = fs::canonicalize(current).map_err(unresolved)?;
+    self.check_forms(caller, &forms(&resolved, &current))
+  }
+
+  /// Checks the spellings of one path; a rule applies if it matches any.
+  fn check_forms(&self, caller: &str, forms: &[String]) -> Result<(), Denial> {
+    let matches = |rule: &Rule| forms.iter().any(|form| rule.glob.matches(form));
+    if let Some(rule) = self.deny.iter().find(|rule| matches(rule)) {
+      return Err(Denial::DenyRule(rule.pattern.clone()));
+    }
+    if !self.allow.is_empty() && !self.allow.iter().any(&matches) {
+      return Err(Denial::NotAllowed);
+    }
+    let granted = self.grants.iter()
+      .filter(|(grantee, _)| grantee == caller || grantee == "*")
+      .any(|(_, rule)| matches(rule));
+    if !self.grants.is_empty() && !granted {
+      return Err(Denial::NotGranted);
+    }
+    Ok(())
+  }
+}
+
+impl FromStr for Policy {
+  type Err = PolicyError;
+
+  /// One rule per line: `allow <glob>`, `deny <glob>` or
+  /// `grant <caller> <glob>`; `#` starts a comment.
+  fn from_str(text: &str) -> Result<Policy, PolicyError> {
+    let mut policy = Policy::new();
+    for (index, line) in text.lines().enumerate() {
+      let line = line.split('#').next().unwrap_or("").trim();
+      let words: Vec<&str> = line.split_whitespace().collect();
+      let syntax = |reason: &str| PolicyError::Syntax { line: index + 1, reason: String::from(reason) };
+      policy = match words.as_slice() {
+        [] => policy,
+        ["allow", pattern] => policy.allow(pattern),
+        ["deny", pattern] => policy.deny(pattern),
+        ["grant", caller, pattern] => policy.grant(caller, pattern),
+        ["allow", ..] | ["deny", ..] => return Err(syntax("expected one pattern")),
+        ["grant", ..] => return Err(syntax("expected a caller and a pattern")),
+        [rule, ..] => return Err(syntax(&format!("unknown rule `{}`", rule))),
+      };
+    }
+    Ok(policy)
+  }
+}
+
+/// Removes the `.` and `..` components, without touching the disk; `None`
+/// when a `..` has nothing left to remove.
+fn normalize(path: &str) -> Option<PathBuf> {
+  let mut normalized = PathBuf::new();
+  for component in Path::new(path).components() {
+    match component {
+      Component::CurDir => {}
+      Component::ParentDir => {
+        if !normalized.pop() {
+          return None;
+        }
+      }
+      other => normalized.push(other.as_os_str()),
+    }
+  }
+  Some(normalized)
+}
+
+/// `path` as the rules may spell it: absolute, and relative to `current`
+/// when inside it.
+fn forms(path: &Path, current: &Path) -> Vec<String> {
+  let absolute = current.join(path);
+  let mut forms = vec![absolute.to_string_lossy().into_owned()];
+  if let Ok(relative) = absolute.strip_prefix(current) {
+    forms.push(relative.to_string_lossy().into_owned());
+  }
+  forms
+}
+
+/// Reads through `inner` only what the policy lets `caller` read.
+pub struct ProtectionProxy<R> {
+  caller: String,
+  policy: Arc<Policy>,
+  inner: R,
+}
+
+impl<R: FileReader> ProtectionProxy<R> {
+  pub fn new(caller: &str, policy: Arc<Policy>, inner: R) -> ProtectionProxy<R> {
+    ProtectionProxy { caller: String::from(caller), policy, inner }
+  }
+
+  pub fn caller(&self) -> &str {
+    &self.caller
+  }
+
+  pub fn into_inner(self) -> R {
+    self.inner
+  }
+}
+
+impl<R: FileReader> FileReader for ProtectionProxy<R> {
+  fn file_name(&self) -> &str {
+    self.inner.file_name()
+  }
+
+  fn read_file_contents(&mut self) -> Result<String, ReadError> {
+    match self.policy.check(&self.caller, self.inner.file_name()) {
+      Ok(()) => self.inner.read_file_contents(),
+      Err(reason) => Err(ReadError::Denied {
+        path: String::from(self.inner.file_name()),
+        caller: self.caller.clone(),
+        reason,
+      }),
+    }
+  }
+}
@@ -0,0 +1,629 @@
+//! `remote` module.
+//!
+//! `RemoteFileReader` stands for a file served by a `FileServer`, another
+//! process on the same machine, over a TCP socket on the loopback interface
+//! or a Unix socket. The server exposes one directory and reads it through
+//! a `ProxyCache`, so every client shares its cached contents.
+//!
+//! Each message is framed by its length, 4 bytes big-endian. A request is
+//! the byte `R` followed by a path relative to the served directory; the
+//! response is a status byte followed by the contents or an error message.
+//! A path must stay inside the served directory, symbolic links included,
+//! and a file must fit in a frame of 64 MiB.
+//! A client keeps its connection open between reads; when it breaks, the
+//! client connects again, a few times, before reporting the error. The
+//! server closes connections idle for too long or stalling in the middle
+//! of a frame, and those over its connection cap; see `Limits`.
+//!
+//! # Example
+//!
+//! ```rust
+//! use std::time::Duration;
+//! use structural::proxy::*;
+//! use structural::proxy::remote::*;
+//!
+//! let root = std::env::temp_dir().join(format!("remote-{}", std::process::id()));
+//! std::fs::create_dir_all(root.join("docs")).unwrap();
+//! std::fs::write(root.join("docs/readme.txt"), "Shared contents").unwrap();
+//!
+//! let server = FileServer::bind(&Endpoint::tcp_localhost(), &root).unwrap();
+//! let mut reader = RemoteFileReader::new(server.endpoint(), "docs/readme.txt").timeout(Duration::from_secs(2));
+//! assert_eq!(reader.read_file_contents().unwrap(), "Shared contents");
+//! let mut other = RemoteFileReader::new(server.endpoint(), "docs/readme.txt");
+//! assert_eq!(other.read_file_contents().unwrap(), "Shared contents");
+//! assert_eq!(server.cache().stats().hits, 1);
+//!
+//! // The server lets go of a connection once its client leaves.
+//! assert_eq!(server.connections(), 2);
+//! drop((reader, other));
+//! for _ in 0..100 {
+//!   if server.connections() == 0 {
+//!     break;
+//!   }
+//!   std::thread::sleep(Duration::from_millis(10));
+//! }
+//! assert_eq!(server.connections(), 0);
+//!
+//! match RemoteFileReader::new(server.endpoint(), "../etc/passwd").read_file_contents() {
+//!   Err(ReadError::Io { error, .. }) => assert_eq!(error.kind(), std::io::ErrorKind::PermissionDenied),
+//!   other => panic!("unexpected {:?}", other),
+//! }
+//! match RemoteFileReader::new(server.endpoint(), "missing.txt").read_file_contents() {
+//!   Err(ReadError::Io { error, .. }) => assert_eq!(error.kind(), std::io::ErrorKind::NotFound),
+//!   other => panic!("unexpected {:?}", other),
+//! }
+//!
+//! // Too large for a frame: the server says so instead of hanging up.
+//! std::fs::File::create(root.join("huge.bin")).unwrap().set_len(64 * 1024 * 1024).unwrap();
+//! let before = server.requests();
+//! match RemoteFileReader::new(server.endpoint(), "huge.bin").read_file_contents() {
+//!   Err(ReadError::Io { error, .. }) => assert!(error.to_string().starts_with("file too large")),
+//!   other => panic!("unexpected {:?}", other),
+//! }
+//! assert_eq!(server.requests(), before + 1);
+//!
+//! // No server: the connection is refused after the retries.
+//! let endpoint = server.endpoint();
+//! drop(server);
+//! let mut orphan = RemoteFileReader::new(endpoint, "docs/readme.txt").reconnects(1, Duration::from_millis(1));
+//! assert!(orphan.read_file_contents().is_err());
+//! std::fs::remove_dir_all(&root).unwrap();
+//! ```
+//!
+//! With tighter limits:
+//!
+//! ```rust
+//! use std::io::{Read, Write};
+//! use std::time::Duration;
+//! use structural::proxy::*;
+//! use structural::proxy::cache::ProxyCache;
+//! use structural::proxy::remote::*;
+//!
+//! let root = std::env::temp_dir().join(format!("remote-limits-{}", std::process::id()));
+//! std::fs::create_dir_all(&root).unwrap();
+//! std::fs::write(root.join("a.txt"), "A").unwrap();
+//! let limits = Limits::default()
+//!   .idle_timeout(Duration::from_millis(200))
+//!   .frame_timeout(Duration::from_millis(100))
+//!   .max_connections(1);
+//! let server = FileServer::with_limits(&Endpoint::tcp_localhost(), &root, ProxyCache::unbounded(), limits).unwrap();
+//! let wait_for = |connections: usize| {
+//!   for _ in 0..200 {
+//!     if server.connections() == connections {
+//!       break;
+//!     }
+//!     std::thread::sleep(Duration::from_millis(10));
+//!   }
+//!   assert_eq!(server.connections(), connections);
+//! };
+//!
+//! // Over the cap, a connection is closed at once.
+//! let mut reader = RemoteFileReader::new(server.endpoint(), "a.txt");
+//! assert_eq!(reader.read_file_contents().unwrap(), "A");
+//! let mut other = RemoteFileReader::new(server.endpoint(), "a.txt").reconnects(0, Duration::from_millis(1));
+//! assert!(other.read_file_contents().is_err());
+//!
+//! // An idle client is let go, and connects again on its next read.
+//! wait_for(0);
+//! assert_eq!(other.read_file_contents().unwrap(), "A");
+//! drop(other);
+//! wait_for(0);
+//! assert_eq!(reader.read_file_contents().unwrap(), "A");
+//!
+//! // So is a client stalling in the middle of a request.
+//! wait_for(0);
+//! let address = match server.endpoint() {
+//!   Endpoint::Tcp(address) => address,
+//!   _ => unreachable!(),
+//! };
+//! let mut stalled = std::net::TcpStream::connect(address).unwrap();
+//! stalled.set_read_timeout(Some(Duration::from_secs(5))).unwrap();
+//! stalled.write_all(&[0, 0, 0, 10, b'R']).unwrap();
+//! assert_eq!(stalled.read(&mut [0u8; 16]).unwrap(), 0);
+//! drop(server);
+//! std::fs::remove_dir_all(&root).unwrap();
+//! ```
+//!
+//! Over a Unix socket, reconnecting to a restarted server:
+//!
+//! ```rust
+//! # #[cfg(unix)]
+//! # {
+//! use structural::proxy::*;
+//! use structural::proxy::remote::*;
+//!
+//! let root = std::env::temp_dir().join(format!("remote-unix-{}", std::process::id()));
+//! std::fs::create_dir_all(&root).unwrap();
+//! std::fs::write(root.join("a.txt"), "A").unwrap();
+//! let endpoint = Endpoint::Unix(root.join("server.sock"));
+//!
+//! let server = FileServer::bind(&endpoint, &root).unwrap();
+//! let mut reader = RemoteFileReader::new(endpoint.clone(), "a.txt");
+//! assert_eq!(reader.read_file_contents().unwrap(), "A");
+//! drop(server);
+//!
+//! std::fs::write(root.join("a.txt"), "AA").unwrap();
+//! let server = FileServer::bind(&endpoint, &root).unwrap();
+//! assert_eq!(reader.read_file_contents().unwrap(), "AA");
+//! assert_eq!(server.requests(), 1);
+//!
+//! // Symbolic links leading out of the directory are not followed.
+//! let outside = std::env::temp_dir().join(format!("remote-outside-{}", std::process::id()));
+//! std::fs::write(&outside, "secret").unwrap();
+//! std::os::unix::fs::symlink(&outside, root.join("link")).unwrap();
+//! match RemoteFileReader::new(endpoint.clone(), "link").read_file_contents() {
+//!   Err(ReadError::Io { error, .. }) => assert_eq!(error.kind(), std::io::ErrorKind::PermissionDenied),
+//!   other => panic!("unexpected {:?}", other),
+//! }
+//! std::fs::remove_file(&outside).unwrap();
+//! drop(server);
+//! std::fs::remove_dir_all(&root).unwrap();
+//! # }
+//! ```
+
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::io::{Read, Write};
+use std::net::{Shutdown, SocketAddr, TcpListener, TcpStream};
+#[cfg(unix)]
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::{Component, Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::thread;
+use std::time::Duration;
+
+use proxy::cache::ProxyCache;
+use proxy::{FileReader, FileReaderProxy, ReadError};
+
+/// Largest frame accepted, to bound what a peer can make us allocate.
+const MAX_FRAME: usize = 64 * 1024 * 1024;
+
+const READ: u8 = b'R';
+const OK: u8 = 0;
+const NOT_FOUND: u8 = 1;
+const FORBIDDEN: u8 = 2;
+const FAILED: u8 = 3;
+
+/// Bounds on the clients of a `FileServer`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Limits {
+  idle_timeout: Duration,
+  frame_timeout: Duration,
+  max_connections: usize,
+}
+
+impl Default for Limits {
+  /// One minute idle, ten seconds per frame and 64 connections.
+  fn default() -> Limits {
+    Limits {
+      idle_timeout: Duration::from_secs(60),
+      frame_timeout: Duration::from_secs(10),
+      max_connections: 64,
+    }
+  }
+}
+
+impl Limits {
+  /// Time a client may wait between requests.
+  pub fn idle_timeout(mut self, timeout: Duration) -> Limits {
+    self.idle_timeout = timeout;
+    self
+  }
+
+  /// Time allowed to receive the rest of a request once it has started,
+  /// and to send the response.
+  pub fn frame_timeout(mut self, timeout: Duration) -> Limits {
+    self.frame_timeout = timeout;
+    self
+  }
+
+  /// Connections served at once; the ones above are closed when accepted.
+  pub fn max_connections(mut self, connections: usize) -> Limits {
+    self.max_connections = connections;
+    self
+  }
+}
+
+/// Where a `FileServer` listens.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Endpoint {
+  /// A loopback address; port 0 picks a free one.
+  Tcp(SocketAddr),
+  #[cfg(unix)]
+  Unix(PathBuf),
+}
+
+impl Endpoint {
+  /// A free port on `127.0.0.1`.
+  pub fn tcp_localhost() -> Endpoint {
+    Endpoint::Tcp(SocketAddr::from(([127, 0, 0, 1], 0)))
+  }
+}
+
+enum Stream {
+  Tcp(TcpStream),
+  #[cfg(unix)]
+  Unix(UnixStream),
+}
+
+impl Stream {
+  fn connect(endpoint: &Endpoint, timeout: Duration) -> io::Result<Stream> {
+    let stream = match *endpoint {
+      Endpoint::Tcp(ref address) => Stream::Tcp(TcpStream::connect_timeout(address, timeout)?),
+      #[cfg(unix)]
+      Endpoint::Unix(ref path) => Stream::Unix(UnixStream::connect(path)?),
+    };
+    stream.set_timeout(Some(timeout))?;
+    Ok(stream)
+  }
+
+  fn set_timeout(&self, timeout: Option<Duration>) -> io::Result<()> {
+    match *self {
+      Stream::Tcp(ref s) => s.set_read_timeout(timeout).and_then(|_| s.set_write_timeout(timeout)),
+      #[cfg(unix)]
+      Stream::Unix(ref s) => s.set_read_timeout(timeout).and_then(|_| s.set_write_timeout(timeout)),
+    }
+  }
+
+  fn try_clone(&self) -> io::Result<Stream> {
+    match *self {
+      Stream::Tcp(ref s) => s.try_clone().map(Stream::Tcp),
+      #[cfg(unix)]
+      Stream::Unix(ref s) => s.try_clone().map(Stream::Unix),
+    }
+  }
+
+  fn shutdown(&self) {
+    let _ = match *self {
+      Stream::Tcp(ref s) => s.shutdown(Shutdown::Both),
+      #[cfg(unix)]
+      Stream::Unix(ref s) => s.shutdown(Shutdown::Both),
+    };
+  }
+}
+
+impl Read for Stream {
+  fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+    match *self {
+      Stream::Tcp(ref mut s) => s.read(buf),
+      #[cfg(unix)]
+      Stream::Unix(ref mut s) => s.read(buf),
+    }
+  }
+}
+
+impl Write for Stream {
+  fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+    match *self {
+      Stream::Tcp(ref mut s) => s.write(buf),
+      #[cfg(unix)]
+      Stream::Unix(ref mut s) => s.write(buf),
+    }
+  }
+
+  fn flush(&mut self) -> io::Result<()> {
+    match *self {
+      Stream::Tcp(ref mut s) => s.flush(),
+      #[cfg(unix)]
+      Stream::Unix(ref mut s) => s.flush(),
+    }
+  }
+}
+
+fn write_frame<W: Write>(writer: &mut W, kind: u8, payload: &[u8]) -> io::Result<()> {
+  let length = payload.len() + 1;
+  if length > MAX_FRAME {
+    return Err(io::Error::new(io::ErrorKind::InvalidInput, "frame too large"));
+  }
+  let mut frame = Vec::with_capacity(length + 4);
+  frame.extend_from_slice(&(length as u32).to_be_bytes());
+  frame.push(kind);
+  frame.extend_from_slice(payload);
+  writer.write_all(&frame)?;
+  writer.flush()
+}
+
+/// Reads a frame; `None` when the peer closed the connection between frames.
+fn read_frame<R: Read>(reader: &mut R) -> io::Result<Option<(u8, Vec<u8>)>> {
+  match read_length(reader)? {
+    Some(length) => read_body(reader, length).map(Some),
+    None => Ok(None),
+  }
+}
+
+/// Reads the length of the next frame; `None` when the peer closed the
+/// connection instead.
+fn read_length<R: Read>(reader: &mut R) -> io::Result<Option<usize>> {
+  let mut length = [0u8; 4];
+  match reader.read_exact(&mut length) {
+    Err(ref e) if e.kind() == io::ErrorKind::UnexpectedEof => return Ok(None),
+    result => result?,
+  }
+  let length = u32::from_be_bytes(length) as usize;
+  if length == 0 || length > MAX_FRAME {
+    return Err(io::Error::new(io::ErrorKind::InvalidData, "invalid frame length"));
+  }
+  Ok(Some(length))
+}
+
+fn read_body<R: Read>(reader: &mut R, length: usize) -> io::Result<(u8, Vec<u8>)> {
+  let mut frame = vec![0u8; length];
+  reader.read_exact(&mut frame)?;
+  let payload = frame.split_off(1);
+  Ok((frame[0], payload))
+}
+
+enum Listener {
+  Tcp(TcpListener),
+  #[cfg(unix)]
+  Unix(UnixListener),
+}
+
+impl Listener {
+  fn accept(&self) -> io::Result<Stream> {
+    match *self {
+      Listener::Tcp(ref l) => l.accept().map(|(s, _)| Stream::Tcp(s)),
+      #[cfg(unix)]
+      Listener::Unix(ref l) => l.accept().map(|(s, _)| Stream::Unix(s)),
+    }
+  }
+}
+
+/// Serves the files of a directory until it is dropped.
+pub struct FileServer {
+  endpoint: Endpoint,
+  cache: ProxyCache,
+  requests: Arc<AtomicUsize>,
+  stop: Arc<AtomicBool>,
+  /// A handle on each open connection, to close it when dropped.
+  connections: Arc<Mutex<HashMap<usize, Stream>>>,
+  worker: Option<thread::JoinHandle<()>>,
+}
+
+impl FileServer {
+  /// Serves `root` with a cache of its own.
+  pub fn bind<P: AsRef<Path>>(endpoint: &Endpoint, root: P) -> io::Result<FileServer> {
+    FileServer::with_cache(endpoint, root, ProxyCache::unbounded())
+  }
+
+  /// Serves `root` on `endpoint` with the default `Limits`.
+  pub fn with_cache<P: AsRef<Path>>(endpoint: &Endpoint, root: P, cache: ProxyCache) -> io::Result<FileServer> {
+    FileServer::with_limits(endpoint, root, cache, Limits::default())
+  }
+
+  /// Serves `root` on `endpoint`; a TCP endpoint must be a loopback
+  /// address.
+  pub fn with_limits<P: AsRef<Path>>(endpoint: &Endpoint, root: P, cache: ProxyCache, limits: Limits) -> io::Result<FileServer> {
+    let (listener, endpoint) = match *endpoint {
+      Endpoint::Tcp(ref address) => {
+        if !address.ip().is_loopback() {
+          return Err(io::Error::new(io::ErrorKind::InvalidInput, "only loopback addresses are served"));
+        }
+        let listener = TcpListener::bind(address)?;
+        let address = listener.local_addr()?;
+        (Listener::Tcp(listener), Endpoint::Tcp(address))
+      }
+      #[cfg(unix)]
+      Endpoint::Unix(ref path) => (Listener::Unix(UnixListener::bind(path)?), Endpoint::Unix(path.clone())),
+    };
+    // Resolved once, to tell where the symbolic links below it lead.
+    let root = fs::canonicalize(root)?;
+    let requests = Arc::new(AtomicUsize::new(0));
+    let stop = Arc::new(AtomicBool::new(false));
+    let connections = Arc::new(Mutex::new(HashMap::new()));
+    let worker = {
+      let (cache, requests, stop, connections) = (cache.clone(), requests.clone(), stop.clone(), connections.clone());
+      thread::spawn(move || {
+        for id in 0.. {
+          let stream = listener.accept();
+          if stop.load(Ordering::SeqCst) {
+            break;
+          }
+          if let Ok(stream) = stream {
+            let mut open = lock(&connections);
+            if open.len() >= limits.max_connections {
+              stream.shutdown();
+              continue;
+            }
+            if let Ok(clone) = stream.try_clone() {
+              open.insert(id, clone);
+            }
+            drop(open);
+            let (root, cache, requests, connections) = (root.clone(), cache.clone(), requests.clone(), connections.clone());
+            thread::spawn(move || {
+              serve(stream, &root, &cache, &requests, limits);
+              lock(&connections).remove(&id);
+            });
+          }
+        }
+      })
+    };
+    Ok(FileServer { endpoint, cache, requests, stop, connections, worker: Some(worker) })
+  }
+
+  /// Where clients connect, with the port picked for a TCP port 0.
+  pub fn endpoint(&self) -> Endpoint {
+    self.endpoint.clone()
+  }
+
+  pub fn cache(&self) -> &ProxyCache {
+    &self.cache
+  }
+
+  /// Number of requests answered so far.
+  pub fn requests(&self) -> usize {
+    self.requests.load(Ordering::SeqCst)
+  }
+
+  /// Number of clients connected.
+  pub fn connections(&self) -> usize {
+    lock(&self.connections).len()
+  }
+}
+
+fn lock(connections: &Mutex<HashMap<usize, Stream>>) -> MutexGuard<'_, HashMap<usize, Stream>> {
+  connections.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+impl Drop for FileServer {
+  fn drop(&mut self) {
+    self.stop.store(true, Ordering::SeqCst);
+    // Wakes the worker blocked on `accept`.
+    let _ = Stream::connect(&self.endpoint, Duration::from_secs(1));
+    if let Some(worker) = self.worker.take() {
+      let _ = worker.join();
+    }
+    for (_, connection) in lock(&self.connections).drain() {
+      connection.shutdown();
+    }
+    #[cfg(unix)]
+    {
+      if let Endpoint::Unix(ref path) = self.endpoint {
+        let _ = std::fs::remove_file(path);
+      }
+    }
+  }
+}
+
+/// Answers the requests of one client until it leaves, stays idle too
+/// long or stalls in the middle of a frame.
+fn serve(mut stream: Stream, root: &Path, cache: &ProxyCache, requests: &AtomicUsize, limits: Limits) {
+  loop {
+    if stream.set_timeout(Some(limits.idle_timeout)).is_err() {
+      break;
+    }
+    let length = match read_length(&mut stream) {
+      Ok(Some(length)) => length,
+      _ => break,
+    };
+    if stream.set_timeout(Some(limits.frame_timeout)).is_err() {
+      break;
+    }
+    let (kind, payload) = match read_body(&mut stream, length) {
+      Ok(frame) => frame,
+      Err(_) => break,
+    };
+    let (status, body) = if kind != READ {
+      (FAILED, b"unknown request".to_vec())
+    } else {
+      answer(root, &String::from_utf8_lossy(&payload), cache)
+    };
+    requests.fetch_add(1, Ordering::SeqCst);
+    if write_frame(&mut stream, status, &body).is_err() {
+      break;
+    }
+  }
+}
+
+fn answer(root: &Path, relative: &str, cache: &ProxyCache) -> (u8, Vec<u8>) {
+  let outside = || (FORBIDDEN, b"only paths inside the served directory are allowed".to_vec());
+  let relative = Path::new(relative);
+  if !relative.components().all(|c| matches!(c, Component::Normal(_) | Component::CurDir)) {
+    return outside();
+  }
+  // A symbolic link may lead out of the directory.
+  let path = match fs::canonicalize(root.join(relative)) {
+    Ok(path) if path.starts_with(root) => path,
+    Ok(_) => return outside(),
+    Err(ref error) if error.kind() == io::ErrorKind::NotFound => return (NOT_FOUND, error.to_string().into_bytes()),
+    Err(error) => return (FAILED, error.to_string().into_bytes()),
+  };
+  // The contents and the status byte must fit in a frame.
+  let too_large = || (FAILED, format!("file too large, over {} bytes", MAX_FRAME - 1).into_bytes());
+  if fs::metadata(&path).map(|m| m.len() >= MAX_FRAME as u64).unwrap_or(false) {
+    return too_large();
+  }
+  let mut reader = FileReaderProxy::with_cache(path.to_string_lossy().into_owned(), cache.clone());
+  match reader.read_file_contents() {
+    Ok(ref contents) if contents.len() >= MAX_FRAME => too_large(),
+    Ok(contents) => (OK, contents.into_bytes()),
+    Err(ReadError::Io { ref error, .. }) if error.kind() == io::ErrorKind::NotFound => {
+      (NOT_FOUND, error.to_string().into_bytes())
+    }
+    Err(error) => (FAILED, error.to_string().into_bytes()),
+  }
+}
+
+/// Reads a file of a `FileServer`.
+pub struct RemoteFileReader {
+  endpoint: Endpoint,
+  file_name: String,
+  timeout: Duration,
+  reconnects: u32,
+  backoff: Duration,
+  stream: Option<Stream>,
+}
+
+impl RemoteFileReader {
+  /// Reads `file_name`, relative to the served directory.
+  pub fn new(endpoint: Endpoint, file_name: &str) -> RemoteFileReader {
+    RemoteFileReader {
+      endpoint,
+      file_name: String::from(file_name),
+      timeout: Duration::from_secs(10),
+      reconnects: 3,
+      backoff: Duration::from_millis(50),
+      stream: None,
+    }
+  }
+
+  /// Time allowed to connect, send or receive.
+  pub fn timeout(mut self, timeout: Duration) -> RemoteFileReader {
+    self.timeout = timeout;
+    self
+  }
+
+  /// Connects again up to `times` times when a read fails on the way,
+  /// waiting `backoff` before each attempt.
+  pub fn reconnects(mut self, times: u32, backoff: Duration) -> RemoteFileReader {
+    self.reconnects = times;
+    self.backoff = backoff;
+    self
+  }
+
+  fn exchange(&mut self) -> io::Result<(u8, Vec<u8>)> {
+    if self.stream.is_none() {
+      self.stream = Some(Stream::connect(&self.endpoint, self.timeout)?);
+    }
+    let stream = self.stream.as_mut().expect("connected above");
+    write_frame(stream, READ, self.file_name.as_bytes())?;
+    read_frame(stream)?.ok_or_else(|| io::Error::new(io::ErrorKind::UnexpectedEof, "connection closed by the server"))
+  }
+}
+
+impl FileReader for RemoteFileReader {
+  fn file_name(&self) -> &str {
+    &self.file_name
+  }
+
+  fn read_file_contents(&mut self) -> Result<String, ReadError> {
+    let mut attempt = 0;
+    let (status, body) = loop {
+      match self.exchange() {
+        Ok(response) => break response,
+        Err(error) => {
+          // The connection is in an unknown state: start over.
+          self.stream = None;
+          if attempt >= self.reconnects {
+            return Err(ReadError::io(&self.file_name, error));
+          }
+          attempt += 1;
+          thread::sleep(self.backoff);
+        }
+      }
+    };
+    let kind = match status {
+      OK => {
+        return String::from_utf8(body)
+          .map_err(|e| ReadError::io(&self.file_name, io::Error::new(io::ErrorKind::InvalidData, e)));
+      }
+      NOT_FOUND => io::ErrorKind::NotFound,
+      FORBIDDEN => io::ErrorKind::PermissionDenied,
+      _ => io::ErrorKind::Other,
+    };
+    let message = String::from_utf8_lossy(&body).into_owned();
+    Err(ReadError::io(&self.file_name, io::Error::new(kind, message)))
+  }
+}